@@ -0,0 +1,433 @@
+#!/usr/bin/env python3
+"""Generates the KQvK and KRvK syzygy tables used by the tests of the syzygy prober.
+
+The positions are solved by retrograde analysis and written in the syzygy file format:
+canonical huffman codes with pair symbols, blocks located by the sparse index and the block lengths,
+and a DTZ map sorted by frequency. The DTZ tables store white to move in plies, which equals the
+distance to mate as these endgames have no zeroing moves for the winning side.
+
+Usage: python3 generate.py [output directory]
+"""
+
+import os
+import sys
+from collections import Counter
+
+WDL_MAGIC = bytes([0x71, 0xE8, 0x23, 0x5D])
+DTZ_MAGIC = bytes([0xD7, 0x66, 0x0C, 0xA5])
+
+FLAG_MAPPED = 2
+FLAG_WIN_PLIES = 4
+FLAG_LOSS_PLIES = 8
+FLAG_SINGLE_VALUE = 128
+
+KING, QUEEN, ROOK = 6, 5, 4
+BLACK = 8
+
+BLOCK_SIZE_LOG2 = 5
+SPAN_LOG2 = 6
+MAX_PAIRS = 200
+UNIQUE_PIECES_SIZE = 31332
+
+KING_STEPS = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+ROOK_DIRECTIONS = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+QUEEN_DIRECTIONS = KING_STEPS
+
+
+def king_squares(sq):
+    rank, file = divmod(sq, 8)
+    for dr, df in KING_STEPS:
+        if 0 <= rank + dr < 8 and 0 <= file + df < 8:
+            yield (rank + dr) * 8 + file + df
+
+
+KING_ATTACKS = [set(king_squares(sq)) for sq in range(64)]
+
+
+def slides(sq, directions, occupied):
+    """Squares reached by a slider, including the first blocker"""
+    rank, file = divmod(sq, 8)
+    for dr, df in directions:
+        r, f = rank + dr, file + df
+        while 0 <= r < 8 and 0 <= f < 8:
+            yield r * 8 + f
+            if r * 8 + f in occupied:
+                break
+            r, f = r + dr, f + df
+
+
+class Endgame:
+    """King and a slider against the bare king, positions are (white king, slider, black king, side to move)"""
+
+    def __init__(self, slider):
+        self.slider = slider
+        self.directions = QUEEN_DIRECTIONS if slider == QUEEN else ROOK_DIRECTIONS
+
+    def attacked(self, wk, x, sq, occupied):
+        return sq in KING_ATTACKS[wk] or (x is not None and sq in slides(x, self.directions, occupied))
+
+    def legal(self, wk, x, bk, stm):
+        if len({wk, x, bk}) != 3 or bk in KING_ATTACKS[wk]:
+            return False
+        #white to move must not have the black king in check
+        return stm == 1 or bk not in set(slides(x, self.directions, {wk, bk}))
+
+    def black_moves(self, wk, x, bk):
+        """Moves of the black king, None marks the capture of the slider"""
+        moves = []
+        for sq in KING_ATTACKS[bk]:
+            if sq == wk:
+                continue
+            if sq == x:
+                if x not in KING_ATTACKS[wk]:
+                    moves.append(None)
+                continue
+            if not self.attacked(wk, x, sq, {wk}):
+                moves.append(sq)
+        return moves
+
+    def in_check(self, wk, x, bk):
+        return bk in set(slides(x, self.directions, {wk, bk}))
+
+    def solve(self):
+        """Distance to mate in plies of the legal positions, positive for wins of the side to move"""
+        dtm = {}
+        remaining = {}
+        frontier = []
+        for wk in range(64):
+            for x in range(64):
+                for bk in range(64):
+                    if not self.legal(wk, x, bk, 1):
+                        continue
+                    moves = self.black_moves(wk, x, bk)
+                    remaining[(wk, x, bk)] = len(moves)
+                    if not moves and self.in_check(wk, x, bk):
+                        dtm[(wk, x, bk, 1)] = 0
+                        frontier.append((wk, x, bk))
+
+        plies = 0
+        while frontier:
+            #white to move positions which mate or reach the lost positions of the frontier
+            wins = []
+            for wk, x, bk in frontier:
+                for s in KING_ATTACKS[wk]:
+                    if s not in (x, bk) and self.legal(s, x, bk, 0) and (s, x, bk, 0) not in dtm:
+                        dtm[(s, x, bk, 0)] = plies + 1
+                        wins.append((s, x, bk))
+                for s in slides(x, self.directions, {wk, bk}):
+                    if s not in (wk, bk) and self.legal(wk, s, bk, 0) and (wk, s, bk, 0) not in dtm:
+                        dtm[(wk, s, bk, 0)] = plies + 1
+                        wins.append((wk, s, bk))
+
+            #black to move positions where every move reaches a win of white
+            frontier = []
+            for wk, x, bk in wins:
+                for s in KING_ATTACKS[bk]:
+                    if s in (wk, x) or not self.legal(wk, x, s, 1) or (wk, x, s, 1) in dtm:
+                        continue
+                    if bk not in self.black_moves(wk, x, s):
+                        continue
+                    remaining[(wk, x, s)] -= 1
+                    if remaining[(wk, x, s)] == 0:
+                        dtm[(wk, x, s, 1)] = -(plies + 2)
+                        frontier.append((wk, x, s))
+            plies += 2
+        return dtm
+
+
+def off_a1h8(sq):
+    return sq // 8 - sq % 8
+
+
+MAP_B1H1H7 = {}
+for _sq in range(64):
+    if off_a1h8(_sq) < 0:
+        MAP_B1H1H7[_sq] = len(MAP_B1H1H7)
+MAP_A1D1D4 = {}
+for _sq in [s for s in range(28) if s % 8 <= 3 and off_a1h8(s) < 0] + [0, 9, 18, 27]:
+    MAP_A1D1D4[_sq] = len(MAP_A1D1D4)
+
+
+def encode(squares):
+    """Index of the leading group of 3 unique pieces, the squares are in the order of the table pieces"""
+    squares = list(squares)
+    if squares[0] % 8 > 3:
+        squares = [sq ^ 7 for sq in squares]
+    if squares[0] // 8 > 3:
+        squares = [sq ^ 56 for sq in squares]
+    for i in range(3):
+        if off_a1h8(squares[i]) == 0:
+            continue
+        if off_a1h8(squares[i]) > 0:
+            squares = squares[:i] + [((sq >> 3) | (sq << 3)) & 63 for sq in squares[i:]]
+        break
+
+    s0, s1, s2 = squares
+    adjust1 = int(s1 > s0)
+    adjust2 = int(s2 > s0) + int(s2 > s1)
+    if off_a1h8(s0):
+        return (MAP_A1D1D4[s0] * 63 + s1 - adjust1) * 62 + s2 - adjust2
+    if off_a1h8(s1):
+        return (6 * 63 + s0 // 8 * 28 + MAP_B1H1H7[s1]) * 62 + s2 - adjust2
+    if off_a1h8(s2):
+        return 6 * 63 * 62 + 4 * 28 * 62 + s0 // 8 * 7 * 28 + (s1 // 8 - adjust1) * 28 + MAP_B1H1H7[s2]
+    return (6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28
+            + s0 // 8 * 7 * 6 + (s1 // 8 - adjust1) * 6 + s2 // 8 - adjust2)
+
+
+def table_values(values, order):
+    """Values of the table indices, the order lists the pieces of the (white king, slider, black king) positions"""
+    table = [None] * UNIQUE_PIECES_SIZE
+    for squares, value in values.items():
+        idx = encode([squares[piece] for piece in order])
+        assert table[idx] in (None, value), "symmetric positions must have the same value"
+        table[idx] = value
+
+    #indices of illegal positions are don't care, repeating the previous value compresses best
+    first = next(value for value in table if value is not None)
+    previous = first
+    for idx, value in enumerate(table):
+        if value is None:
+            table[idx] = previous
+        previous = table[idx]
+    return table
+
+
+class Compressed:
+    """Sub table compressed with pair symbols and a canonical huffman code"""
+
+    def __init__(self, values):
+        distinct = sorted(set(values))
+        #symbols are (left, right) pairs or leaves storing the value
+        symbols = [("leaf", value) for value in distinct]
+        lengths = [1] * len(distinct)
+        leaf = {value: sym for sym, value in enumerate(distinct)}
+        sequence = [leaf[value] for value in values]
+
+        for _ in range(MAX_PAIRS):
+            pairs = Counter(zip(sequence, sequence[1:]))
+            candidates = [(count, pair) for pair, count in pairs.items()
+                          if lengths[pair[0]] + lengths[pair[1]] <= 256]
+            if not candidates:
+                break
+            count, (left, right) = max(candidates)
+            if count < 8:
+                break
+            new = len(symbols)
+            symbols.append((left, right))
+            lengths.append(lengths[left] + lengths[right])
+            replaced = []
+            i = 0
+            while i < len(sequence):
+                if i + 1 < len(sequence) and sequence[i] == left and sequence[i + 1] == right:
+                    replaced.append(new)
+                    i += 2
+                else:
+                    replaced.append(sequence[i])
+                    i += 1
+            sequence = replaced
+
+        code_lengths = huffman_lengths(Counter(sequence))
+        assert len(code_lengths) > 1, "the huffman code needs at least 2 symbols"
+
+        #longer codes have lower symbols, symbols without code are numbered last
+        coded = sorted(code_lengths, key=lambda sym: (-code_lengths[sym], sym))
+        uncoded = [sym for sym in range(len(symbols)) if sym not in code_lengths]
+        number = {sym: n for n, sym in enumerate(coded + uncoded)}
+        self.symbols = [None] * len(symbols)
+        for sym, content in enumerate(symbols):
+            if content[0] == "leaf":
+                self.symbols[number[sym]] = content
+            else:
+                self.symbols[number[sym]] = (number[content[0]], number[content[1]])
+        self.lengths = [0] * len(symbols)
+        for sym, length in enumerate(lengths):
+            self.lengths[number[sym]] = length
+
+        self.min_len = min(code_lengths.values())
+        self.max_len = max(code_lengths.values())
+        counts = Counter(code_lengths.values())
+        count = [counts.get(length, 0) for length in range(self.min_len, self.max_len + 1)]
+        levels = len(count)
+        self.lowest_sym = [sum(count[i + 1:]) for i in range(levels)]
+        base = [0] * levels
+        for i in reversed(range(levels - 1)):
+            assert (base[i + 1] + count[i + 1]) % 2 == 0
+            base[i] = (base[i + 1] + count[i + 1]) // 2
+        assert base[0] + count[0] == 1 << self.min_len, "the huffman code must be complete"
+
+        self.codes = {}
+        for sym in range(len(coded)):
+            level = code_lengths[coded[sym]] - self.min_len
+            self.codes[sym] = (base[level] + sym - self.lowest_sym[level], level + self.min_len)
+        self.sequence = [number[sym] for sym in sequence]
+        self.size = len(values)
+        self.pack_blocks()
+
+    def pack_blocks(self):
+        block_bits = 8 << BLOCK_SIZE_LOG2
+        self.blocks = []
+        self.block_values = []
+        bits, values, used = [], 0, 0
+        for sym in self.sequence:
+            code, length = self.codes[sym]
+            if used + length > block_bits:
+                self.blocks.append(bits)
+                self.block_values.append(values)
+                bits, values, used = [], 0, 0
+            bits.append((code, length))
+            values += self.lengths[sym]
+            used += length
+        self.blocks.append(bits)
+        self.block_values.append(values)
+
+    def sizes(self, flags):
+        out = bytearray([flags, BLOCK_SIZE_LOG2, SPAN_LOG2, 0])
+        out += len(self.blocks).to_bytes(4, "little")
+        out += bytes([self.max_len, self.min_len])
+        for lowest in self.lowest_sym:
+            out += lowest.to_bytes(2, "little")
+        out += len(self.symbols).to_bytes(2, "little")
+        for sym, content in enumerate(self.symbols):
+            if content[0] == "leaf":
+                left, right = content[1], 0xFFF
+            else:
+                left, right = content
+            out += bytes([left & 0xFF, (left >> 8) | (right & 0xF) << 4, right >> 4])
+        if len(self.symbols) % 2:
+            out.append(0)
+        return out
+
+    def sparse_index(self):
+        starts = [0]
+        for values in self.block_values:
+            starts.append(starts[-1] + values)
+        span = 1 << SPAN_LOG2
+        out = bytearray()
+        block = 0
+        for k in range(-(-self.size // span)):
+            idx = k * span + span // 2
+            while block + 1 < len(self.blocks) and starts[block + 1] <= idx:
+                block += 1
+            out += block.to_bytes(4, "little") + (idx - starts[block]).to_bytes(2, "little")
+        return out
+
+    def block_lengths(self):
+        return b"".join((values - 1).to_bytes(2, "little") for values in self.block_values)
+
+    def data(self):
+        out = bytearray()
+        for bits in self.blocks:
+            value, length = 0, 0
+            for code, code_length in bits:
+                value = value << code_length | code
+                length += code_length
+            block_bits = 8 << BLOCK_SIZE_LOG2
+            out += (value << (block_bits - length)).to_bytes(block_bits // 8, "big")
+        return out
+
+
+def huffman_lengths(frequencies):
+    """Code length of every symbol"""
+    nodes = [(count, [sym]) for sym, count in frequencies.items()]
+    lengths = {sym: 0 for sym in frequencies}
+    while len(nodes) > 1:
+        nodes.sort(key=lambda node: node[0])
+        (c1, s1), (c2, s2) = nodes[0], nodes[1]
+        for sym in s1 + s2:
+            lengths[sym] += 1
+        nodes = nodes[2:] + [(c1 + c2, s1 + s2)]
+    return lengths
+
+
+def align(out, alignment):
+    out += bytes(-len(out) % alignment)
+
+
+def write_table(path, magic, orders, sides, flags, dtz_map=None):
+    """Writes the sub tables of the sides, each side is a list of values or a single value"""
+    out = bytearray(magic)
+    out.append(1)
+    out.append(0)
+    for pieces in zip(*orders):
+        out.append(pieces[0] | (pieces[1] << 4 if len(pieces) > 1 else 0))
+    align(out, 2)
+
+    for side, flag in zip(sides, flags):
+        if isinstance(side, int):
+            out += bytes([flag | FLAG_SINGLE_VALUE, side])
+        else:
+            out += side.sizes(flag)
+    if dtz_map is not None:
+        for values in dtz_map:
+            out.append(len(values))
+            out += bytes(values)
+        align(out, 2)
+
+    compressed = [side for side in sides if not isinstance(side, int)]
+    for side in compressed:
+        out += side.sparse_index()
+    for side in compressed:
+        out += side.block_lengths()
+    for side in compressed:
+        align(out, 64)
+        out += side.data()
+    align(out, 64)
+    out += bytes(16)
+
+    with open(path, "wb") as file:
+        file.write(out)
+
+
+def generate(directory, name, slider):
+    endgame = Endgame(slider)
+    dtm = endgame.solve()
+
+    #positions are keyed by the squares of the white king, the slider and the black king
+    wdl = {0: {}, 1: {}}
+    dtz = {}
+    for wk in range(64):
+        for x in range(64):
+            for bk in range(64):
+                for stm in (0, 1):
+                    if not endgame.legal(wk, x, bk, stm):
+                        continue
+                    result = dtm.get((wk, x, bk, stm))
+                    assert stm == 1 or result is not None, "white to move always wins"
+                    wdl[stm][(wk, x, bk)] = 2 if result is None else (4 if result > 0 else 0)
+                    if stm == 0:
+                        dtz[(wk, x, bk)] = result
+
+    #the pieces are stored in a different order for each side
+    pieces = [KING, slider, KING | BLACK]
+    orders = [[0, 1, 2], [1, 2, 0]]
+    piece_orders = [[pieces[i] for i in order] for order in orders]
+
+    assert set(table_values(wdl[0], orders[0])) == {4}
+    black = Compressed(table_values(wdl[1], orders[1]))
+    write_table(os.path.join(directory, f"{name}.rtbw"), WDL_MAGIC, piece_orders, [4, black], [0, 0])
+
+    #the DTZ values are remapped by frequency, the map stores the plies to mate minus one
+    plies = table_values(dtz, orders[0])
+    frequency = Counter(plies)
+    dtz_map = sorted(frequency, key=lambda value: (-frequency[value], value))
+    remap = {value: i for i, value in enumerate(dtz_map)}
+    white = Compressed([remap[value] for value in plies])
+    flags = FLAG_MAPPED | FLAG_WIN_PLIES | FLAG_LOSS_PLIES
+    write_table(os.path.join(directory, f"{name}.rtbz"), DTZ_MAGIC, piece_orders[:1], [white], [flags],
+                [[value - 1 for value in dtz_map], [], [], []])
+
+    longest = max(dtz.values())
+    print(f"{name}: {len(black.symbols)} symbols, {len(black.blocks)} WDL blocks, "
+          f"{len(white.blocks)} DTZ blocks, longest mate {longest} plies")
+
+
+def main():
+    directory = sys.argv[1] if len(sys.argv) > 1 else os.path.dirname(os.path.abspath(__file__))
+    os.makedirs(directory, exist_ok=True)
+    generate(directory, "KQvK", QUEEN)
+    generate(directory, "KRvK", ROOK)
+
+
+if __name__ == "__main__":
+    main()
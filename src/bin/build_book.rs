@@ -0,0 +1,360 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use chess_bot::{
+    bot::search::{
+        hashed_book::convert_text_book,
+        polyglot::{polyglot_key, PolyglotBook, PolyglotEntry},
+    },
+    game::{parser::png_parser::PGNParser, Board, Color, Move},
+    init,
+};
+
+/// #### Opening book builder
+/// Streams the games of PGN files and counts the moves played in every position up to a maximum ply.
+/// The moves are written in the text format of the opening book (`--text`) and/or as Polyglot book (`--bin`).
+///
+/// Options:
+/// - `--max-ply <n>` maximum number of plies of a game added to the book (default 20)
+/// - `--min-games <n>` minimum number of games a move must be played in (default 1)
+/// - `--min-elo <n>` minimum `WhiteElo` and `BlackElo` of a game, games without ratings are skipped
+/// - `--results <results>` comma separated results of the games to use, e.g. `1-0,0-1` (default all)
+/// - `--min-score <percent>` minimum score of a move for the side playing it (default 0)
+///
+/// With `convert` a book in the text format is converted to a hashed book keyed by the zobrist key.
+///
+/// Usage: build_book [--text <file>] [--bin <file>] [options] <pgn file>... | build_book convert <text book> <output>
+fn main() {
+    init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().is_some_and(|arg| arg == "convert") {
+        let text_book = args.get(1).expect("Text book must be provided");
+        let output = args.get(2).expect("Output file must be provided");
+        let entries = convert_text_book(text_book, output).expect("Failed to convert the book");
+        println!("{entries} entries written to {output}");
+        return;
+    }
+
+    let config = Config::from_args(&args);
+
+    let mut builder = BookBuilder::default();
+    for pgn_file in &config.pgn_files {
+        let file = File::open(pgn_file).expect("Failed to open the PGN file");
+        for game in PgnGames::new(BufReader::new(file)) {
+            builder.add_game(&game, &config);
+        }
+        println!(
+            "{pgn_file}: {} games added, {} skipped",
+            builder.games_added, builder.games_skipped
+        );
+    }
+    println!("{} positions in the book", builder.positions.len());
+
+    if let Some(path) = &config.text_file {
+        builder
+            .write_text(path, &config)
+            .expect("Failed to write the text book");
+        println!("Text book written to {path}");
+    }
+    if let Some(path) = &config.bin_file {
+        builder
+            .polyglot_book(&config)
+            .write(path)
+            .expect("Failed to write the Polyglot book");
+        println!("Polyglot book written to {path}");
+    }
+}
+
+struct Config {
+    text_file: Option<String>,
+    bin_file: Option<String>,
+    max_ply: usize,
+    min_games: u32,
+    min_elo: Option<u32>,
+    results: Option<Vec<String>>,
+    min_score: f64,
+    pgn_files: Vec<String>,
+}
+
+impl Config {
+    fn from_args(args: &[String]) -> Config {
+        let mut config = Config {
+            text_file: None,
+            bin_file: None,
+            max_ply: 20,
+            min_games: 1,
+            min_elo: None,
+            results: None,
+            min_score: 0.0,
+            pgn_files: Vec::new(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("Missing value for {arg}"))
+                    .clone()
+            };
+            match arg.as_str() {
+                "--text" => config.text_file = Some(value()),
+                "--bin" => config.bin_file = Some(value()),
+                "--max-ply" => config.max_ply = value().parse().expect("Invalid maximum ply"),
+                "--min-games" => {
+                    config.min_games = value().parse().expect("Invalid minimum number of games")
+                }
+                "--min-elo" => config.min_elo = Some(value().parse().expect("Invalid minimum elo")),
+                "--results" => {
+                    config.results = Some(value().split(',').map(|s| s.trim().into()).collect())
+                }
+                "--min-score" => config.min_score = value().parse().expect("Invalid minimum score"),
+                _ => config.pgn_files.push(arg.clone()),
+            }
+        }
+
+        assert!(
+            config.text_file.is_some() || config.bin_file.is_some(),
+            "At least one output file (--text or --bin) must be provided"
+        );
+        assert!(
+            !config.pgn_files.is_empty(),
+            "At least one PGN file must be provided"
+        );
+        config
+    }
+
+    /// Checks the result and rating filters against the tags of the game
+    fn accepts(&self, tags: &HashMap<String, String>) -> bool {
+        if let Some(results) = &self.results {
+            if !tags
+                .get("Result")
+                .is_some_and(|result| results.contains(result))
+            {
+                return false;
+            }
+        }
+        if let Some(min_elo) = self.min_elo {
+            //games without a rating of both players are skipped
+            let rated = |tag: &str| {
+                tags.get(tag)
+                    .and_then(|elo| elo.parse::<u32>().ok())
+                    .is_some_and(|elo| elo >= min_elo)
+            };
+            if !(rated("WhiteElo") && rated("BlackElo")) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Number of games and results of a move, the results are from the perspective of the side playing the move
+#[derive(Default)]
+struct MoveStats {
+    games: u32,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl MoveStats {
+    fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) * 100.0 / self.games.max(1) as f64
+    }
+}
+
+#[derive(Default)]
+struct BookBuilder {
+    /// Moves in uci notation of each position keyed by the fen without half move and ply clock
+    positions: HashMap<String, HashMap<String, MoveStats>>,
+    games_added: usize,
+    games_skipped: usize,
+}
+
+impl BookBuilder {
+    fn add_game(&mut self, pgn: &str, config: &Config) {
+        //variations are not supported by the parser
+        let has_variations = pgn
+            .split_once("\n\n")
+            .is_some_and(|(_, moves)| moves.contains('('));
+        let Some(game) = (!has_variations)
+            .then(|| PGNParser::from_pgn(pgn).ok())
+            .flatten()
+        else {
+            self.games_skipped += 1;
+            return;
+        };
+        if !config.accepts(&game.tags) {
+            self.games_skipped += 1;
+            return;
+        }
+
+        let white_score = match game.tags.get("Result").map(|result| result.as_str()) {
+            Some("1-0") => Some(1),
+            Some("0-1") => Some(-1),
+            Some("1/2-1/2") => Some(0),
+            _ => None,
+        };
+
+        let mut board = game.initial_board();
+        for mov in game.moves.iter().take(config.max_ply) {
+            let stats = self
+                .positions
+                .entry(position_key(&board))
+                .or_default()
+                .entry(mov.to_string())
+                .or_default();
+            stats.games += 1;
+            let score = match board.side_to_move() {
+                Color::White => white_score,
+                Color::Black => white_score.map(|score| -score),
+            };
+            match score {
+                Some(1) => stats.wins += 1,
+                Some(-1) => stats.losses += 1,
+                Some(_) => stats.draws += 1,
+                None => {}
+            }
+
+            board
+                .make_move(mov, false, false)
+                .expect("Moves of a parsed game are legal");
+        }
+        self.games_added += 1;
+    }
+
+    /// Moves of the position which pass the filters, ordered by the number of games
+    fn book_moves<'a>(
+        moves: &'a HashMap<String, MoveStats>,
+        config: &Config,
+    ) -> Vec<(&'a String, &'a MoveStats)> {
+        let mut moves: Vec<_> = moves
+            .iter()
+            .filter(|(_, stats)| stats.games >= config.min_games)
+            .filter(|(_, stats)| stats.score() >= config.min_score)
+            .collect();
+        moves.sort_by(|a, b| b.1.games.cmp(&a.1.games).then(a.0.cmp(b.0)));
+        moves
+    }
+
+    fn write_text(&self, path: &str, config: &Config) -> std::io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        let mut positions: Vec<_> = self.positions.iter().collect();
+        positions.sort_by(|a, b| a.0.cmp(b.0));
+        for (fen, moves) in positions {
+            let moves = BookBuilder::book_moves(moves, config);
+            if moves.is_empty() {
+                continue;
+            }
+            writeln!(file, "pos {fen}")?;
+            for (uci_move, stats) in moves {
+                writeln!(file, "{uci_move} {}", stats.games)?;
+            }
+        }
+        file.flush()
+    }
+
+    /// Converts the positions to Polyglot entries, the number of games is scaled down
+    /// if it doesn't fit into the weight of an entry
+    fn polyglot_book(&self, config: &Config) -> PolyglotBook {
+        let mut entries = Vec::new();
+        for (fen, moves) in &self.positions {
+            let moves = BookBuilder::book_moves(moves, config);
+            let Some(max_games) = moves.first().map(|(_, stats)| stats.games) else {
+                continue;
+            };
+            let board = Board::from_fen(&format!("{fen} 0 1")).expect("Book positions are valid");
+            let key = polyglot_key(&board);
+
+            for (uci_move, stats) in moves {
+                let mov = Move::from_uci_notation(uci_move, &board).expect("Book moves are legal");
+                let weight = if max_games > u16::MAX as u32 {
+                    (stats.games as u64 * u16::MAX as u64 / max_games as u64).max(1)
+                } else {
+                    stats.games as u64
+                };
+                entries.push(PolyglotEntry {
+                    key,
+                    mov: PolyglotEntry::encode_move(&mov, &board),
+                    weight: weight as u16,
+                    learn: 0,
+                });
+            }
+        }
+        PolyglotBook::from_entries(entries)
+    }
+}
+
+/// Fen of the board without half move and ply clock as used by the text book
+fn position_key(board: &Board) -> String {
+    board
+        .to_fen()
+        .split(' ')
+        .take(4)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Iterator over the games of a PGN file, which yields the tags and the move text of one game at a time
+struct PgnGames<R: BufRead> {
+    lines: std::io::Lines<R>,
+    tags: Vec<String>,
+    moves: Vec<String>,
+}
+
+impl<R: BufRead> PgnGames<R> {
+    fn new(reader: R) -> PgnGames<R> {
+        PgnGames {
+            lines: reader.lines(),
+            tags: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    fn take_game(&mut self) -> Option<String> {
+        let game = (!self.tags.is_empty() && !self.moves.is_empty())
+            .then(|| format!("{}\n\n{}", self.tags.join("\n"), self.moves.join("\n")));
+        self.tags.clear();
+        self.moves.clear();
+        game
+    }
+}
+
+impl<R: BufRead> Iterator for PgnGames<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let Some(Ok(line)) = self.lines.next() else {
+                if self.tags.is_empty() && self.moves.is_empty() {
+                    return None;
+                }
+                match self.take_game() {
+                    Some(game) => return Some(game),
+                    None => continue,
+                }
+            };
+            let line = line.trim();
+
+            if line.starts_with('[') {
+                //the tags of the next game start
+                let game = if self.moves.is_empty() {
+                    None
+                } else {
+                    self.take_game()
+                };
+                self.tags.push(line.to_string());
+                if game.is_some() {
+                    return game;
+                }
+            } else if !line.is_empty() && !self.tags.is_empty() {
+                self.moves.push(line.to_string());
+            }
+        }
+    }
+}
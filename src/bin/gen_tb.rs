@@ -0,0 +1,139 @@
+use std::{path::Path, time::Instant};
+
+use chess_bot::{
+    bot::{
+        evaluate_board,
+        tablebase::dtm::{Dtm, DtmTable, DtmTablebases, EXTENSION},
+        EvalParams,
+    },
+    init,
+};
+
+/// #### Tablebase generator
+/// Generates distance to mate tables with retrograde analysis, e.g. `gen_tb tables KQvK KRvK KPvK`.
+/// The tables of all materials reachable by captures and promotions are generated as well,
+/// tables already found in the output directory are reused.
+/// Tables with up to 4 pieces take seconds to minutes, 5 pieces need several GB of memory.
+///
+/// With `verify` the evaluation of every position of the table is compared with the tablebase result,
+/// which lists the positions where the evaluation favors the wrong side.
+///
+/// Usage: gen_tb <output directory> <material>... | gen_tb verify <directory> <material>
+fn main() {
+    init();
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).is_some_and(|arg| arg == "verify") {
+        let directory = args.get(2).expect("Table directory must be provided");
+        let material = args.get(3).expect("Material must be provided");
+        verify(directory, material);
+        return;
+    }
+
+    let directory = args.get(1).expect("Output directory must be provided");
+    let materials = &args[2..];
+    assert!(
+        !materials.is_empty(),
+        "At least one material must be provided"
+    );
+
+    std::fs::create_dir_all(directory).expect("Failed to create the output directory");
+    let mut tablebases = DtmTablebases::open(directory).unwrap_or_default();
+    println!("Found {} tables in {directory}", tablebases.table_count());
+
+    for material in materials {
+        let mut start = Instant::now();
+        tablebases
+            .generate_all(material, &mut |table| {
+                let path =
+                    Path::new(directory).join(format!("{}.{EXTENSION}", table.material().name()));
+                table.write(&path).expect("Failed to write the table");
+                println!(
+                    "{:<8} {} in {:.2?}",
+                    table.material().name(),
+                    Statistics::from_table(table),
+                    start.elapsed()
+                );
+                start = Instant::now();
+            })
+            .expect("Failed to generate the table");
+    }
+}
+
+/// Number of won, drawn and lost positions for the side to move and the longest mate
+#[derive(Default)]
+struct Statistics {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    longest: u8,
+}
+
+impl Statistics {
+    fn from_table(table: &DtmTable) -> Statistics {
+        let mut statistics = Statistics::default();
+        for index in 0..table.len() {
+            match table.get(index).expect("Failed to read the table") {
+                Some(Dtm::Win(plies)) => {
+                    statistics.wins += 1;
+                    statistics.longest = statistics.longest.max(plies);
+                }
+                Some(Dtm::Loss(_)) => statistics.losses += 1,
+                Some(Dtm::Draw) => statistics.draws += 1,
+                None => {}
+            }
+        }
+        statistics
+    }
+}
+
+impl std::fmt::Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "wins: {:>10} | draws: {:>10} | losses: {:>10} | longest mate: {:>3} plies",
+            self.wins, self.draws, self.losses, self.longest
+        )
+    }
+}
+
+/// Compares the sign of the evaluation with the result of every position in the table
+fn verify(directory: &str, material: &str) {
+    let tablebases = DtmTablebases::open(directory).expect("Failed to open the tables");
+    let table = tablebases
+        .get(material)
+        .unwrap_or_else(|| panic!("No table for {material} in {directory}"));
+    let params = EvalParams::default();
+
+    let mut positions = 0;
+    let mut wrong = Vec::new();
+    for index in 0..table.len() {
+        let Some(board) = table.position(index) else {
+            continue;
+        };
+        let dtm = table
+            .get(index)
+            .expect("Failed to read the table")
+            .expect("Legal positions have a value");
+        positions += 1;
+
+        let eval = evaluate_board(&board, &params);
+        let correct = match dtm {
+            Dtm::Win(_) => eval > 0,
+            Dtm::Loss(_) => eval < 0,
+            Dtm::Draw => eval.abs() < 100,
+        };
+        if !correct {
+            wrong.push((board.to_fen(), dtm, eval));
+        }
+    }
+
+    for (fen, dtm, eval) in wrong.iter().take(20) {
+        println!("{fen:<40} {dtm:<20} eval: {eval}");
+    }
+    println!(
+        "{} of {positions} positions evaluated for the wrong side ({:.2}%)",
+        wrong.len(),
+        wrong.len() as f64 * 100.0 / positions.max(1) as f64
+    );
+}
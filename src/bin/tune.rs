@@ -0,0 +1,352 @@
+use std::io::{BufRead, Write};
+
+use chess_bot::{
+    bot::{evaluate_board, Eval, EvalParams, W},
+    game::{board::move_gen::MoveGeneration, Board, Move},
+    init,
+};
+
+/// Maximum number of captures resolved by the quiescence search
+const MAX_QS_DEPTH: u8 = 8;
+/// Change applied to a weight in each step of the local search
+const STEP: Eval = 1;
+
+/// #### Texel tuning
+/// Fits the evaluation weights to the results of labelled positions.
+/// Each line of the position file contains a FEN and the game result from whites perspective,
+/// either as `1-0`, `0-1`, `1/2-1/2` or as a number (`1.0`, `0.5`, `0.0`),
+/// optionally enclosed by `[]` or `""` (e.g. `<fen> [0.5]` or `<fen> c9 "1-0";`).
+///
+/// The tuning starts from the compiled in defaults or from the parameter file set in `EVAL_PARAMS`.
+/// If the output file ends with `.rs` the weights are written as rust constants,
+/// otherwise as parameter file which can be loaded by the engine.
+///
+/// Usage: tune <position file> \[iterations] \[output file]
+fn main() {
+    init();
+    let args: Vec<String> = std::env::args().collect();
+
+    let position_file = args.get(1).expect("Position file must be provided");
+    let iterations = args
+        .get(2)
+        .map(|s| s.parse::<usize>().expect("Iterations must be a number"))
+        .unwrap_or(100);
+    let output_file = args.get(3).map_or("tuned_params.rs", |s| s.as_str());
+
+    let mut params = match std::env::var("EVAL_PARAMS") {
+        Ok(path) if !path.is_empty() => {
+            EvalParams::from_file(&path).expect("Invalid evaluation parameter file")
+        }
+        _ => EvalParams::default(),
+    };
+
+    let positions = load_positions(position_file);
+    println!("Loaded {} positions", positions.len());
+
+    let positions = resolve_positions(positions, &params);
+    println!("Resolved quiet positions");
+
+    let k = fit_k(&positions, &params);
+    let mut best_error = error(&positions, &params, k);
+    println!("K: {k:.4} | Error: {best_error:.8}");
+
+    for iteration in 1..=iterations {
+        let mut improved = false;
+
+        for group in 0..params.groups().len() {
+            for index in 0..params.groups()[group].weights.len() {
+                for phase in [Phase::MidGame, Phase::EndGame] {
+                    for step in [STEP, -STEP] {
+                        adjust(&mut params, group, index, phase, step);
+                        let new_error = error(&positions, &params, k);
+                        if new_error < best_error {
+                            best_error = new_error;
+                            improved = true;
+                            break;
+                        }
+                        adjust(&mut params, group, index, phase, -step);
+                    }
+                }
+            }
+        }
+
+        println!("Iteration {iteration} | Error: {best_error:.8}");
+        if output_file.ends_with(".rs") {
+            write_rust_source(output_file, &params, positions.len(), k, best_error);
+        } else {
+            std::fs::write(output_file, params.to_param_string())
+                .expect("Could not write output file");
+        }
+
+        if !improved {
+            println!("No further improvement found");
+            break;
+        }
+    }
+
+    println!("Tuned parameters written to {output_file}");
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    MidGame,
+    EndGame,
+}
+
+fn adjust(params: &mut EvalParams, group: usize, index: usize, phase: Phase, step: Eval) {
+    let w = &mut params.groups_mut()[group].weights[index];
+    match phase {
+        Phase::MidGame => w.0 += step,
+        Phase::EndGame => w.1 += step,
+    }
+}
+
+/// Parses a line of the position file into the board and the result from whites perspective
+fn parse_labelled_position(line: &str) -> Option<(Board, f64)> {
+    let line = line.trim();
+    let (fen, result) = match line.find(['[', '"']) {
+        Some(index) => (&line[..index], &line[index..]),
+        None => line.rsplit_once(char::is_whitespace)?,
+    };
+    let separator = |c: char| c == ';' || c.is_whitespace();
+    let fen = fen.trim_end_matches(separator);
+    let fen = fen
+        .strip_suffix(" c9")
+        .unwrap_or(fen)
+        .trim_end_matches(separator);
+
+    //the result of an EPD line is the operand of the c9 opcode
+    let result = result.trim();
+    let result = result.strip_prefix("c9").unwrap_or(result);
+    let result = result.trim_matches(|c: char| c == '[' || c == ']' || c == '"' || separator(c));
+    let result = match result {
+        "1-0" => 1.0,
+        "0-1" => 0.0,
+        "1/2-1/2" => 0.5,
+        value => value
+            .parse::<f64>()
+            .ok()
+            .filter(|r| (0.0..=1.0).contains(r))?,
+    };
+
+    Board::from_fen(fen).ok().map(|board| (board, result))
+}
+
+fn load_positions(path: &str) -> Vec<(Board, f64)> {
+    let file = std::fs::File::open(path).expect("Could not open position file");
+    let mut positions = Vec::new();
+    let mut invalid = 0;
+
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.expect("Could not read position file");
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_labelled_position(&line) {
+            Some(position) => positions.push(position),
+            None => invalid += 1,
+        }
+    }
+
+    if invalid > 0 {
+        println!("Skipped {invalid} invalid lines");
+    }
+    positions
+}
+
+/// Replaces every position with the leaf of its quiescence search principal variation,
+/// so that the static evaluation is not distorted by pending captures
+fn resolve_positions(positions: Vec<(Board, f64)>, params: &EvalParams) -> Vec<(Board, f64)> {
+    let threads = thread_count();
+    let chunk_size = positions.len().div_ceil(threads).max(1);
+    let mut chunks: Vec<Vec<(Board, f64)>> = positions
+        .chunks(chunk_size)
+        .map(|chunk| chunk.to_vec())
+        .collect();
+
+    std::thread::scope(|scope| {
+        for chunk in chunks.iter_mut() {
+            scope.spawn(move || {
+                for (board, _) in chunk.iter_mut() {
+                    let mut pv = Vec::new();
+                    quiescence(board, params, Eval::MIN + 1, Eval::MAX, 0, &mut pv);
+                    for mov in pv {
+                        board.make_move(&mov, true, false).unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    chunks.into_iter().flatten().collect()
+}
+
+fn quiescence(
+    board: &mut Board,
+    params: &EvalParams,
+    mut alpha: Eval,
+    beta: Eval,
+    depth: u8,
+    pv: &mut Vec<Move>,
+) -> Eval {
+    let stand_pat = evaluate_board(board, params);
+    if stand_pat >= beta {
+        return beta;
+    }
+    alpha = alpha.max(stand_pat);
+    if depth >= MAX_QS_DEPTH {
+        return alpha;
+    }
+
+    let captures = MoveGeneration::generate_legal_moves_captures(board);
+    for mov in captures.iter() {
+        board.make_move(mov, true, false).unwrap();
+        let mut line = Vec::new();
+        let score = -quiescence(board, params, -beta, -alpha, depth + 1, &mut line);
+        board.undo_move(mov, true).unwrap();
+
+        if score >= beta {
+            return beta;
+        }
+        if score > alpha {
+            alpha = score;
+            pv.clear();
+            pv.push(*mov);
+            pv.extend(line);
+        }
+    }
+
+    alpha
+}
+
+#[inline(always)]
+fn sigmoid(eval: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+/// Mean squared error between the game results and the predicted results of the evaluation
+fn error(positions: &[(Board, f64)], params: &EvalParams, k: f64) -> f64 {
+    let threads = thread_count();
+    let chunk_size = positions.len().div_ceil(threads).max(1);
+
+    let total: f64 = std::thread::scope(|scope| {
+        let handles: Vec<_> = positions
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(board, result)| {
+                            let eval = evaluate_board(board, params)
+                                * board.side_to_move().perspective() as Eval;
+                            (result - sigmoid(eval as f64, k)).powi(2)
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    });
+
+    total / positions.len().max(1) as f64
+}
+
+/// Finds the scaling constant K of the sigmoid which minimizes the error of the current weights
+fn fit_k(positions: &[(Board, f64)], params: &EvalParams) -> f64 {
+    let mut best_k = 1.0;
+    let mut best_error = error(positions, params, best_k);
+    let mut step = 0.5;
+
+    for _ in 0..12 {
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for k in [best_k - step, best_k + step] {
+                if k <= 0.0 {
+                    continue;
+                }
+                let e = error(positions, params, k);
+                if e < best_error {
+                    best_error = e;
+                    best_k = k;
+                    improved = true;
+                }
+            }
+        }
+        step /= 2.0;
+    }
+
+    best_k
+}
+
+fn thread_count() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Writes the parameters as rust constants, which can replace the constants of the evaluation terms
+fn write_rust_source(path: &str, params: &EvalParams, positions: usize, k: f64, error: f64) {
+    let mut file = std::fs::File::create(path).expect("Could not create output file");
+    let mut source = format!(
+        "// Tuned evaluation parameters\n// positions: {positions}, K: {k:.4}, error: {error:.8}\n"
+    );
+
+    let format_w = |w: &W| format!("W({}, {})", w.0, w.1);
+    for group in params.groups() {
+        let name = group.name.to_ascii_uppercase();
+        source.push('\n');
+        if group.type_name == "W" {
+            source += &format!("const {name}: W = {};\n", format_w(&group.weights[0]));
+            continue;
+        }
+
+        source += &format!("#[rustfmt::skip]\nconst {name}: {} = [\n", group.type_name);
+        let nested = group.type_name.starts_with("[[");
+        let chunks: Vec<&[W]> = if nested {
+            group.weights.chunks(64).collect()
+        } else {
+            vec![group.weights]
+        };
+        for chunk in chunks {
+            let indent = if nested {
+                source += "    [\n";
+                "        "
+            } else {
+                "    "
+            };
+            for row in chunk.chunks(8) {
+                let row: Vec<String> = row.iter().map(format_w).collect();
+                source += &format!("{indent}{},\n", row.join(", "));
+            }
+            if nested {
+                source += "    ],\n";
+            }
+        }
+        source += "];\n";
+    }
+
+    file.write_all(source.as_bytes())
+        .expect("Could not write output file");
+}
+
+mod test {
+    #[test]
+    fn test_parse_labelled_position() {
+        use super::parse_labelled_position;
+        use chess_bot::game::Board;
+
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        let expected = Board::from_fen(fen).unwrap().to_fen();
+        for line in [
+            format!("{fen} [0.9]"),
+            format!("{fen} 0.9"),
+            format!("{fen} c9 \"0.9\";"),
+            format!("{fen}; c9 0.9;"),
+        ] {
+            let (board, result) = parse_labelled_position(&line).unwrap();
+            assert_eq!(board.to_fen(), expected);
+            assert_eq!(result, 0.9, "{line}");
+        }
+        let (_, result) = parse_labelled_position(&format!("{fen} c9 \"1/2-1/2\";")).unwrap();
+        assert_eq!(result, 0.5);
+    }
+}
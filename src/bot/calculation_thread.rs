@@ -1,6 +1,10 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{
+    mpsc::{Receiver, Sender},
+    Arc,
+};
 
 use super::{
+    evaluation::params::EvalParams,
     search::{
         searcher::Searcher,
         transposition_table::{TranspositionTable, DEFAULT_HASH_SIZE},
@@ -8,17 +12,33 @@ use super::{
     },
     ActionMessage, ReactionMessage,
 };
+use crate::uci::commands::command_set_option::OptionType;
 
 pub fn thread_loop(
     receiver: Receiver<ActionMessage>,
     sender: Sender<ReactionMessage>,
     flag: AbortFlag,
+    params: Arc<EvalParams>,
 ) {
     let tx = sender.clone();
     let tt = TranspositionTable::new(DEFAULT_HASH_SIZE);
 
     let opening_book_path = std::env::var("OPENING_BOOK").unwrap_or("".to_string());
-    let mut searcher = Searcher::new(tt, tx, flag, opening_book_path);
+    let eval_file = std::env::var("EVAL_FILE").unwrap_or("".to_string());
+    let syzygy_path = std::env::var("SYZYGY_PATH").unwrap_or("".to_string());
+    let dtm_path = std::env::var("DTM_PATH").unwrap_or("".to_string());
+    let mut searcher = Searcher::new(
+        tt,
+        tx,
+        flag,
+        opening_book_path,
+        eval_file,
+        syzygy_path,
+        params,
+    );
+    if !dtm_path.is_empty() {
+        searcher.handle_set_option(OptionType::DtmPath(dtm_path));
+    }
 
     loop {
         match receiver.recv() {
@@ -31,6 +51,8 @@ pub fn thread_loop(
                     info!("Search run for {} seconds", elapsed.as_secs_f64());
                 }
                 ActionMessage::SetOption(option_typ) => searcher.handle_set_option(option_typ),
+                ActionMessage::SetEvalParams(params) => searcher.set_eval_params(params),
+                ActionMessage::GameOver(result) => searcher.game_over(result),
             },
             Err(_) => {
                 info!("Disconnected from main thread");
@@ -0,0 +1,16 @@
+use super::eval::Eval;
+use crate::game::Board;
+
+/// Score of each piece on the board, all pieces have to be given away to win
+const PIECE_BURDEN: Eval = 100;
+
+/// #### Antichess evaluation
+/// Losing all pieces or being stalemated wins, so every piece on the board counts against its color
+/// regardless of its type. The forced captures are left to the search.
+/// Returns the score in the perspective of the side to move.
+pub(super) fn evaluate_antichess(board: &Board) -> Eval {
+    let color = board.side_to_move();
+    let own = board.get_bb_occupied(color).count_ones() as Eval;
+    let enemy = board.get_bb_occupied(color.opposite()).count_ones() as Eval;
+    (enemy - own) * PIECE_BURDEN
+}
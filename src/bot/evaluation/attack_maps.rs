@@ -0,0 +1,68 @@
+use crate::game::{
+    board::move_gen::{attacks_bishop, attacks_king, attacks_knight, attacks_rook},
+    Board, Color, PieceType,
+};
+
+use super::masks::pawn_attacks;
+
+/// #### Attack maps
+/// All squares attacked by each piece type of each color.
+/// Calculated the same way as the king danger mask of the move generation, but for both colors:
+/// sliding pieces see through nothing, own pieces are included as defended squares.
+pub(super) struct AttackMaps {
+    /// Attacked squares indexed by \[color]\[piece type]
+    pub by_piece: [[u64; 6]; 2],
+    /// All attacked squares indexed by \[color]
+    pub all: [u64; 2],
+    /// Squares attacked at least twice indexed by \[color]
+    pub double: [u64; 2],
+}
+
+impl AttackMaps {
+    pub fn from_board(board: &Board) -> AttackMaps {
+        let mut maps = AttackMaps {
+            by_piece: [[0; 6]; 2],
+            all: [0; 2],
+            double: [0; 2],
+        };
+        maps.calculate_for(board, Color::White);
+        maps.calculate_for(board, Color::Black);
+        maps
+    }
+
+    fn calculate_for(&mut self, board: &Board, color: Color) {
+        let occupied = *board.get_bb_all_occupied();
+
+        let pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color));
+        self.add(color, PieceType::Pawn, pawn_attacks(pawns, color));
+
+        for sq in board.get_piece_positions(PieceType::Knight.as_colored_piece(color)) {
+            self.add(color, PieceType::Knight, attacks_knight(sq, 0));
+        }
+        for sq in board.get_piece_positions(PieceType::Bishop.as_colored_piece(color)) {
+            self.add(color, PieceType::Bishop, attacks_bishop(sq, occupied, 0));
+        }
+        for sq in board.get_piece_positions(PieceType::Rook.as_colored_piece(color)) {
+            self.add(color, PieceType::Rook, attacks_rook(sq, occupied, 0));
+        }
+        for sq in board.get_piece_positions(PieceType::Queen.as_colored_piece(color)) {
+            self.add(
+                color,
+                PieceType::Queen,
+                attacks_bishop(sq, occupied, 0) | attacks_rook(sq, occupied, 0),
+            );
+        }
+        self.add(
+            color,
+            PieceType::King,
+            attacks_king(board.get_king_pos(color), 0),
+        );
+    }
+
+    #[inline(always)]
+    fn add(&mut self, color: Color, piece_type: PieceType, attacks: u64) {
+        self.double[color] |= self.all[color] & attacks;
+        self.all[color] |= attacks;
+        self.by_piece[color][piece_type] |= attacks;
+    }
+}
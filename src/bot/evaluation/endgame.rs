@@ -0,0 +1,367 @@
+use crate::game::{
+    bit_manipulation::constants::A_FILE, Board, Color, GameResult, PieceType, Square,
+};
+
+use super::{
+    eval::{Eval, DRAW, KNOWN_WIN},
+    kpk,
+    masks::{relative_rank, DARK_SQUARES},
+    params::EvalParams,
+    piece_square_table::{BISHOP_MG, ROOK_MG},
+};
+
+/// Scale factor which keeps the evaluation unchanged
+pub(super) const SCALE_NORMAL: Eval = 64;
+const SCALE_DRAW: Eval = 0;
+/// Only opposite colored bishops and pawns are left
+const SCALE_OPPOSITE_BISHOPS: Eval = 32;
+/// Opposite colored bishops with a rook for each side
+const SCALE_OPPOSITE_BISHOPS_ROOKS: Eval = 48;
+
+/// Number of pieces of each piece type indexed by \[color]\[piece type]
+struct MaterialSignature([[u32; 6]; 2]);
+
+impl MaterialSignature {
+    fn from_board(board: &Board) -> MaterialSignature {
+        let bb = board.get_bb_pieces();
+        let mut counts = [[0; 6]; 2];
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceType::iter() {
+                counts[color][piece_type] = bb[color][piece_type].count_ones();
+            }
+        }
+        MaterialSignature(counts)
+    }
+
+    #[inline(always)]
+    fn count(&self, color: Color, piece_type: PieceType) -> u32 {
+        self.0[color][piece_type]
+    }
+
+    /// Number of pieces without the kings
+    fn pieces(&self, color: Color) -> u32 {
+        self.0[color].iter().sum::<u32>() - self.count(color, PieceType::King)
+    }
+
+    /// Only the given pieces (without the king) are left for the color
+    fn only(&self, color: Color, pieces: &[(PieceType, u32)]) -> bool {
+        pieces.iter().map(|(_, count)| count).sum::<u32>() == self.pieces(color)
+            && pieces
+                .iter()
+                .all(|(piece_type, count)| self.count(color, *piece_type) == *count)
+    }
+}
+
+/// Endgames with a specialized evaluation function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EndgameKind {
+    /// King and pawn vs king, looked up in the bitbase
+    Kpk,
+    /// King, bishop and knight vs king, the king is driven to a corner of the bishops color
+    Kbnk,
+    /// Mating material vs a lone king, the king is driven to the edge
+    Kxk,
+}
+
+/// Specialized evaluation selected by the material signature of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Endgame {
+    kind: EndgameKind,
+    strong_side: Color,
+}
+
+impl std::fmt::Display for Endgame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            EndgameKind::Kpk => "KPK bitbase",
+            EndgameKind::Kbnk => "KBNK mate",
+            EndgameKind::Kxk => "KXK mate",
+        };
+        write!(f, "{kind} ({:?} is the strong side)", self.strong_side)
+    }
+}
+
+impl Endgame {
+    /// Returns the specialized evaluation for the material on the board if there is one
+    pub(super) fn probe(board: &Board) -> Option<Endgame> {
+        //pieces in the pockets can still be dropped, so the material on the board doesn't decide the ending
+        //and without a royal king there is nothing to mate
+        if !board.cur_state().pockets.is_empty() || !board.variant().has_royal_king() {
+            return None;
+        }
+
+        let material = MaterialSignature::from_board(board);
+
+        for strong_side in [Color::White, Color::Black] {
+            let weak_side = strong_side.opposite();
+            if material.pieces(weak_side) != 0 {
+                continue;
+            }
+
+            let kind = if material.only(strong_side, &[(PieceType::Pawn, 1)]) {
+                EndgameKind::Kpk
+            } else if material.only(
+                strong_side,
+                &[(PieceType::Bishop, 1), (PieceType::Knight, 1)],
+            ) {
+                EndgameKind::Kbnk
+            } else if has_mating_material(board, &material, strong_side) {
+                EndgameKind::Kxk
+            } else {
+                continue;
+            };
+
+            return Some(Endgame { kind, strong_side });
+        }
+
+        None
+    }
+
+    /// Evaluates the board and returns the score from whites perspective
+    pub(super) fn evaluate(&self, board: &Board, params: &EvalParams) -> Eval {
+        let score = match self.kind {
+            EndgameKind::Kpk => self.evaluate_kpk(board, params),
+            EndgameKind::Kbnk => self.evaluate_kbnk(board, params),
+            EndgameKind::Kxk => self.evaluate_kxk(board, params),
+        };
+
+        score * self.strong_side.perspective() as Eval
+    }
+
+    fn evaluate_kpk(&self, board: &Board, params: &EvalParams) -> Eval {
+        let pawn = Square::new(
+            board.get_bb_pieces()[self.strong_side][PieceType::Pawn].trailing_zeros() as u8,
+        );
+
+        let win = kpk::probe(
+            self.strong_side,
+            board.get_king_pos(self.strong_side),
+            pawn,
+            board.get_king_pos(self.strong_side.opposite()),
+            board.side_to_move(),
+        );
+
+        if win {
+            //advancing the pawn is progress
+            KNOWN_WIN
+                + params.piece_values[PieceType::Pawn].1
+                + 10 * relative_rank(pawn.rank(), self.strong_side) as Eval
+        } else {
+            DRAW
+        }
+    }
+
+    fn evaluate_kbnk(&self, board: &Board, params: &EvalParams) -> Eval {
+        let strong_king = board.get_king_pos(self.strong_side);
+        let weak_king = board.get_king_pos(self.strong_side.opposite());
+        let bishop = Square::new(
+            board.get_bb_pieces()[self.strong_side][PieceType::Bishop].trailing_zeros() as u8,
+        );
+
+        //mate is only possible in the corners of the bishops color, a1 and h8 are dark squares
+        let corner_distance = match bishop.color() {
+            Color::Black => push_to_dark_corner(weak_king),
+            Color::White => push_to_dark_corner(Square::new(weak_king.square_value() ^ 7)),
+        };
+
+        KNOWN_WIN
+            + params.piece_values[PieceType::Bishop].1
+            + params.piece_values[PieceType::Knight].1
+            + push_close(strong_king, weak_king)
+            + 50 * corner_distance
+    }
+
+    fn evaluate_kxk(&self, board: &Board, params: &EvalParams) -> Eval {
+        let bb = board.get_bb_pieces();
+        let strong_king = board.get_king_pos(self.strong_side);
+        let weak_king = board.get_king_pos(self.strong_side.opposite());
+
+        let material: Eval = PieceType::iter()
+            .map(|piece_type| {
+                bb[self.strong_side][piece_type].count_ones() as Eval
+                    * params.piece_values[piece_type].1
+            })
+            .sum();
+
+        KNOWN_WIN + material + push_to_edge(weak_king) + push_close(strong_king, weak_king)
+    }
+}
+
+/// The color can force mate against a lone king
+fn has_mating_material(board: &Board, material: &MaterialSignature, color: Color) -> bool {
+    let bishops = *board.get_bb_pieces()[color][PieceType::Bishop];
+    let bishops_on_both_colors = bishops & DARK_SQUARES != 0 && bishops & !DARK_SQUARES != 0;
+
+    material.count(color, PieceType::Queen) > 0
+        || material.count(color, PieceType::Rook) > 0
+        || bishops_on_both_colors
+        || (bishops != 0 && material.count(color, PieceType::Knight) > 0)
+}
+
+/// Bonus for driving the king to the edge of the board
+fn push_to_edge(sq: Square) -> Eval {
+    let edge_distance = |x: u8| x.min(7 - x) as Eval;
+    let rank = edge_distance(sq.rank());
+    let file = edge_distance(sq.file());
+    90 - (7 * file * file / 2 + 7 * rank * rank / 2)
+}
+
+/// Bonus for the kings being close to each other
+fn push_close(a: Square, b: Square) -> Eval {
+    140 - 20 * a.distance(b) as Eval
+}
+
+/// Distance to the a8 h1 diagonal, highest in the dark corners a1 and h8
+fn push_to_dark_corner(sq: Square) -> Eval {
+    (7 - sq.rank() as Eval - sq.file() as Eval).abs()
+}
+
+/// #### Scale factor
+/// Scales the score of known drawish material down, the strong side is the side the score favors.
+/// Returns a factor out of [`SCALE_NORMAL`]:
+/// - insufficient mating material (see [`GameResult`]) is a draw
+/// - two knights vs a lone king
+/// - the strong side has no pawns and is at most a minor piece ahead (e.g. KR vs KB)
+/// - a bishop and rook pawns, where the bishop doesn't control the promotion square
+///   and the enemy king is in front of the pawns
+/// - opposite colored bishops
+pub(super) fn scale_factor(board: &Board, score: Eval) -> Eval {
+    if GameResult::check_insufficient_material(board) {
+        return SCALE_DRAW;
+    }
+    if score == 0 {
+        return SCALE_NORMAL;
+    }
+
+    let strong_side = if score > 0 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let weak_side = strong_side.opposite();
+    let material = MaterialSignature::from_board(board);
+    let strong_material = board.non_pawn_material(strong_side);
+    let weak_material = board.non_pawn_material(weak_side);
+
+    //two knights can't force mate
+    if material.only(strong_side, &[(PieceType::Knight, 2)]) && material.pieces(weak_side) == 0 {
+        return SCALE_DRAW;
+    }
+
+    if material.count(strong_side, PieceType::Pawn) == 0
+        && strong_material - weak_material <= BISHOP_MG
+    {
+        return if strong_material < ROOK_MG {
+            SCALE_DRAW
+        } else if weak_material <= BISHOP_MG {
+            4
+        } else {
+            14
+        };
+    }
+
+    if is_wrong_bishop(board, &material, strong_side) {
+        return SCALE_DRAW;
+    }
+
+    let bishops = board.get_bb_pieces();
+    let white_bishop = *bishops[Color::White][PieceType::Bishop];
+    let black_bishop = *bishops[Color::Black][PieceType::Bishop];
+    let opposite_bishops = material.count(Color::White, PieceType::Bishop) == 1
+        && material.count(Color::Black, PieceType::Bishop) == 1
+        && (white_bishop & DARK_SQUARES == 0) != (black_bishop & DARK_SQUARES == 0);
+
+    if opposite_bishops {
+        if strong_material == BISHOP_MG && weak_material == BISHOP_MG {
+            return SCALE_OPPOSITE_BISHOPS;
+        }
+        if strong_material == BISHOP_MG + ROOK_MG && weak_material == BISHOP_MG + ROOK_MG {
+            return SCALE_OPPOSITE_BISHOPS_ROOKS;
+        }
+    }
+
+    SCALE_NORMAL
+}
+
+/// Bishop and pawns on a single rook file, the bishop doesn't control the promotion square
+/// and the enemy king reached the promotion square
+fn is_wrong_bishop(board: &Board, material: &MaterialSignature, strong_side: Color) -> bool {
+    if board.non_pawn_material(strong_side) != BISHOP_MG
+        || material.count(strong_side, PieceType::Bishop) != 1
+        || board.non_pawn_material(strong_side.opposite()) != 0
+    {
+        return false;
+    }
+
+    let bb = board.get_bb_pieces();
+    let pawns = *bb[strong_side][PieceType::Pawn];
+    let file = if pawns & !A_FILE == 0 {
+        0
+    } else if pawns & !(A_FILE << 7) == 0 {
+        7
+    } else {
+        return false;
+    };
+
+    let promotion_square = Square::new(strong_side.promotion_rank() * 8 + file);
+    let bishop = Square::new(bb[strong_side][PieceType::Bishop].trailing_zeros() as u8);
+
+    bishop.color() != promotion_square.color()
+        && board
+            .get_king_pos(strong_side.opposite())
+            .distance(promotion_square)
+            <= 1
+}
+
+mod test {
+    #[test]
+    fn test_endgames() {
+        use crate::bot::{evaluate_board, EvalParams};
+        use crate::game::{Board, Color, Variant};
+
+        let params = EvalParams::default();
+        let eval = |fen: &str| evaluate_board(&Board::from_fen(fen).unwrap(), &params);
+        let known_win = super::KNOWN_WIN;
+
+        //king in front of the pawn on the 6th rank wins, for both colors and sides to move
+        assert!(eval("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1") > known_win);
+        assert!(eval("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1") < -known_win);
+        assert!(eval("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1") > known_win);
+        assert!(eval("8/8/8/8/3p4/3k4/8/3K4 w - - 0 1") < -known_win);
+        //rook pawn with the enemy king in the corner and a pawn which can be captured
+        assert_eq!(eval("k7/8/K7/P7/8/8/8/8 w - - 0 1"), 0);
+        assert_eq!(eval("8/8/8/8/8/3k4/3P4/7K b - - 0 1"), 0);
+
+        //the king is driven to the corner of the bishops color
+        let right_corner = eval("7k/8/5K2/8/8/8/8/2B1N3 w - - 0 1");
+        let wrong_corner = eval("k7/8/2K5/8/8/8/8/2B1N3 w - - 0 1");
+        assert!(right_corner > wrong_corner && wrong_corner > known_win);
+        //and to the edge by a rook
+        assert!(eval("4k3/8/4K3/8/8/8/8/R7 w - - 0 1") > eval("8/8/8/3k4/8/4K3/8/R7 w - - 0 1"));
+
+        //insufficient material, wrong bishop and two knights
+        assert_eq!(eval("4k3/8/8/8/8/8/8/3NK3 w - - 0 1"), 0);
+        assert_eq!(eval("k7/8/8/P7/P7/8/8/2B1K3 w - - 0 1"), 0);
+        assert_eq!(eval("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1"), 0);
+        assert!(eval("k7/8/8/P7/P7/8/8/3BK3 w - - 0 1") > 100);
+        //rook vs bishop and opposite colored bishops are scaled down
+        assert!(eval("4k3/8/8/8/3b4/8/8/R3K3 w - - 0 1").abs() < 50);
+        assert!(eval("4k3/8/8/8/3b4/8/8/R3K3 w - - 0 1") >= 0);
+        let opposite_bishops = eval("4k3/1b6/8/8/3PP3/8/8/2B1K3 w - - 0 1");
+        let same_bishops = eval("4k3/2b5/8/8/3PP3/8/8/2B1K3 w - - 0 1");
+        assert!(opposite_bishops > 0 && opposite_bishops < same_bishops * 3 / 4);
+
+        //Antichess positions have any number of kings and no specialized endgames
+        for fen in [
+            "8/8/8/8/8/8/8/R7 w - - 0 1",
+            "8/8/8/8/8/2k5/8/R3K1K1 w - - 0 1",
+        ] {
+            let board = Board::from_fen_variant(fen, Variant::Antichess).unwrap();
+            assert_eq!(super::Endgame::probe(&board), None);
+            assert_eq!(
+                super::MaterialSignature::from_board(&board).pieces(Color::White),
+                1
+            );
+        }
+    }
+}
@@ -1,10 +1,15 @@
-pub type Eval = i32;
+pub use crate::game::piece_square_table::Eval;
 
 pub const NEG_INF: Eval = i32::MIN + 1;
 pub const POS_INF: Eval = i32::MAX - 1;
 
 pub const DRAW: Eval = 0;
 pub const MATE: Eval = 10000;
+/// Score of a position which is known to be won, but where no mate was found yet
+pub const KNOWN_WIN: Eval = 1000;
+/// Score of a tablebase win, decreased by the distance from the root like mate scores.
+/// Tablebase wins are below the mate scores so a found mate is still preferred.
+pub const TB_WIN: Eval = MATE - 2 * u8::MAX as Eval;
 
 pub fn is_mate_score(score: Eval) -> bool {
     if score == NEG_INF || score == POS_INF {
@@ -14,6 +19,14 @@ pub fn is_mate_score(score: Eval) -> bool {
     score.abs() >= MATE - u8::MAX as i32
 }
 
+pub fn is_tb_score(score: Eval) -> bool {
+    if score == NEG_INF || score == POS_INF || is_mate_score(score) {
+        return false;
+    }
+
+    score.abs() >= TB_WIN - u8::MAX as i32
+}
+
 pub fn display_eval(eval: Eval) -> String {
     if is_mate_score(eval) {
         let mate_in = (MATE - eval.abs()) as f32 / 2.0;
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crate::game::{Board, Move};
+
+use super::{
+    estimate_board,
+    eval::Eval,
+    evaluate_board, evaluate_board_pst,
+    nnue::{NNUEEvaluator, Network},
+    params::EvalParams,
+};
+
+/// #### Evaluator
+/// Interface between the search and a static evaluation function.
+/// Only the full evaluation is required, the other hooks are optional:
+/// - Incremental hooks are called by the searcher after every move made or undone on its board,
+///   evaluators with internal state (e.g. accumulators) can update it from the deltas
+/// - A lazy bound is a cheap estimate with a margin, if the estimate is outside of the
+///   search window by more than the margin, the full evaluation is skipped
+pub trait Evaluator {
+    /// Name used to select the evaluator
+    fn name(&self) -> &'static str;
+
+    /// Evaluates the board state and returns a score in the perspective of the side to move.
+    fn evaluate(&mut self, board: &Board) -> Eval;
+
+    /// Called when the searcher starts searching a new root position
+    fn on_new_position(&mut self, _board: &Board) {}
+
+    /// Called after the move was made on the board
+    fn on_make_move(&mut self, _board: &Board, _mov: &Move) {}
+
+    /// Called after the move was undone on the board
+    fn on_undo_move(&mut self, _board: &Board, _mov: &Move) {}
+
+    /// Cheap estimate of the evaluation and the maximum error of the estimate
+    fn lazy_bound(&mut self, _board: &Board) -> Option<(Eval, Eval)> {
+        None
+    }
+}
+
+/// Hand crafted evaluation with all evaluation terms
+pub struct ClassicEvaluator(pub Arc<EvalParams>);
+
+impl Evaluator for ClassicEvaluator {
+    fn name(&self) -> &'static str {
+        ClassicEvaluator::NAME
+    }
+
+    fn evaluate(&mut self, board: &Board) -> Eval {
+        evaluate_board(board, &self.0)
+    }
+
+    fn lazy_bound(&mut self, board: &Board) -> Option<(Eval, Eval)> {
+        estimate_board(board, &self.0)
+    }
+}
+
+impl ClassicEvaluator {
+    pub const NAME: &'static str = "Classic";
+}
+
+/// Material and piece square tables only
+pub struct PSTEvaluator(pub Arc<EvalParams>);
+
+impl Evaluator for PSTEvaluator {
+    fn name(&self) -> &'static str {
+        PSTEvaluator::NAME
+    }
+
+    fn evaluate(&mut self, board: &Board) -> Eval {
+        evaluate_board_pst(board, &self.0)
+    }
+}
+
+impl PSTEvaluator {
+    pub const NAME: &'static str = "PST";
+}
+
+/// The evaluator used if no other evaluator is selected
+pub const DEFAULT_EVALUATOR: &str = ClassicEvaluator::NAME;
+
+/// Names of all registered evaluators which can be selected at runtime
+pub const REGISTERED_EVALUATORS: [&str; 3] = [
+    ClassicEvaluator::NAME,
+    PSTEvaluator::NAME,
+    NNUEEvaluator::NAME,
+];
+
+/// Creates the registered evaluator with the given name (case insensitive),
+/// the network is only used by the NNUE evaluator, the parameters by the hand crafted evaluators
+pub fn create_evaluator(
+    name: &str,
+    network: Option<Arc<Network>>,
+    params: Arc<EvalParams>,
+) -> Option<Box<dyn Evaluator>> {
+    match name.to_ascii_lowercase().as_str() {
+        "classic" => Some(Box::new(ClassicEvaluator(params))),
+        "pst" => Some(Box::new(PSTEvaluator(params))),
+        "nnue" => Some(Box::new(NNUEEvaluator::new(network, params))),
+        _ => None,
+    }
+}
+
+mod test {
+    #[test]
+    fn test_lazy_bound() {
+        use super::{ClassicEvaluator, Evaluator};
+        use crate::game::{Board, MoveGeneration};
+        use std::sync::Arc;
+
+        let mut evaluator = ClassicEvaluator(Arc::default());
+        let mut boards: Vec<Board> = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4kr2/B1r2pb1/b6p/1p4pn/pP2qP1P/P4NPN/3KP2R/1b1R1B2 b - - 1 28",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "6k1/5ppp/8/8/6nq/8/5PPP/5RK1 w - - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "8/1P4k1/8/2P5/8/6K1/pp6/8 b - - 0 1",
+        ]
+        .iter()
+        .map(|fen| Board::from_fen(fen).unwrap())
+        .collect();
+
+        //add the positions after every legal move to cover tactical positions
+        let children: Vec<Board> = boards
+            .iter()
+            .flat_map(|board| {
+                MoveGeneration::generate_legal_moves(board)
+                    .iter()
+                    .map(|mov| {
+                        let mut child = board.clone();
+                        child.make_move(mov, true, false).unwrap();
+                        child
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        boards.extend(children);
+
+        let mut bounded = 0;
+        for board in &boards {
+            let Some((estimate, margin)) = evaluator.lazy_bound(board) else {
+                continue;
+            };
+            let eval = evaluator.evaluate(board);
+            assert!(
+                (eval - estimate).abs() <= margin,
+                "{}: eval {eval}, estimate {estimate}, margin {margin}",
+                board.to_fen()
+            );
+            bounded += 1;
+        }
+        assert!(bounded > boards.len() / 2);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::game::{
+    bit_manipulation::{north, south},
+    board::move_gen::{attacks::ATTACK_PATTERN_KING, attacks_bishop, attacks_knight, attacks_rook},
+    Board, Color, PieceType, Square,
+};
+
+use super::{
+    attack_maps::AttackMaps,
+    eval::Eval,
+    masks::{FILE_MASKS, FORWARD_FILE_MASKS},
+    max_weight, non_pawn_material,
+    params::EvalParams,
+    piece_square_table::W,
+    END_GAME_MATERIAL_START,
+};
+
+/// Own pawn in front of the king, indexed by the distance to the king rank (0 = no pawn in front)
+pub(super) const PAWN_SHIELD: [W; 4] = [W(-25, 0), W(15, 0), W(6, 0), W(-5, 0)];
+/// Enemy pawn advancing towards the king, indexed by the distance to the king rank (0 = no pawn in front)
+/// A pawn directly in front of the king is blocked by it and not dangerous
+pub(super) const PAWN_STORM: [W; 5] = [W(0, 0), W(0, 0), W(-30, 0), W(-15, 0), W(-5, 0)];
+
+/// File near the king without own pawns
+pub(super) const SEMI_OPEN_FILE_NEAR_KING: W = W(-15, 0);
+/// File near the king without any pawns
+pub(super) const OPEN_FILE_NEAR_KING: W = W(-25, -5);
+
+/// Attack units for each attacked square in the king zone indexed by piece type
+const ATTACK_WEIGHT: [Eval; 6] = [0, 2, 2, 3, 5, 0];
+/// Attack units if a safe check is available indexed by piece type
+const SAFE_CHECK_WEIGHT: [Eval; 6] = [0, 3, 2, 4, 6, 0];
+/// Attack units for each king zone square which is attacked twice and only defended by the king
+const WEAK_ZONE_SQUARE_WEIGHT: Eval = 1;
+
+/// Maximum number of attack units considered, more units don't increase the danger any further
+const MAX_ATTACK_UNITS: usize = 50;
+/// King danger of the maximum number of attack units in centipawns
+const MAX_KING_DANGER: Eval = 500;
+/// King danger indexed by the attack units, grows quadratic until the maximum is reached
+const KING_DANGER: [Eval; MAX_ATTACK_UNITS + 1] = calculate_king_danger_table();
+
+const fn calculate_king_danger_table() -> [Eval; MAX_ATTACK_UNITS + 1] {
+    let mut table = [0; MAX_ATTACK_UNITS + 1];
+    let mut units = 0;
+    while units <= MAX_ATTACK_UNITS {
+        table[units] = MAX_KING_DANGER * (units * units) as Eval
+            / (MAX_ATTACK_UNITS * MAX_ATTACK_UNITS) as Eval;
+        units += 1;
+    }
+    table
+}
+
+#[inline(always)]
+/// Squares around the king and one more rank towards the enemy
+fn king_zone(king_sq: Square, color: Color) -> u64 {
+    let zone = ATTACK_PATTERN_KING[king_sq] | king_sq.to_mask();
+    match color {
+        Color::White => zone | north(zone, 1),
+        Color::Black => zone | south(zone, 1),
+    }
+}
+
+#[inline(always)]
+/// King danger of the attack units, scaled down with the non pawn material of the attacker.
+/// Without pieces left to join the attack the king can't be mated anymore.
+fn king_danger(attack_units: Eval, attacker_material: Eval) -> Eval {
+    let danger = KING_DANGER[(attack_units.max(0) as usize).min(MAX_ATTACK_UNITS)];
+    danger * attacker_material.min(END_GAME_MATERIAL_START) / END_GAME_MATERIAL_START
+}
+
+/// #### King safety evaluation
+/// Attack unit based king safety model, returns the weighted scores indexed by color.
+/// The following terms are considered:
+/// - Pawn shield and pawn storm on the king file and the adjacent files
+/// - Open and semi open files near the king
+/// - Weighted number of attacked king zone squares by each attacking piece
+/// - Safe checks available for the enemy
+///
+/// Attack units are only turned into a penalty if at least two pieces attack the king zone
+/// (or a queen is involved), a single attacker can rarely harm the king.
+/// The penalty is tapered by the non pawn material of the attacker instead of the game phase.
+pub(super) fn evaluate_king_safety(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+) -> [W; 2] {
+    [
+        evaluate_king_safety_for(board, attacks, params, Color::White),
+        evaluate_king_safety_for(board, attacks, params, Color::Black),
+    ]
+}
+
+fn evaluate_king_safety_for(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+    color: Color,
+) -> W {
+    let enemy = color.opposite();
+    let king_sq = board.get_king_pos(color);
+
+    let mut score = evaluate_pawn_shelter(board, params, king_sq, color);
+
+    let zone = king_zone(king_sq, color);
+    let occupied = *board.get_bb_all_occupied();
+    let enemy_occupied = *board.get_bb_occupied(enemy);
+
+    let mut attack_units = 0;
+    let mut attackers = 0;
+    let mut queen_attack = false;
+    for piece_type in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ] {
+        for sq in board.get_piece_positions(piece_type.as_colored_piece(enemy)) {
+            let piece_attacks = match piece_type {
+                PieceType::Knight => attacks_knight(sq, 0),
+                PieceType::Bishop => attacks_bishop(sq, occupied, 0),
+                PieceType::Rook => attacks_rook(sq, occupied, 0),
+                _ => attacks_bishop(sq, occupied, 0) | attacks_rook(sq, occupied, 0),
+            };
+
+            let zone_attacks = (piece_attacks & zone).count_ones() as Eval;
+            if zone_attacks > 0 {
+                attackers += 1;
+                attack_units += zone_attacks * ATTACK_WEIGHT[piece_type];
+                queen_attack |= piece_type == PieceType::Queen;
+            }
+        }
+    }
+
+    //squares from which a piece would give check, a check is safe if the square is not defended
+    let safe = !attacks.all[color] & !enemy_occupied;
+    let knight_checks = attacks_knight(king_sq, 0);
+    let bishop_checks = attacks_bishop(king_sq, occupied, 0);
+    let rook_checks = attacks_rook(king_sq, occupied, 0);
+    let safe_checks = [
+        (PieceType::Knight, knight_checks),
+        (PieceType::Bishop, bishop_checks),
+        (PieceType::Rook, rook_checks),
+        (PieceType::Queen, bishop_checks | rook_checks),
+    ];
+    for (piece_type, check_squares) in safe_checks {
+        if check_squares & attacks.by_piece[enemy][piece_type] & safe != 0 {
+            attack_units += SAFE_CHECK_WEIGHT[piece_type];
+        }
+    }
+
+    //zone squares attacked twice and only defended by the king
+    let defended_by_pieces = attacks.all[color] & !attacks.by_piece[color][PieceType::King];
+    let weak = zone & attacks.double[enemy] & !defended_by_pieces;
+    attack_units += weak.count_ones() as Eval * WEAK_ZONE_SQUARE_WEIGHT;
+
+    if attackers >= 2 || queen_attack {
+        //same mid and end game weight, so the phase of the defender doesn't taper it again
+        let danger = king_danger(attack_units, non_pawn_material(board, params, enemy));
+        score -= W(danger, danger);
+    }
+
+    score
+}
+
+/// Evaluates the pawn shield, pawn storm and open files on the king file and the adjacent files
+fn evaluate_pawn_shelter(board: &Board, params: &EvalParams, king_sq: Square, color: Color) -> W {
+    let mut score = W::ZERO;
+
+    let own_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color));
+    let enemy_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color.opposite()));
+    let in_front = FORWARD_FILE_MASKS[color][king_sq.square_value() as usize & !7];
+
+    //a king on the edge is sheltered by the same files as a king on the b or g file
+    let center_file = king_sq.file().clamp(1, 6) as usize;
+    for (file, file_mask) in FILE_MASKS.iter().enumerate().skip(center_file - 1).take(3) {
+        let own_on_file = own_pawns & file_mask;
+        let enemy_on_file = enemy_pawns & file_mask;
+
+        if own_on_file == 0 {
+            score += if enemy_on_file == 0 {
+                params.open_file_near_king
+            } else {
+                params.semi_open_file_near_king
+            };
+        }
+
+        let front_rank_mask = in_front << file;
+        let shield = closest_pawn_distance(own_on_file & front_rank_mask, king_sq, color);
+        score += params.pawn_shield[shield.min(params.pawn_shield.len() - 1)];
+
+        let storm = closest_pawn_distance(enemy_on_file & front_rank_mask, king_sq, color);
+        if storm < params.pawn_storm.len() {
+            score += params.pawn_storm[storm];
+        }
+    }
+
+    score
+}
+
+#[inline(always)]
+/// Rank distance of the closest pawn in front of the king, 0 if there is no pawn
+fn closest_pawn_distance(pawns: u64, king_sq: Square, color: Color) -> usize {
+    if pawns == 0 {
+        return 0;
+    }
+
+    let closest = match color {
+        Color::White => pawns.trailing_zeros(),
+        Color::Black => 63 - pawns.leading_zeros(),
+    };
+    Square::new(closest as u8).rank().abs_diff(king_sq.rank()) as usize
+}
+
+/// Upper bound of the king safety penalty of a color, the worst shelter on all three files and the maximum king danger
+pub(super) fn king_safety_bound(params: &EvalParams) -> Eval {
+    let file_bound = max_weight(&[params.open_file_near_king, params.semi_open_file_near_king])
+        + max_weight(&params.pawn_shield)
+        + max_weight(&params.pawn_storm);
+    3 * file_bound + MAX_KING_DANGER
+}
+
+mod test {
+    #[test]
+    fn test_king_danger() {
+        use super::{king_danger, END_GAME_MATERIAL_START, KING_DANGER, MAX_KING_DANGER};
+
+        assert!(KING_DANGER.windows(2).all(|danger| danger[0] <= danger[1]));
+        assert_eq!(KING_DANGER[0], 0);
+        assert_eq!(king_danger(1000, END_GAME_MATERIAL_START), MAX_KING_DANGER);
+        assert_eq!(
+            king_danger(1000, 2 * END_GAME_MATERIAL_START),
+            MAX_KING_DANGER
+        );
+        assert_eq!(
+            king_danger(1000, END_GAME_MATERIAL_START / 2),
+            MAX_KING_DANGER / 2
+        );
+        assert_eq!(king_danger(1000, 0), 0);
+    }
+
+    #[test]
+    fn test_king_safety() {
+        use super::{evaluate_king_safety, AttackMaps, EvalParams, MAX_KING_DANGER, W};
+        use crate::game::{Board, Color};
+
+        let params = EvalParams::default();
+        let king_safety = |fen: &str| {
+            let board = Board::from_fen(fen).unwrap();
+            evaluate_king_safety(&board, &AttackMaps::from_board(&board), &params)
+        };
+
+        //pawn shield in front of the king
+        let sheltered = king_safety("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+        let open = king_safety("6k1/5ppp/8/8/8/8/8/6K1 w - - 0 1");
+        assert_eq!(sheltered[Color::White], sheltered[Color::Black]);
+        assert!(sheltered[Color::White].0 > open[Color::White].0);
+
+        //queen and knight attacking the king zone, the danger fades with the material of the attacker
+        let attack = king_safety("r1b3k1/5ppp/8/8/6nq/8/5PPP/5RK1 w - - 0 1");
+        let attack_without_support = king_safety("6k1/5ppp/8/8/6nq/8/5PPP/5RK1 w - - 0 1");
+        let quiet = king_safety("r1b3k1/5ppp/8/8/8/2n5/5PPP/q4RK1 w - - 0 1");
+        let danger = attack[Color::White] - quiet[Color::White];
+        assert!(danger.0 < 0);
+        assert_eq!(danger.0, danger.1);
+        assert!(attack[Color::White].0 < attack_without_support[Color::White].0);
+
+        //the danger is capped, even if every piece attacks the king
+        let mating_attack = king_safety("1r4k1/8/8/4b3/3b2nq/5n1r/8/6K1 w - - 0 1");
+        let bare_king = king_safety("6k1/8/8/8/8/8/8/6K1 w - - 0 1");
+        assert_eq!(
+            mating_attack[Color::White] - bare_king[Color::White],
+            W(-MAX_KING_DANGER, -MAX_KING_DANGER)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use lazy_static::lazy_static;
+
+use crate::game::{
+    board::move_gen::attacks::{ATTACK_PATTERN_KING, ATTACK_PATTERN_PAWN},
+    Color, Square,
+};
+
+/*
+    King and pawn vs king bitbase
+    The strong side is normalized to white with the pawn on the files a to d,
+    every position is indexed by the side to move, both king squares and the pawn square (ranks 2 to 7).
+    The bitbase is generated by retrograde analysis when it is first used (or at startup by the init function).
+*/
+
+/// Pawn squares on the files a to d and the ranks 2 to 7
+const PAWN_SQUARES: usize = 4 * 6;
+const SIZE: usize = 2 * 64 * 64 * PAWN_SQUARES;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+lazy_static! {
+    /// One bit per position, set if white wins
+    static ref KPK_BITBASE: Vec<u64> = generate();
+}
+
+pub fn lazy_static_kpk_bitbase_init() {
+    lazy_static::initialize(&KPK_BITBASE);
+}
+
+#[inline(always)]
+fn index(white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> usize {
+    let pawn_index = (pawn / 8 - 1) as usize * 4 + (pawn % 8) as usize;
+    (!white_to_move) as usize
+        | (black_king as usize) << 1
+        | (white_king as usize) << 7
+        | pawn_index << 13
+}
+
+/// Returns true if the strong side wins the king and pawn vs king position
+pub(super) fn probe(
+    strong_side: Color,
+    strong_king: Square,
+    strong_pawn: Square,
+    weak_king: Square,
+    side_to_move: Color,
+) -> bool {
+    let mut squares = [
+        strong_king.square_value(),
+        strong_pawn.square_value(),
+        weak_king.square_value(),
+    ];
+
+    //normalize the strong side to white and the pawn to the files a to d
+    if strong_side == Color::Black {
+        squares.iter_mut().for_each(|sq| *sq ^= 56);
+    }
+    if squares[1] % 8 >= 4 {
+        squares.iter_mut().for_each(|sq| *sq ^= 7);
+    }
+    //pawns on the back ranks are not part of the bitbase
+    if !(8..56).contains(&squares[1]) {
+        return false;
+    }
+
+    let i = index(
+        side_to_move == strong_side,
+        squares[0],
+        squares[2],
+        squares[1],
+    );
+    KPK_BITBASE[i / 64] & (1 << (i % 64)) != 0
+}
+
+fn generate() -> Vec<u64> {
+    let mut results = vec![INVALID; SIZE];
+    let mut positions = Vec::with_capacity(SIZE);
+
+    for pawn_index in 0..PAWN_SQUARES as u8 {
+        let pawn = (pawn_index / 4 + 1) * 8 + pawn_index % 4;
+        for white_king in 0..64 {
+            for black_king in 0..64 {
+                for white_to_move in [true, false] {
+                    let result = initial_result(white_to_move, white_king, black_king, pawn);
+                    results[index(white_to_move, white_king, black_king, pawn)] = result;
+                    if result == UNKNOWN {
+                        positions.push((white_to_move, white_king, black_king, pawn));
+                    }
+                }
+            }
+        }
+    }
+
+    //iterate until no unknown position can be resolved anymore, the remaining positions are draws
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(white_to_move, white_king, black_king, pawn) in positions.iter() {
+            let i = index(white_to_move, white_king, black_king, pawn);
+            if results[i] != UNKNOWN {
+                continue;
+            }
+            let result = classify(&results, white_to_move, white_king, black_king, pawn);
+            if result != UNKNOWN {
+                results[i] = result;
+                changed = true;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0u64; SIZE / 64];
+    for (i, result) in results.iter().enumerate() {
+        if *result == WIN {
+            bitbase[i / 64] |= 1 << (i % 64);
+        }
+    }
+    bitbase
+}
+
+/// Result of the position which can be decided without looking at the successors
+fn initial_result(white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> u8 {
+    let distance = |a: u8, b: u8| Square::new(a).distance(Square::new(b));
+    let king_attacks = |sq: u8| ATTACK_PATTERN_KING[sq as usize];
+    let pawn_attacks = ATTACK_PATTERN_PAWN[Color::White][pawn as usize];
+    let push = pawn + 8;
+
+    if distance(white_king, black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (white_to_move && pawn_attacks & (1 << black_king) != 0)
+    {
+        return INVALID;
+    }
+
+    //the pawn can promote without being captured
+    if white_to_move
+        && pawn / 8 == 6
+        && white_king != push
+        && black_king != push
+        && (distance(black_king, push) > 1 || distance(white_king, push) == 1)
+    {
+        return WIN;
+    }
+
+    //stalemate or the pawn can be captured
+    if !white_to_move
+        && (king_attacks(black_king) & !(king_attacks(white_king) | pawn_attacks) == 0
+            || king_attacks(black_king) & !king_attacks(white_king) & (1 << pawn) != 0)
+    {
+        return DRAW;
+    }
+
+    UNKNOWN
+}
+
+/// Combines the results of all successors, a position is won for white if any white move wins
+/// and drawn for black if any black move draws. Illegal successors are invalid and ignored.
+fn classify(results: &[u8], white_to_move: bool, white_king: u8, black_king: u8, pawn: u8) -> u8 {
+    let mut successors = 0;
+
+    if white_to_move {
+        let mut moves = ATTACK_PATTERN_KING[white_king as usize];
+        while moves != 0 {
+            let to = moves.trailing_zeros() as u8;
+            moves &= moves - 1;
+            successors |= results[index(false, to, black_king, pawn)];
+        }
+
+        //pushes to the promotion rank are handled by the initial results
+        if pawn / 8 < 6 {
+            successors |= results[index(false, white_king, black_king, pawn + 8)];
+        }
+        if pawn / 8 == 1 && pawn + 8 != white_king && pawn + 8 != black_king {
+            successors |= results[index(false, white_king, black_king, pawn + 16)];
+        }
+    } else {
+        let mut moves = ATTACK_PATTERN_KING[black_king as usize];
+        while moves != 0 {
+            let to = moves.trailing_zeros() as u8;
+            moves &= moves - 1;
+            successors |= results[index(true, white_king, to, pawn)];
+        }
+    }
+
+    let (good, bad) = if white_to_move {
+        (WIN, DRAW)
+    } else {
+        (DRAW, WIN)
+    };
+    if successors & good != 0 {
+        good
+    } else if successors & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
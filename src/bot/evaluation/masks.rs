@@ -0,0 +1,113 @@
+use crate::game::{
+    bit_manipulation::{constants::A_FILE, north_east, north_west, south_east, south_west},
+    Color,
+};
+
+const fn calculate_file_masks() -> [u64; 8] {
+    let mut res = [0; 8];
+    let mut i = 0;
+    while i < 8 {
+        res[i] = A_FILE << i;
+        i += 1;
+    }
+    res
+}
+
+const fn calculate_adjacent_file_masks() -> [u64; 8] {
+    let mut res = [0; 8];
+    let mut i = 0;
+    while i < 8 {
+        if i > 0 {
+            res[i] |= FILE_MASKS[i - 1];
+        }
+        if i < 7 {
+            res[i] |= FILE_MASKS[i + 1];
+        }
+        i += 1;
+    }
+    res
+}
+
+/// Squares in front of the square (from the perspective of the color) on the same file
+const fn calculate_forward_file_masks(white: bool) -> [u64; 64] {
+    let mut res = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        let file_mask = FILE_MASKS[i % 8];
+        let rank = i / 8;
+        res[i] = if white {
+            if rank == 7 {
+                0
+            } else {
+                file_mask & (u64::MAX << ((rank + 1) * 8))
+            }
+        } else {
+            file_mask & !(u64::MAX << (rank * 8))
+        };
+        i += 1;
+    }
+    res
+}
+
+/// Squares in front of the square (from the perspective of the color) on the same and the adjacent files
+const fn calculate_passed_pawn_masks(white: bool) -> [u64; 64] {
+    let forward = calculate_forward_file_masks(white);
+    let mut res = [0; 64];
+    let mut i = 0;
+    while i < 64 {
+        res[i] = forward[i];
+        if i % 8 > 0 {
+            res[i] |= forward[i - 1];
+        }
+        if i % 8 < 7 {
+            res[i] |= forward[i + 1];
+        }
+        i += 1;
+    }
+    res
+}
+
+const fn calculate_dark_squares() -> u64 {
+    let mut res = 0;
+    let mut i = 0;
+    while i < 64 {
+        if (i / 8 + i % 8) % 2 == 0 {
+            res |= 1 << i;
+        }
+        i += 1;
+    }
+    res
+}
+
+pub(super) const FILE_MASKS: [u64; 8] = calculate_file_masks();
+pub(super) const ADJACENT_FILE_MASKS: [u64; 8] = calculate_adjacent_file_masks();
+
+const FORWARD_FILE_WHITE: [u64; 64] = calculate_forward_file_masks(true);
+const FORWARD_FILE_BLACK: [u64; 64] = calculate_forward_file_masks(false);
+/// Indexed by \[color]\[square]
+pub(super) const FORWARD_FILE_MASKS: [[u64; 64]; 2] = [FORWARD_FILE_WHITE, FORWARD_FILE_BLACK];
+
+const PASSED_PAWN_WHITE: [u64; 64] = calculate_passed_pawn_masks(true);
+const PASSED_PAWN_BLACK: [u64; 64] = calculate_passed_pawn_masks(false);
+/// Indexed by \[color]\[square]
+pub(super) const PASSED_PAWN_MASKS: [[u64; 64]; 2] = [PASSED_PAWN_WHITE, PASSED_PAWN_BLACK];
+
+pub(super) const DARK_SQUARES: u64 = calculate_dark_squares();
+
+#[inline(always)]
+/// All squares attacked by the given pawns of the color
+pub(super) fn pawn_attacks(pawns: u64, color: Color) -> u64 {
+    match color {
+        Color::White => north_east(pawns, 1) | north_west(pawns, 1),
+        Color::Black => south_east(pawns, 1) | south_west(pawns, 1),
+    }
+}
+
+#[inline(always)]
+/// Rank of the square seen from the perspective of the color (0 = own back rank)
+pub(super) fn relative_rank(rank: u8, color: Color) -> usize {
+    match color {
+        Color::White => rank as usize,
+        Color::Black => 7 - rank as usize,
+    }
+}
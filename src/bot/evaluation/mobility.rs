@@ -0,0 +1,271 @@
+use crate::game::{
+    board::move_gen::{
+        attacks_knight,
+        magic::{get_bishop_moves, get_rook_moves},
+    },
+    Board, Color, PieceType, Square,
+};
+
+use super::{
+    attack_maps::AttackMaps,
+    eval::Eval,
+    masks::{relative_rank, ADJACENT_FILE_MASKS, DARK_SQUARES, FILE_MASKS, PASSED_PAWN_MASKS},
+    max_weight,
+    params::EvalParams,
+    piece_square_table::W,
+};
+
+/*
+    Mobility weights indexed by the number of reachable squares in the mobility area
+    Values loosely based on the classical Stockfish evaluation, scaled to the PeSTO piece values
+*/
+
+#[rustfmt::skip]
+pub(super) const KNIGHT_MOBILITY: [W; 9] = [
+    W(-40, -50), W(-25, -30), W(-8, -15), W(-2, -5), W(4, 4), W(10, 8), W(15, 12), W(20, 15),
+    W(25, 18),
+];
+
+#[rustfmt::skip]
+pub(super) const BISHOP_MOBILITY: [W; 14] = [
+    W(-30, -40), W(-15, -20), W(0, -8), W(6, 0), W(12, 6), W(18, 12), W(22, 18), W(26, 22),
+    W(28, 26), W(32, 28), W(34, 32), W(38, 32), W(40, 36), W(44, 38),
+];
+
+#[rustfmt::skip]
+pub(super) const ROOK_MOBILITY: [W; 15] = [
+    W(-30, -50), W(-15, -20), W(-5, -5), W(-3, 5), W(-1, 15), W(2, 25), W(5, 35), W(8, 40),
+    W(12, 45), W(13, 50), W(14, 55), W(16, 60), W(18, 62), W(20, 64), W(22, 65),
+];
+
+#[rustfmt::skip]
+pub(super) const QUEEN_MOBILITY: [W; 28] = [
+    W(-20, -30), W(-12, -20), W(-4, -10), W(-2, -4), W(0, 2), W(2, 6), W(4, 10), W(6, 14),
+    W(8, 18), W(10, 22), W(11, 25), W(12, 28), W(13, 31), W(14, 34), W(15, 36), W(16, 38),
+    W(17, 40), W(18, 42), W(19, 44), W(20, 46), W(21, 48), W(22, 50), W(23, 52), W(24, 54),
+    W(25, 55), W(26, 56), W(27, 57), W(28, 58),
+];
+
+/// Rook on a file without any pawns
+pub(super) const ROOK_OPEN_FILE: W = W(20, 8);
+/// Rook on a file without own pawns
+pub(super) const ROOK_SEMI_OPEN_FILE: W = W(10, 5);
+/// Rook on the 7th rank, if the enemy king is on the 8th rank or enemy pawns are on the 7th rank
+pub(super) const ROOK_ON_SEVENTH: W = W(10, 25);
+/// Bishops on light and dark squares
+pub(super) const BISHOP_PAIR: W = W(30, 50);
+/// Knight on the 4th to 6th rank, supported by a pawn and can't be attacked by enemy pawns
+pub(super) const KNIGHT_OUTPOST: W = W(25, 15);
+/// Rook with low mobility trapped in the corner by the own uncastled king
+pub(super) const TRAPPED_ROOK: W = W(-40, -5);
+/// Bishop on a7/h7 (relative) trapped by an enemy pawn on b6/g6 (relative)
+pub(super) const TRAPPED_BISHOP: W = W(-60, -60);
+
+/// Mobility below this count is considered trapped for a rook in the corner
+const TRAPPED_ROOK_MAX_MOBILITY: u32 = 3;
+
+#[inline(always)]
+/// Mirrors the square for black, so that it can be compared to squares from whites perspective
+fn relative_square(square: Square, color: Color) -> Square {
+    match color {
+        Color::White => square,
+        Color::Black => Square::new(square.square_value() ^ 56),
+    }
+}
+
+#[inline(always)]
+/// Squares that are considered for the mobility of the color
+/// squares occupied by own pieces or attacked by enemy pawns are excluded
+fn mobility_area(board: &Board, attacks: &AttackMaps, color: Color) -> u64 {
+    !*board.get_bb_occupied(color) & !attacks.by_piece[color.opposite()][PieceType::Pawn]
+}
+
+/// #### Mobility evaluation
+/// Scores the number of squares each knight, bishop, rook and queen can reach in the mobility area,
+/// returns the weighted scores indexed by color.
+pub(super) fn evaluate_mobility(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+) -> [W; 2] {
+    [
+        evaluate_mobility_for(board, attacks, params, Color::White),
+        evaluate_mobility_for(board, attacks, params, Color::Black),
+    ]
+}
+
+fn evaluate_mobility_for(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+    color: Color,
+) -> W {
+    let mut score = W::ZERO;
+    let area = mobility_area(board, attacks, color);
+    let occupied = *board.get_bb_all_occupied();
+
+    for sq in board.get_piece_positions(PieceType::Knight.as_colored_piece(color)) {
+        let moves = attacks_knight(sq, 0) & area;
+        score += params.knight_mobility[moves.count_ones() as usize];
+    }
+    for sq in board.get_piece_positions(PieceType::Bishop.as_colored_piece(color)) {
+        let moves = *get_bishop_moves(sq, occupied) & area;
+        score += params.bishop_mobility[moves.count_ones() as usize];
+    }
+    for sq in board.get_piece_positions(PieceType::Rook.as_colored_piece(color)) {
+        let moves = *get_rook_moves(sq, occupied) & area;
+        score += params.rook_mobility[moves.count_ones() as usize];
+    }
+    for sq in board.get_piece_positions(PieceType::Queen.as_colored_piece(color)) {
+        let moves = (*get_bishop_moves(sq, occupied) | *get_rook_moves(sq, occupied)) & area;
+        score += params.queen_mobility[moves.count_ones() as usize];
+    }
+
+    score
+}
+
+/// #### Piece activity evaluation
+/// Evaluates the placement of the pieces beyond the piece square tables,
+/// returns the weighted scores indexed by color.
+/// The following terms are considered:
+/// - Rooks on open and semi open files and on the 7th rank
+/// - Bishop pair
+/// - Knight outposts
+/// - Trapped rooks and bishops
+pub(super) fn evaluate_activity(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+) -> [W; 2] {
+    [
+        evaluate_activity_for(board, attacks, params, Color::White),
+        evaluate_activity_for(board, attacks, params, Color::Black),
+    ]
+}
+
+fn evaluate_activity_for(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+    color: Color,
+) -> W {
+    let mut score = W::ZERO;
+    let enemy = color.opposite();
+    let own_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color));
+    let enemy_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(enemy));
+    let occupied = *board.get_bb_all_occupied();
+    let own_king = relative_square(board.get_king_pos(color), color);
+    let enemy_king = board.get_king_pos(enemy);
+
+    for sq in board.get_piece_positions(PieceType::Rook.as_colored_piece(color)) {
+        let file_mask = FILE_MASKS[sq.file() as usize];
+        if file_mask & own_pawns == 0 {
+            score += if file_mask & enemy_pawns == 0 {
+                params.rook_open_file
+            } else {
+                params.rook_semi_open_file
+            };
+        }
+
+        if relative_rank(sq.rank(), color) == 6 {
+            let enemy_pawns_on_seventh = enemy_pawns & (0xff << (sq.rank() * 8)) != 0;
+            if relative_rank(enemy_king.rank(), color) == 7 || enemy_pawns_on_seventh {
+                score += params.rook_on_seventh;
+            }
+        }
+
+        //rook trapped in the corner by the own king which has not castled
+        let relative_sq = relative_square(sq, color);
+        let king_side_trap = (own_king == Square::F1 || own_king == Square::G1)
+            && (relative_sq == Square::G1
+                || relative_sq == Square::H1
+                || relative_sq == Square::H2);
+        let queen_side_trap = (own_king == Square::C1 || own_king == Square::B1)
+            && (relative_sq == Square::B1
+                || relative_sq == Square::A1
+                || relative_sq == Square::A2);
+        if (king_side_trap || queen_side_trap)
+            && (*get_rook_moves(sq, occupied) & !*board.get_bb_occupied(color)).count_ones()
+                <= TRAPPED_ROOK_MAX_MOBILITY
+        {
+            score += params.trapped_rook;
+        }
+    }
+
+    //the bishops of a pair cover squares of both colors
+    let bishops = board.get_bb_for(PieceType::Bishop.as_colored_piece(color));
+    if *bishops & DARK_SQUARES != 0 && *bishops & !DARK_SQUARES != 0 {
+        score += params.bishop_pair;
+    }
+    for sq in bishops.get_occupied() {
+        let relative_sq = relative_square(sq, color);
+        let trapping_pawn = match relative_sq {
+            Square::A7 => Some(Square::B6),
+            Square::H7 => Some(Square::G6),
+            _ => None,
+        };
+        if trapping_pawn
+            .is_some_and(|pawn_sq| enemy_pawns & relative_square(pawn_sq, color).to_mask() != 0)
+        {
+            score += params.trapped_bishop;
+        }
+    }
+
+    let own_pawn_attacks = attacks.by_piece[color][PieceType::Pawn];
+    for sq in board.get_piece_positions(PieceType::Knight.as_colored_piece(color)) {
+        let rank = relative_rank(sq.rank(), color);
+        let can_be_attacked =
+            PASSED_PAWN_MASKS[color][sq] & ADJACENT_FILE_MASKS[sq.file() as usize] & enemy_pawns
+                != 0;
+
+        if (3..=5).contains(&rank) && own_pawn_attacks & sq.to_mask() != 0 && !can_be_attacked {
+            score += params.knight_outpost;
+        }
+    }
+
+    score
+}
+
+/// Upper bound of the mobility and activity score of the color, every piece gets at most the largest weight of each term
+pub(super) fn mobility_bound(board: &Board, params: &EvalParams, color: Color) -> Eval {
+    let count = |piece_type: PieceType| {
+        board
+            .get_bb_for(piece_type.as_colored_piece(color))
+            .count_ones() as Eval
+    };
+
+    let knight_bound = max_weight(&params.knight_mobility) + max_weight(&[params.knight_outpost]);
+    let bishop_bound = max_weight(&params.bishop_mobility) + max_weight(&[params.trapped_bishop]);
+    let rook_bound = max_weight(&params.rook_mobility)
+        + max_weight(&[params.rook_open_file, params.rook_semi_open_file])
+        + max_weight(&[params.rook_on_seventh])
+        + max_weight(&[params.trapped_rook]);
+    let queen_bound = max_weight(&params.queen_mobility);
+
+    count(PieceType::Knight) * knight_bound
+        + count(PieceType::Bishop) * bishop_bound
+        + count(PieceType::Rook) * rook_bound
+        + count(PieceType::Queen) * queen_bound
+        + max_weight(&[params.bishop_pair])
+}
+
+mod test {
+    #[test]
+    fn test_bishop_pair() {
+        use super::{evaluate_activity, AttackMaps, EvalParams, W};
+        use crate::game::{Board, Color};
+
+        let params = EvalParams::default();
+        let activity = |fen: &str| {
+            let board = Board::from_fen(fen).unwrap();
+            evaluate_activity(&board, &AttackMaps::from_board(&board), &params)[Color::White]
+        };
+
+        assert_eq!(
+            activity("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"),
+            params.bishop_pair
+        );
+        //two bishops on dark squares (e.g. after a promotion) are no pair
+        assert_eq!(activity("4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1"), W::ZERO);
+    }
+}
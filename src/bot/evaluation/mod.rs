@@ -1,23 +1,155 @@
+mod antichess;
+mod attack_maps;
+mod endgame;
 pub mod eval;
-mod piece_square_table;
+pub mod evaluator;
+mod king_safety;
+mod kpk;
+mod masks;
+mod mobility;
+pub mod nnue;
+pub mod params;
+mod pawn_structure;
+pub mod symmetry;
+pub mod trace;
+pub use crate::game::piece_square_table::W;
+use attack_maps::AttackMaps;
+use endgame::Endgame;
 use eval::Eval;
-use piece_square_table::read_p_sq_table;
+pub(crate) use kpk::lazy_static_kpk_bitbase_init;
+use params::EvalParams;
+use trace::EvalTrace;
 
-use crate::game::{bit_manipulation::iter_set_bits, Color, PieceType, Square};
+use crate::game::{bit_manipulation::iter_set_bits, piece_square_table, Color, PieceType};
 
 use super::Board;
 
 /// Evaluates the board state and returns a score in the perspective of the side to move.
-pub fn evaluate_board(board: &Board) -> Eval {
+pub fn evaluate_board(board: &Board, params: &EvalParams) -> Eval {
+    evaluate(board, params, None)
+}
+
+/// Evaluates the board state like [`evaluate_board`] and records every evaluation term
+pub fn trace_board(board: &Board, params: &EvalParams) -> EvalTrace {
+    let mut trace = EvalTrace {
+        side_to_move: board.side_to_move(),
+        ..Default::default()
+    };
+    trace.score =
+        evaluate(board, params, Some(&mut trace)) * board.side_to_move().perspective() as Eval;
+    trace
+}
+
+fn evaluate(board: &Board, params: &EvalParams, mut trace: Option<&mut EvalTrace>) -> Eval {
+    //without a royal king the goal of the game is inverted
+    if !board.variant().has_royal_king() {
+        return antichess::evaluate_antichess(board);
+    }
+
     let mut score = 0;
 
     let color = board.side_to_move();
+    let phase = Phase::from_board(board, params);
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.phase = [Color::White, Color::Black]
+            .map(|color| (phase.mid_game[color], phase.end_game[color]));
+        trace.scale_factor = endgame::SCALE_NORMAL;
+    }
 
-    score += evaluate_pieces(board);
+    if let Some(endgame) = Endgame::probe(board) {
+        if let Some(trace) = trace {
+            trace.endgame = Some(endgame.to_string());
+        }
+        return endgame.evaluate(board, params) * color.perspective() as Eval;
+    }
+
+    score += evaluate_pieces(board, params);
+    if let Some(trace) = trace.as_deref_mut() {
+        trace_pieces(board, params, &phase, trace);
+    }
+
+    //every term is added to the score and recorded in the trace
+    let mut add_term = |name: &'static str, scores: [W; 2]| {
+        let term_score = phase.taper_sides(&scores);
+        score += term_score;
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.add(name, scores, term_score);
+        }
+    };
+
+    add_term("Pawns", pawn_structure::evaluate_pawns(board, params));
+    if board.variant().has_drops() {
+        add_term("Pockets", pocket_material(board, params));
+    }
+
+    let attacks = AttackMaps::from_board(board);
+    add_term(
+        "King safety",
+        king_safety::evaluate_king_safety(board, &attacks, params),
+    );
+    add_term(
+        "Mobility",
+        mobility::evaluate_mobility(board, &attacks, params),
+    );
+    add_term(
+        "Activity",
+        mobility::evaluate_activity(board, &attacks, params),
+    );
+
+    let scale_factor = endgame::scale_factor(board, score);
+    if let Some(trace) = trace {
+        trace.scale_factor = scale_factor;
+    }
+    score = score * scale_factor / endgame::SCALE_NORMAL;
 
     score * color.perspective() as Eval
 }
 
+/// Evaluates only the material and piece square tables in the perspective of the side to move.
+pub fn evaluate_board_pst(board: &Board, params: &EvalParams) -> Eval {
+    evaluate_pieces(board, params) * board.side_to_move().perspective() as Eval
+}
+
+/// Material and piece square table estimate of [`evaluate_board`] and the maximum error of the estimate,
+/// the error is bounded by the largest weights the pieces of both colors can get in the other terms.
+/// None if the estimate can't be bounded, e.g. the position has a specialized endgame evaluation,
+/// drawish material which is scaled down or pieces in the pockets
+pub(super) fn estimate_board(board: &Board, params: &EvalParams) -> Option<(Eval, Eval)> {
+    if !board.variant().has_royal_king()
+        || board.variant().has_drops()
+        || Endgame::probe(board).is_some()
+    {
+        return None;
+    }
+    //the scale factor depends on the side the score favors, which the estimate can't predict
+    if endgame::scale_factor(board, 1) != endgame::SCALE_NORMAL
+        || endgame::scale_factor(board, -1) != endgame::SCALE_NORMAL
+    {
+        return None;
+    }
+
+    let margin = [Color::White, Color::Black]
+        .into_iter()
+        .map(|color| {
+            pawn_structure::pawn_structure_bound(board, params, color)
+                + king_safety::king_safety_bound(params)
+                + mobility::mobility_bound(board, params, color)
+        })
+        .sum();
+    let estimate = evaluate_pieces(board, params) * board.side_to_move().perspective() as Eval;
+    Some((estimate, margin))
+}
+
+#[inline(always)]
+/// Largest absolute weight, no phase can weight any of the weights to a larger score
+fn max_weight(weights: &[W]) -> Eval {
+    weights
+        .iter()
+        .map(|w| w.0.abs().max(w.1.abs()))
+        .max()
+        .unwrap_or(0)
+}
+
 ///  Rook + Bishop + Knight + Queen:
 const END_GAME_MATERIAL_START: Eval = 2050;
 
@@ -25,50 +157,115 @@ fn calc_endgame_factor(sum: i32) -> f32 {
     1_f32 - 1_f32.min(sum as f32 / END_GAME_MATERIAL_START as f32)
 }
 
+/// Non pawn material of the color weighted with the mid game piece values of the parameters,
+/// so that the phase follows the material weights while they are tuned
+fn non_pawn_material(board: &Board, params: &EvalParams, color: Color) -> Eval {
+    if params.incremental_psqt {
+        return board.non_pawn_material(color);
+    }
+
+    let bb = board.get_bb_pieces();
+    [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .into_iter()
+    .map(|piece_type| {
+        params.piece_values[piece_type].0 * bb[color][piece_type].count_ones() as Eval
+    })
+    .sum()
+}
+
+/// Mid game and end game factors for each color, derived from the remaining non pawn material of the color
+struct Phase {
+    mid_game: [f32; 2],
+    end_game: [f32; 2],
+}
+
+impl Phase {
+    fn from_board(board: &Board, params: &EvalParams) -> Phase {
+        let end_game = [
+            calc_endgame_factor(non_pawn_material(board, params, Color::White)),
+            calc_endgame_factor(non_pawn_material(board, params, Color::Black)),
+        ];
+        Phase {
+            mid_game: [1_f32 - end_game[0], 1_f32 - end_game[1]],
+            end_game,
+        }
+    }
+
+    #[inline(always)]
+    /// Weights the score of the color with the phase factors of the color
+    fn taper(&self, w: &W, color: Color) -> Eval {
+        w.weight(self.mid_game[color], self.end_game[color])
+    }
+
+    #[inline(always)]
+    /// Weights the scores of both colors (indexed by color) and returns the score from whites perspective
+    fn taper_sides(&self, w: &[W; 2]) -> Eval {
+        self.taper(&w[Color::White], Color::White) - self.taper(&w[Color::Black], Color::Black)
+    }
+}
+
 #[inline(always)]
-fn evaluate_pieces(board: &Board) -> Eval {
-    let mut score = 0;
+fn evaluate_pieces(board: &Board, params: &EvalParams) -> Eval {
+    let scores = if params.incremental_psqt {
+        [
+            board.psqt_score(Color::White),
+            board.psqt_score(Color::Black),
+        ]
+    } else {
+        piece_square_scores(board, params)
+    };
 
-    let mut material_without_pawn = (0, 0);
-    Square::iter_ah_18().for_each(|sq| {
-        let piece = board.get_sq_piece(sq);
-        if let Some(piece) = piece {
-            let material = match piece.color() {
-                Color::White => &mut material_without_pawn.0,
-                Color::Black => &mut material_without_pawn.1,
-            };
-
-            *material += match piece.ptype() {
-                PieceType::Knight => piece_square_table::KNIGHT_MG,
-                PieceType::Bishop => piece_square_table::BISHOP_MG,
-                PieceType::Rook => piece_square_table::ROOK_MG,
-                PieceType::Queen => piece_square_table::QUEEN_MG,
-                _ => 0,
-            }
+    Phase::from_board(board, params).taper_sides(&scores)
+}
+
+/// Records the material and the piece square tables of [`evaluate_pieces`] as separate terms
+fn trace_pieces(board: &Board, params: &EvalParams, phase: &Phase, trace: &mut EvalTrace) {
+    let bb = board.get_bb_pieces();
+    let mut material = [W::ZERO; 2];
+    for color in [Color::White, Color::Black] {
+        for piece_type in PieceType::iter() {
+            material[color] +=
+                params.piece_values[piece_type] * bb[color][piece_type].count_ones() as Eval;
         }
-    });
+    }
+    let piece_squares = piece_square_scores(board, params);
+    let positional = [
+        piece_squares[Color::White] - material[Color::White],
+        piece_squares[Color::Black] - material[Color::Black],
+    ];
 
-    let endgame_factor = (
-        calc_endgame_factor(material_without_pawn.0),
-        calc_endgame_factor(material_without_pawn.1),
-    );
-    let mid_game_factor = (1_f32 - endgame_factor.0, 1_f32 - endgame_factor.1);
+    trace.add("Material", material, phase.taper_sides(&material));
+    trace.add("Piece squares", positional, phase.taper_sides(&positional));
+}
 
+/// Material of the pieces in the pockets indexed by color, they can be dropped on any empty square
+fn pocket_material(board: &Board, params: &EvalParams) -> [W; 2] {
+    let pockets = board.cur_state().pockets;
+    [Color::White, Color::Black].map(|color| {
+        let mut material = W::ZERO;
+        for piece_type in pockets.droppable(color) {
+            material += params.piece_values[piece_type] * pockets.count(color, piece_type) as Eval;
+        }
+        material
+    })
+}
+
+/// Material and piece square table scores indexed by color calculated from all pieces on the board
+fn piece_square_scores(board: &Board, params: &EvalParams) -> [W; 2] {
+    let mut scores = [W::ZERO; 2];
     let bb = board.get_bb_pieces();
-    for piece_type in PieceType::iter() {
-        let white_piece_board = bb[Color::White][piece_type];
-        let black_piece_board = bb[Color::Black][piece_type];
-
-        let piece = piece_type.as_colored_piece(Color::White);
-        iter_set_bits(*white_piece_board).for_each(|sq| {
-            let w = read_p_sq_table(piece, sq);
-            score += w.weight(mid_game_factor.0, endgame_factor.0);
-        });
-        let piece = piece_type.as_colored_piece(Color::Black);
-        iter_set_bits(*black_piece_board).for_each(|sq| {
-            let w = read_p_sq_table(piece, sq);
-            score -= w.weight(mid_game_factor.0, endgame_factor.0);
-        });
+    for color in [Color::White, Color::Black] {
+        for piece_type in PieceType::iter() {
+            let piece = piece_type.as_colored_piece(color);
+            iter_set_bits(*bb[color][piece_type]).for_each(|sq| {
+                scores[color] += params.piece_square(piece, sq);
+            });
+        }
     }
-    score
+    scores
 }
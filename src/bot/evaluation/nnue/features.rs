@@ -0,0 +1,64 @@
+use crate::game::{Color, PieceType, Square};
+
+/// #### Feature set
+/// Describes how a piece on the board is mapped to an input of the feature transformer.
+/// Both feature sets are relative to the king of the perspective, so every king move of the
+/// perspective requires a full refresh of its accumulator.
+/// - HalfKP: king square x (own/enemy non king piece) x square = 64 * 10 * 64 inputs
+/// - HalfKA: king square x (own/enemy piece incl. kings) x square = 64 * 12 * 64 inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureSet {
+    HalfKP,
+    HalfKA,
+}
+
+impl FeatureSet {
+    pub fn from_id(id: u32) -> Option<FeatureSet> {
+        match id {
+            0 => Some(FeatureSet::HalfKP),
+            1 => Some(FeatureSet::HalfKA),
+            _ => None,
+        }
+    }
+
+    /// Number of piece variations (type and relative color) per king square
+    const fn piece_count(&self) -> usize {
+        match self {
+            FeatureSet::HalfKP => 10,
+            FeatureSet::HalfKA => 12,
+        }
+    }
+
+    /// Number of inputs of the feature transformer
+    pub const fn input_size(&self) -> usize {
+        64 * self.piece_count() * 64
+    }
+
+    /// Returns true if the piece is an input of the feature set
+    pub fn is_feature(&self, piece_type: PieceType) -> bool {
+        match self {
+            FeatureSet::HalfKP => piece_type != PieceType::King,
+            FeatureSet::HalfKA => true,
+        }
+    }
+
+    #[inline(always)]
+    /// Index of the feature for the piece on the square seen from the perspective.
+    /// Squares are mirrored vertically for black, so that both perspectives share the weights.
+    pub fn index(
+        &self,
+        perspective: Color,
+        king_sq: Square,
+        piece_type: PieceType,
+        piece_color: Color,
+        sq: Square,
+    ) -> usize {
+        let orient = |sq: Square| match perspective {
+            Color::White => sq.square_value() as usize,
+            Color::Black => (sq.square_value() ^ 56) as usize,
+        };
+        let piece = piece_type as usize * 2 + (piece_color != perspective) as usize;
+
+        (orient(king_sq) * self.piece_count() + piece) * 64 + orient(sq)
+    }
+}
@@ -0,0 +1,293 @@
+mod features;
+mod network;
+mod simd;
+
+use std::sync::Arc;
+
+pub use network::Network;
+
+use network::LayerBuffers;
+use simd::{add_assign_i16, sub_assign_i16};
+
+use crate::game::{Board, Color, Move, PieceType, Square};
+
+use super::{
+    eval::Eval,
+    evaluator::{ClassicEvaluator, Evaluator},
+    params::EvalParams,
+};
+
+/// Feature transformer output of both perspectives indexed by color
+#[derive(Clone)]
+struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+/// A piece added to or removed from a square
+type FeatureChange = (PieceType, Color, Square);
+
+/// #### NNUE evaluator
+/// Evaluates the board with an efficiently updatable neural network.
+/// The accumulators are kept on a stack parallel to the moves made by the searcher,
+/// making a move copies the accumulator and applies the feature deltas of the move,
+/// undoing a move simply pops the accumulator.
+///
+/// Without a loaded network the classic evaluation with the given parameters is used.
+pub struct NNUEEvaluator {
+    network: Option<Arc<Network>>,
+    stack: Vec<Accumulator>,
+    current: usize,
+    buffers: Option<LayerBuffers>,
+    fallback: ClassicEvaluator,
+}
+
+impl NNUEEvaluator {
+    pub const NAME: &'static str = "NNUE";
+
+    pub fn new(network: Option<Arc<Network>>, params: Arc<EvalParams>) -> Self {
+        let buffers = network.as_ref().map(|network| network.create_buffers());
+        Self {
+            network,
+            stack: Vec::new(),
+            current: 0,
+            buffers,
+            fallback: ClassicEvaluator(params),
+        }
+    }
+
+    /// Recalculates the accumulator of the perspective from all pieces on the board
+    fn refresh(&mut self, board: &Board, perspective: Color) {
+        let Some(network) = &self.network else {
+            return;
+        };
+        let feature_set = network.feature_set();
+        let king_sq = board.get_king_pos(perspective);
+
+        let values = &mut self.stack[self.current].values[perspective];
+        values.copy_from_slice(network.ft_biases());
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceType::iter().filter(|p| feature_set.is_feature(*p)) {
+                for sq in board.get_piece_positions(piece_type.as_colored_piece(color)) {
+                    let feature = feature_set.index(perspective, king_sq, piece_type, color, sq);
+                    add_assign_i16(values, network.ft_weights(feature));
+                }
+            }
+        }
+    }
+
+    /// Pushes a copy of the current accumulator onto the stack
+    fn push(&mut self) {
+        if self.current + 1 == self.stack.len() {
+            let copy = self.stack[self.current].clone();
+            self.stack.push(copy);
+        } else {
+            let (done, next) = self.stack.split_at_mut(self.current + 1);
+            for color in [Color::White, Color::Black] {
+                next[0].values[color].copy_from_slice(&done[self.current].values[color]);
+            }
+        }
+        self.current += 1;
+    }
+
+    /// Applies the added and removed pieces of the move to the accumulator of the perspective
+    fn apply_changes(
+        &mut self,
+        board: &Board,
+        perspective: Color,
+        added: &[Option<FeatureChange>],
+        removed: &[Option<FeatureChange>],
+    ) {
+        let Some(network) = &self.network else {
+            return;
+        };
+        let feature_set = network.feature_set();
+        let king_sq = board.get_king_pos(perspective);
+        let values = &mut self.stack[self.current].values[perspective];
+
+        for &(piece_type, color, sq) in added.iter().flatten() {
+            if feature_set.is_feature(piece_type) {
+                let feature = feature_set.index(perspective, king_sq, piece_type, color, sq);
+                add_assign_i16(values, network.ft_weights(feature));
+            }
+        }
+        for &(piece_type, color, sq) in removed.iter().flatten() {
+            if feature_set.is_feature(piece_type) {
+                let feature = feature_set.index(perspective, king_sq, piece_type, color, sq);
+                sub_assign_i16(values, network.ft_weights(feature));
+            }
+        }
+    }
+}
+
+impl Evaluator for NNUEEvaluator {
+    fn name(&self) -> &'static str {
+        NNUEEvaluator::NAME
+    }
+
+    fn evaluate(&mut self, board: &Board) -> Eval {
+        let (Some(network), Some(buffers)) = (&self.network, &mut self.buffers) else {
+            return self.fallback.evaluate(board);
+        };
+
+        let accumulator = &self.stack[self.current];
+        let us = board.side_to_move();
+        network.evaluate(
+            &accumulator.values[us],
+            &accumulator.values[us.opposite()],
+            buffers,
+        )
+    }
+
+    fn on_new_position(&mut self, board: &Board) {
+        let Some(network) = &self.network else {
+            return;
+        };
+        if self.stack.is_empty() {
+            let values = vec![0; network.accumulator_size()];
+            self.stack.push(Accumulator {
+                values: [values.clone(), values],
+            });
+        }
+
+        self.current = 0;
+        self.refresh(board, Color::White);
+        self.refresh(board, Color::Black);
+    }
+
+    fn on_make_move(&mut self, board: &Board, mov: &Move) {
+        if self.network.is_none() {
+            return;
+        }
+        self.push();
+        if mov.is_null() {
+            return;
+        }
+
+        let source = mov.source();
+        let dest = mov.dest();
+        let flag = mov.flag();
+        let mover = board.side_to_move().opposite();
+        let moved_to = board
+            .get_sq_piece_variation(dest)
+            .expect("The moved piece must be on the destination square");
+        let moved_from = if flag.is_promotion() {
+            PieceType::Pawn
+        } else {
+            moved_to
+        };
+
+        let mut added = [Some((moved_to, mover, dest)), None];
+        //drops only add the piece to the board
+        let mut removed = [
+            (!flag.is_drop()).then_some((moved_from, mover, source)),
+            None,
+        ];
+
+        if let Some(captured) = board.cur_state().captured_piece {
+            let captured_sq = if flag.is_en_passant() {
+                dest - mover.perspective() * 8
+            } else {
+                dest
+            };
+            removed[1] = Some((captured, mover.opposite(), captured_sq));
+        }
+
+        if let Some(castle_type) = flag.castle_side() {
+            let (rook_source, rook_dest) = board
+                .cur_state()
+                .castling_rights
+                .rook_positions(mover, castle_type);
+            removed[1] = Some((PieceType::Rook, mover, rook_source));
+            added[1] = Some((PieceType::Rook, mover, rook_dest));
+        }
+
+        for perspective in [Color::White, Color::Black] {
+            if moved_to == PieceType::King && perspective == mover {
+                self.refresh(board, perspective);
+            } else {
+                self.apply_changes(board, perspective, &added, &removed);
+            }
+        }
+    }
+
+    fn on_undo_move(&mut self, _board: &Board, _mov: &Move) {
+        if self.network.is_some() {
+            self.current -= 1;
+        }
+    }
+
+    fn lazy_bound(&mut self, board: &Board) -> Option<(Eval, Eval)> {
+        match self.network {
+            Some(_) => None,
+            None => self.fallback.lazy_bound(board),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_incremental_accumulator() {
+        use super::{features::FeatureSet, NNUEEvaluator, Network};
+        use crate::bot::evaluation::evaluator::Evaluator;
+        use crate::bot::evaluation::params::EvalParams;
+        use crate::game::{board::move_gen::MoveGeneration, Board, Color};
+        use rand::{Rng, SeedableRng};
+        use std::sync::Arc;
+
+        const L1: usize = 32;
+        const L2: usize = 8;
+        const L3: usize = 8;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for feature_set in [FeatureSet::HalfKP, FeatureSet::HalfKA] {
+            let mut bytes = b"CENN".to_vec();
+            for header in [1, feature_set as u32, L1 as u32, L2 as u32, L3 as u32] {
+                bytes.extend_from_slice(&header.to_le_bytes());
+            }
+            for _ in 0..L1 * (feature_set.input_size() + 1) {
+                bytes.extend_from_slice(&rng.gen_range(-64i16..64).to_le_bytes());
+            }
+            for (inputs, outputs) in [(2 * L1, L2), (L2, L3), (L3, 1)] {
+                for _ in 0..outputs {
+                    bytes.extend_from_slice(&rng.gen_range(-1000i32..1000).to_le_bytes());
+                }
+                bytes.extend((0..inputs * outputs).map(|_| rng.gen::<i8>() as u8));
+            }
+            let network = Arc::new(Network::from_reader(&mut bytes.as_slice()).unwrap());
+
+            let mut board = Board::from_fen(
+                "r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPpP/R3K2R w KQkq - 0 1",
+            )
+            .unwrap();
+            let params = Arc::new(EvalParams::default());
+            let mut evaluator = NNUEEvaluator::new(Some(network.clone()), params.clone());
+            let mut reference = NNUEEvaluator::new(Some(network), params);
+            evaluator.on_new_position(&board);
+
+            let mut played = Vec::new();
+            for _ in 0..40 {
+                let moves = MoveGeneration::generate_legal_moves(&board);
+                if moves.is_empty() {
+                    break;
+                }
+                let mov = moves.get(rng.gen_range(0..moves.len())).unwrap();
+                board.make_move(&mov, true, false).unwrap();
+                evaluator.on_make_move(&board, &mov);
+                played.push(mov);
+
+                reference.on_new_position(&board);
+                let current = &evaluator.stack[evaluator.current];
+                for color in [Color::White, Color::Black] {
+                    assert_eq!(current.values[color], reference.stack[0].values[color]);
+                }
+                assert_eq!(evaluator.evaluate(&board), reference.evaluate(&board));
+            }
+
+            for mov in played.iter().rev() {
+                board.undo_move(mov, true).unwrap();
+                evaluator.on_undo_move(&board, mov);
+            }
+            assert_eq!(evaluator.current, 0);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::Read;
+
+use thiserror::Error;
+
+use crate::bot::evaluation::eval::Eval;
+
+use super::{
+    features::FeatureSet,
+    simd::{clipped_relu_i16, clipped_relu_i32, dot_u8_i8},
+};
+
+/// Magic bytes at the start of every network file
+const NETWORK_MAGIC: &[u8; 4] = b"CENN";
+/// Supported version of the network file format
+const NETWORK_VERSION: u32 = 1;
+
+/// The hidden layer outputs are scaled by 2^WEIGHT_SCALE_BITS
+const WEIGHT_SCALE_BITS: u32 = 6;
+/// The network output divided by this factor is the evaluation in centipawns
+const OUTPUT_SCALE: i32 = 16;
+/// Upper limit for layer sizes, protects against allocating huge buffers for corrupt files
+const MAX_LAYER_SIZE: usize = 4096;
+
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Failed to read the network file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Not a network file, invalid magic bytes")]
+    InvalidMagic,
+    #[error("Unsupported network version {0}, expected {NETWORK_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Unknown feature set id {0}")]
+    UnknownFeatureSet(u32),
+    #[error("Invalid layer size {0}")]
+    InvalidLayerSize(u32),
+    #[error("Unexpected data after the end of the network")]
+    TrailingData,
+}
+
+/// #### Network
+/// Quantized NNUE network with the architecture
+/// (feature transformer -> L1) x 2 -> L2 -> L3 -> 1
+/// - The feature transformer has int16 weights and is updated incrementally in the accumulators
+/// - The accumulators of both perspectives (side to move first) are clipped to \[0, 127]
+///   and fed into the dense layers with int8 weights and int32 biases
+///
+/// #### File format (little endian)
+/// ```text
+/// magic "CENN" | version u32 | feature set u32 (0 = HalfKP, 1 = HalfKA) | L1 u32 | L2 u32 | L3 u32
+/// feature transformer: biases i16 x L1 | weights i16 x inputs x L1 (input major)
+/// hidden layer 1:      biases i32 x L2 | weights i8 x L2 x 2*L1 (output major)
+/// hidden layer 2:      biases i32 x L3 | weights i8 x L3 x L2
+/// output layer:        bias i32        | weights i8 x L3
+/// ```
+pub struct Network {
+    feature_set: FeatureSet,
+    ft_biases: Vec<i16>,
+    ft_weights: Vec<i16>,
+    l1: DenseLayer,
+    l2: DenseLayer,
+    out: DenseLayer,
+}
+
+/// Fully connected layer with int8 weights stored output major
+struct DenseLayer {
+    inputs: usize,
+    biases: Vec<i32>,
+    weights: Vec<i8>,
+}
+
+impl DenseLayer {
+    fn read(reader: &mut impl Read, inputs: usize, outputs: usize) -> Result<Self, NetworkError> {
+        Ok(DenseLayer {
+            inputs,
+            biases: read_i32s(reader, outputs)?,
+            weights: read_i8s(reader, inputs * outputs)?,
+        })
+    }
+
+    #[inline(always)]
+    fn forward(&self, input: &[u8], output: &mut [i32]) {
+        for (i, out) in output.iter_mut().enumerate() {
+            let weights = &self.weights[i * self.inputs..(i + 1) * self.inputs];
+            *out = self.biases[i] + dot_u8_i8(input, weights);
+        }
+    }
+
+    fn outputs(&self) -> usize {
+        self.biases.len()
+    }
+}
+
+impl Network {
+    pub fn from_file(path: &str) -> Result<Network, NetworkError> {
+        let file = std::fs::File::open(path)?;
+        Network::from_reader(&mut std::io::BufReader::new(file))
+    }
+
+    pub fn from_reader(reader: &mut impl Read) -> Result<Network, NetworkError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != NETWORK_MAGIC {
+            return Err(NetworkError::InvalidMagic);
+        }
+
+        let version = read_u32(reader)?;
+        if version != NETWORK_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+
+        let feature_set_id = read_u32(reader)?;
+        let feature_set = FeatureSet::from_id(feature_set_id)
+            .ok_or(NetworkError::UnknownFeatureSet(feature_set_id))?;
+
+        let l1 = read_layer_size(reader)?;
+        let l2 = read_layer_size(reader)?;
+        let l3 = read_layer_size(reader)?;
+
+        let network = Network {
+            feature_set,
+            ft_biases: read_i16s(reader, l1)?,
+            ft_weights: read_i16s(reader, feature_set.input_size() * l1)?,
+            l1: DenseLayer::read(reader, 2 * l1, l2)?,
+            l2: DenseLayer::read(reader, l2, l3)?,
+            out: DenseLayer::read(reader, l3, 1)?,
+        };
+
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(NetworkError::TrailingData);
+        }
+
+        Ok(network)
+    }
+
+    pub fn feature_set(&self) -> FeatureSet {
+        self.feature_set
+    }
+
+    /// Size of the accumulator of one perspective
+    pub fn accumulator_size(&self) -> usize {
+        self.ft_biases.len()
+    }
+
+    pub fn ft_biases(&self) -> &[i16] {
+        &self.ft_biases
+    }
+
+    #[inline(always)]
+    /// Feature transformer weights of the feature
+    pub fn ft_weights(&self, feature: usize) -> &[i16] {
+        let size = self.accumulator_size();
+        &self.ft_weights[feature * size..(feature + 1) * size]
+    }
+
+    /// Buffers for the layer outputs, reused for every evaluation
+    pub fn create_buffers(&self) -> LayerBuffers {
+        LayerBuffers {
+            input: vec![0; 2 * self.accumulator_size()],
+            l1_out: vec![0; self.l1.outputs()],
+            l1_act: vec![0; self.l1.outputs()],
+            l2_out: vec![0; self.l2.outputs()],
+            l2_act: vec![0; self.l2.outputs()],
+        }
+    }
+
+    /// Runs the dense layers on the accumulators and returns the evaluation in centipawns
+    pub fn evaluate(&self, us: &[i16], them: &[i16], buffers: &mut LayerBuffers) -> Eval {
+        let size = self.accumulator_size();
+        clipped_relu_i16(us, &mut buffers.input[..size]);
+        clipped_relu_i16(them, &mut buffers.input[size..]);
+
+        self.l1.forward(&buffers.input, &mut buffers.l1_out);
+        clipped_relu_i32(&buffers.l1_out, &mut buffers.l1_act, WEIGHT_SCALE_BITS);
+
+        self.l2.forward(&buffers.l1_act, &mut buffers.l2_out);
+        clipped_relu_i32(&buffers.l2_out, &mut buffers.l2_act, WEIGHT_SCALE_BITS);
+
+        let mut out = [0i32; 1];
+        self.out.forward(&buffers.l2_act, &mut out);
+
+        (out[0] / OUTPUT_SCALE) as Eval
+    }
+}
+
+/// Intermediate results of the dense layers
+pub struct LayerBuffers {
+    input: Vec<u8>,
+    l1_out: Vec<i32>,
+    l1_act: Vec<u8>,
+    l2_out: Vec<i32>,
+    l2_act: Vec<u8>,
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, NetworkError> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_layer_size(reader: &mut impl Read) -> Result<usize, NetworkError> {
+    let size = read_u32(reader)?;
+    if size == 0 || size as usize > MAX_LAYER_SIZE {
+        return Err(NetworkError::InvalidLayerSize(size));
+    }
+    Ok(size as usize)
+}
+
+fn read_i8s(reader: &mut impl Read, count: usize) -> Result<Vec<i8>, NetworkError> {
+    let mut buf = vec![0u8; count];
+    reader.read_exact(&mut buf)?;
+    Ok(buf.into_iter().map(|b| b as i8).collect())
+}
+
+fn read_i16s(reader: &mut impl Read, count: usize) -> Result<Vec<i16>, NetworkError> {
+    let mut buf = vec![0u8; count * 2];
+    reader.read_exact(&mut buf)?;
+    Ok(buf
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect())
+}
+
+fn read_i32s(reader: &mut impl Read, count: usize) -> Result<Vec<i32>, NetworkError> {
+    let mut buf = vec![0u8; count * 4];
+    reader.read_exact(&mut buf)?;
+    Ok(buf
+        .chunks_exact(4)
+        .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
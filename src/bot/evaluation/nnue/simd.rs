@@ -0,0 +1,145 @@
+//! Vector kernels used by the network inference.
+//! Every kernel has a portable scalar implementation which the compiler can auto vectorize,
+//! on x86_64 an AVX2 implementation is selected at runtime if the cpu supports it.
+
+/// Maximum value of the clipped ReLU activation
+pub(super) const CLIP_MAX: i32 = 127;
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    use std::sync::OnceLock;
+    static HAS_AVX2: OnceLock<bool> = OnceLock::new();
+    *HAS_AVX2.get_or_init(|| std::arch::is_x86_feature_detected!("avx2"))
+}
+
+#[inline(always)]
+/// acc += weights (element wise)
+pub(super) fn add_assign_i16(acc: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(acc.len(), weights.len());
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: avx2 support was checked at runtime
+        unsafe { avx2::add_assign_i16(acc, weights) };
+        return;
+    }
+    for (a, w) in acc.iter_mut().zip(weights) {
+        *a = a.wrapping_add(*w);
+    }
+}
+
+#[inline(always)]
+/// acc -= weights (element wise)
+pub(super) fn sub_assign_i16(acc: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(acc.len(), weights.len());
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: avx2 support was checked at runtime
+        unsafe { avx2::sub_assign_i16(acc, weights) };
+        return;
+    }
+    for (a, w) in acc.iter_mut().zip(weights) {
+        *a = a.wrapping_sub(*w);
+    }
+}
+
+#[inline(always)]
+/// Clamps the accumulator values to \[0, CLIP_MAX] and stores them as u8
+pub(super) fn clipped_relu_i16(input: &[i16], output: &mut [u8]) {
+    debug_assert_eq!(input.len(), output.len());
+    for (o, i) in output.iter_mut().zip(input) {
+        *o = (*i as i32).clamp(0, CLIP_MAX) as u8;
+    }
+}
+
+#[inline(always)]
+/// Shifts the layer output down by the weight scale and clamps it to \[0, CLIP_MAX]
+pub(super) fn clipped_relu_i32(input: &[i32], output: &mut [u8], shift: u32) {
+    debug_assert_eq!(input.len(), output.len());
+    for (o, i) in output.iter_mut().zip(input) {
+        *o = (*i >> shift).clamp(0, CLIP_MAX) as u8;
+    }
+}
+
+#[inline(always)]
+/// Dot product of the activated inputs and the weights of a neuron
+pub(super) fn dot_u8_i8(input: &[u8], weights: &[i8]) -> i32 {
+    debug_assert_eq!(input.len(), weights.len());
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: avx2 support was checked at runtime
+        return unsafe { avx2::dot_u8_i8(input, weights) };
+    }
+    dot_u8_i8_scalar(input, weights)
+}
+
+#[inline(always)]
+fn dot_u8_i8_scalar(input: &[u8], weights: &[i8]) -> i32 {
+    input
+        .iter()
+        .zip(weights)
+        .map(|(i, w)| *i as i32 * *w as i32)
+        .sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    const I16_LANES: usize = 16;
+    const I8_LANES: usize = 32;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_assign_i16(acc: &mut [i16], weights: &[i16]) {
+        let chunks = acc.len() / I16_LANES;
+        for i in 0..chunks {
+            let a = acc.as_mut_ptr().add(i * I16_LANES) as *mut __m256i;
+            let w = weights.as_ptr().add(i * I16_LANES) as *const __m256i;
+            _mm256_storeu_si256(
+                a,
+                _mm256_add_epi16(_mm256_loadu_si256(a), _mm256_loadu_si256(w)),
+            );
+        }
+        for i in chunks * I16_LANES..acc.len() {
+            acc[i] = acc[i].wrapping_add(weights[i]);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_assign_i16(acc: &mut [i16], weights: &[i16]) {
+        let chunks = acc.len() / I16_LANES;
+        for i in 0..chunks {
+            let a = acc.as_mut_ptr().add(i * I16_LANES) as *mut __m256i;
+            let w = weights.as_ptr().add(i * I16_LANES) as *const __m256i;
+            _mm256_storeu_si256(
+                a,
+                _mm256_sub_epi16(_mm256_loadu_si256(a), _mm256_loadu_si256(w)),
+            );
+        }
+        for i in chunks * I16_LANES..acc.len() {
+            acc[i] = acc[i].wrapping_sub(weights[i]);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn dot_u8_i8(input: &[u8], weights: &[i8]) -> i32 {
+        let chunks = input.len() / I8_LANES;
+        let ones = _mm256_set1_epi16(1);
+        let mut sum = _mm256_setzero_si256();
+        for i in 0..chunks {
+            let x = _mm256_loadu_si256(input.as_ptr().add(i * I8_LANES) as *const __m256i);
+            let w = _mm256_loadu_si256(weights.as_ptr().add(i * I8_LANES) as *const __m256i);
+            //inputs are clipped to 127, so the pairwise sums of the products can't saturate
+            let products = _mm256_maddubs_epi16(x, w);
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(products, ones));
+        }
+
+        let low = _mm256_castsi256_si128(sum);
+        let high = _mm256_extracti128_si256(sum, 1);
+        let sum = _mm_add_epi32(low, high);
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b01_00_11_10));
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b10_11_00_01));
+
+        let tail = chunks * I8_LANES;
+        _mm_cvtsi128_si32(sum) + super::dot_u8_i8_scalar(&input[tail..], &weights[tail..])
+    }
+}
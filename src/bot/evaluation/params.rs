@@ -0,0 +1,363 @@
+use lazy_static::lazy_static;
+use thiserror::Error;
+
+use crate::game::{Color, Piece};
+
+use super::{
+    eval::Eval,
+    king_safety, mobility, pawn_structure,
+    piece_square_table::{self, W},
+};
+
+/// Access to the weights of a parameter as a flat slice
+pub trait Weights {
+    fn weights(&self) -> &[W];
+    fn weights_mut(&mut self) -> &mut [W];
+}
+
+impl Weights for W {
+    fn weights(&self) -> &[W] {
+        std::slice::from_ref(self)
+    }
+
+    fn weights_mut(&mut self) -> &mut [W] {
+        std::slice::from_mut(self)
+    }
+}
+
+impl<const N: usize> Weights for [W; N] {
+    fn weights(&self) -> &[W] {
+        self
+    }
+
+    fn weights_mut(&mut self) -> &mut [W] {
+        self
+    }
+}
+
+impl<const N: usize, const M: usize> Weights for [[W; N]; M] {
+    fn weights(&self) -> &[W] {
+        self.as_flattened()
+    }
+
+    fn weights_mut(&mut self) -> &mut [W] {
+        self.as_flattened_mut()
+    }
+}
+
+/// Named group of weights of the parameter set
+pub struct ParamGroup<'a> {
+    pub name: &'static str,
+    /// Rust type of the group, used to write the group as rust source
+    pub type_name: &'static str,
+    pub weights: &'a [W],
+}
+
+/// Named group of mutable weights of the parameter set
+pub struct ParamGroupMut<'a> {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub weights: &'a mut [W],
+}
+
+/// Declares the parameter set with the compiled in constants as defaults
+/// and the accessors to iterate over all weight groups
+macro_rules! eval_params {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty = $default:expr,)*) => {
+        /// #### Evaluation parameters
+        /// All weights of the classic evaluation, the defaults are the compiled in constants.
+        /// The weights are grouped by their evaluation term, the groups can be iterated
+        /// to tune, load or store the parameters without knowing every single term.
+        /// The weights can only be changed through the groups, so that the incremental
+        /// scores of the board are not used for changed weights.
+        #[derive(Clone)]
+        pub struct EvalParams {
+            $($(#[$doc])* pub(super) $name: $ty,)*
+            /// Material and piece square tables are the compiled in defaults,
+            /// so the incrementally updated scores of the board can be used
+            pub(super) incremental_psqt: bool,
+        }
+
+        impl Default for EvalParams {
+            fn default() -> Self {
+                EvalParams {
+                    $($name: $default,)*
+                    incremental_psqt: true,
+                }
+            }
+        }
+
+        impl EvalParams {
+            pub fn groups(&self) -> Vec<ParamGroup<'_>> {
+                vec![$(ParamGroup {
+                    name: stringify!($name),
+                    type_name: stringify!($ty),
+                    weights: self.$name.weights(),
+                },)*]
+            }
+
+            /// Mutable access to all weights, changed material or piece square table weights
+            /// can't use the incrementally updated scores of the board anymore
+            pub fn groups_mut(&mut self) -> Vec<ParamGroupMut<'_>> {
+                self.incremental_psqt = false;
+                vec![$(ParamGroupMut {
+                    name: stringify!($name),
+                    type_name: stringify!($ty),
+                    weights: self.$name.weights_mut(),
+                },)*]
+            }
+        }
+    };
+}
+
+eval_params! {
+    /// Material value indexed by piece type
+    piece_values: [W; 6] = piece_square_table::PIECE_VALUES,
+    /// Positional piece square table bonus indexed by piece type,
+    /// squares are ordered from a8 to h1 (as read from whites perspective)
+    piece_square_tables: [[W; 64]; 6] = piece_square_table::positional_tables(),
+
+    passed_pawn: [W; 8] = pawn_structure::PASSED_PAWN,
+    passed_pawn_free_path: [W; 8] = pawn_structure::PASSED_PAWN_FREE_PATH,
+    passed_pawn_enemy_king_distance: W = pawn_structure::PASSED_PAWN_ENEMY_KING_DISTANCE,
+    passed_pawn_own_king_distance: W = pawn_structure::PASSED_PAWN_OWN_KING_DISTANCE,
+    candidate_passer: [W; 8] = pawn_structure::CANDIDATE_PASSER,
+    supported_pawn: [W; 8] = pawn_structure::SUPPORTED_PAWN,
+    phalanx_pawn: [W; 8] = pawn_structure::PHALANX_PAWN,
+    isolated_pawn: W = pawn_structure::ISOLATED_PAWN,
+    doubled_pawn: W = pawn_structure::DOUBLED_PAWN,
+    backward_pawn: W = pawn_structure::BACKWARD_PAWN,
+
+    pawn_shield: [W; 4] = king_safety::PAWN_SHIELD,
+    pawn_storm: [W; 5] = king_safety::PAWN_STORM,
+    semi_open_file_near_king: W = king_safety::SEMI_OPEN_FILE_NEAR_KING,
+    open_file_near_king: W = king_safety::OPEN_FILE_NEAR_KING,
+
+    knight_mobility: [W; 9] = mobility::KNIGHT_MOBILITY,
+    bishop_mobility: [W; 14] = mobility::BISHOP_MOBILITY,
+    rook_mobility: [W; 15] = mobility::ROOK_MOBILITY,
+    queen_mobility: [W; 28] = mobility::QUEEN_MOBILITY,
+    rook_open_file: W = mobility::ROOK_OPEN_FILE,
+    rook_semi_open_file: W = mobility::ROOK_SEMI_OPEN_FILE,
+    rook_on_seventh: W = mobility::ROOK_ON_SEVENTH,
+    bishop_pair: W = mobility::BISHOP_PAIR,
+    knight_outpost: W = mobility::KNIGHT_OUTPOST,
+    trapped_rook: W = mobility::TRAPPED_ROOK,
+    trapped_bishop: W = mobility::TRAPPED_BISHOP,
+}
+
+lazy_static! {
+    pub static ref DEFAULT_PARAMS: EvalParams = EvalParams::default();
+}
+
+impl EvalParams {
+    #[inline(always)]
+    /// Material and piece square table score of the piece on the square
+    pub fn piece_square(&self, piece: Piece, sq: u8) -> W {
+        let index = match piece.color() {
+            Color::White => sq as usize ^ 56,
+            Color::Black => sq as usize,
+        };
+
+        self.piece_values[piece.ptype()] + self.piece_square_tables[piece.ptype()][index]
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParamsError {
+    #[error("Failed to read the parameter file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {0}: expected \"<name> = <mid game> <end game>, ...\"")]
+    MissingName(usize),
+    #[error("Line {0}: unknown parameter {1}")]
+    UnknownParameter(usize, String),
+    #[error("Line {0}: parameter {1} is defined twice")]
+    DuplicateParameter(usize, String),
+    #[error("Line {0}: invalid weight \"{1}\", expected \"<mid game> <end game>\"")]
+    InvalidWeight(usize, String),
+    #[error("Parameter {name} has {found} weights, expected {expected}")]
+    WrongLength {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Number of weights written per line in the parameter file
+const WEIGHTS_PER_LINE: usize = 8;
+
+impl EvalParams {
+    /// Loads the parameters from a parameter file, see [`EvalParams::parse`]
+    pub fn from_file(path: &str) -> Result<EvalParams, ParamsError> {
+        EvalParams::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// #### Parameter file
+    /// Plain text with one parameter group per assignment, `#` starts a comment.
+    /// Each weight is written as `<mid game> <end game>`, weights are separated by commas
+    /// and may continue on the following lines:
+    /// ```text
+    /// bishop_pair = 30 50
+    /// passed_pawn = 0 0, 5 10, 10 15, 15 25,
+    ///     35 50, 60 100, 100 160, 0 0
+    /// ```
+    /// Groups missing in the file keep their compiled in default.
+    pub fn parse(content: &str) -> Result<EvalParams, ParamsError> {
+        let mut params = EvalParams::default();
+        let mut parsed: Vec<(&str, Vec<W>)> = Vec::new();
+
+        for (line_index, line) in content.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let values = match line.split_once('=') {
+                Some((name, values)) => {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(ParamsError::MissingName(line_number));
+                    }
+                    if !params.groups().iter().any(|group| group.name == name) {
+                        return Err(ParamsError::UnknownParameter(line_number, name.into()));
+                    }
+                    if parsed.iter().any(|(parsed_name, _)| *parsed_name == name) {
+                        return Err(ParamsError::DuplicateParameter(line_number, name.into()));
+                    }
+                    parsed.push((name, Vec::new()));
+                    values
+                }
+                None => line,
+            };
+
+            let (_, weights) = parsed
+                .last_mut()
+                .ok_or(ParamsError::MissingName(line_number))?;
+            for weight in values.split(',').map(str::trim).filter(|w| !w.is_empty()) {
+                weights.push(
+                    parse_weight(weight)
+                        .ok_or_else(|| ParamsError::InvalidWeight(line_number, weight.into()))?,
+                );
+            }
+        }
+
+        let mut groups = params.groups_mut();
+        for (name, weights) in parsed {
+            let group = groups
+                .iter_mut()
+                .find(|group| group.name == name)
+                .expect("Parameter names are checked while parsing");
+            if group.weights.len() != weights.len() {
+                return Err(ParamsError::WrongLength {
+                    name: name.into(),
+                    expected: group.weights.len(),
+                    found: weights.len(),
+                });
+            }
+            group.weights.copy_from_slice(&weights);
+        }
+        drop(groups);
+
+        params.incremental_psqt = params.piece_values == piece_square_table::PIECE_VALUES
+            && params.piece_square_tables == piece_square_table::positional_tables();
+        Ok(params)
+    }
+
+    /// Writes all parameters in the format of the parameter file
+    pub fn to_param_string(&self) -> String {
+        let mut result =
+            String::from("# Evaluation parameters, each weight is \"<mid game> <end game>\"\n");
+
+        for group in self.groups() {
+            let lines: Vec<String> = group
+                .weights
+                .chunks(WEIGHTS_PER_LINE)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .map(|w| format!("{} {}", w.0, w.1))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect();
+
+            if lines.len() == 1 {
+                result += &format!("{} = {}\n", group.name, lines[0]);
+            } else {
+                result += &format!("{} =\n    {}\n", group.name, lines.join(",\n    "));
+            }
+        }
+
+        result
+    }
+}
+
+fn parse_weight(weight: &str) -> Option<W> {
+    let mut values = weight.split_whitespace().map(|v| v.parse::<Eval>());
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(mid_game)), Some(Ok(end_game)), None) => Some(W(mid_game, end_game)),
+        _ => None,
+    }
+}
+
+mod test {
+    #[test]
+    fn test_param_file_round_trip() {
+        use super::{EvalParams, ParamsError};
+
+        let mut params = EvalParams::default();
+        params.bishop_pair.0 += 7;
+        params.piece_square_tables[2][17].1 -= 3;
+
+        let parsed = EvalParams::parse(&params.to_param_string()).unwrap();
+        for (a, b) in parsed.groups().iter().zip(params.groups()) {
+            assert!(a.weights.iter().zip(b.weights).all(|(a, b)| a == b));
+        }
+
+        let partial = EvalParams::parse("# only one group\nbishop_pair = 1 2\n").unwrap();
+        assert_eq!(partial.bishop_pair, super::W(1, 2));
+        assert_eq!(partial.rook_open_file, EvalParams::default().rook_open_file);
+
+        assert!(matches!(
+            EvalParams::parse("bishop_pair = 1 2, 3 4"),
+            Err(ParamsError::WrongLength { .. })
+        ));
+        assert!(matches!(
+            EvalParams::parse("unknown = 1 2"),
+            Err(ParamsError::UnknownParameter(1, _))
+        ));
+        assert!(matches!(
+            EvalParams::parse("bishop_pair = 1"),
+            Err(ParamsError::InvalidWeight(1, _))
+        ));
+    }
+
+    #[test]
+    fn test_tuned_material_phase() {
+        use super::EvalParams;
+        use crate::bot::evaluation::{evaluate_board, non_pawn_material};
+        use crate::game::{Board, Color, PieceType};
+
+        //without the incremental scores the material and the phase are calculated from the parameters
+        let default = EvalParams::default();
+        let mut tuned = EvalParams::default();
+        tuned.groups_mut();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "6k1/5ppp/8/8/8/8/1Q3PPP/6K1 b - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(
+                evaluate_board(&board, &default),
+                evaluate_board(&board, &tuned)
+            );
+        }
+
+        //the phase follows the tuned material values
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/1Q3PPP/6K1 b - - 0 1").unwrap();
+        tuned.piece_values[PieceType::Queen].0 = 500;
+        assert_eq!(non_pawn_material(&board, &tuned, Color::White), 500);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::game::{
+    bit_manipulation::{east, iter_set_bits, west},
+    Board, Color, PieceType, Square,
+};
+
+use super::{
+    eval::Eval,
+    masks::{
+        pawn_attacks, relative_rank, ADJACENT_FILE_MASKS, FORWARD_FILE_MASKS, PASSED_PAWN_MASKS,
+    },
+    max_weight,
+    params::EvalParams,
+    piece_square_table::W,
+};
+
+/*
+    Pawn structure weights indexed by the relative rank of the pawn (0 = own back rank)
+    Values loosely based on the classical Stockfish evaluation, scaled to the PeSTO piece values
+*/
+
+pub(super) const PASSED_PAWN: [W; 8] = [
+    W(0, 0),
+    W(5, 10),
+    W(10, 15),
+    W(15, 25),
+    W(35, 50),
+    W(60, 100),
+    W(100, 160),
+    W(0, 0),
+];
+
+/// Additional bonus if no piece is blocking the path of the passed pawn to the promotion square
+pub(super) const PASSED_PAWN_FREE_PATH: [W; 8] = [
+    W(0, 0),
+    W(0, 0),
+    W(0, 0),
+    W(2, 5),
+    W(5, 15),
+    W(10, 30),
+    W(20, 50),
+    W(0, 0),
+];
+
+/// Scaled by the distance of the enemy king to the square in front of a passed pawn
+pub(super) const PASSED_PAWN_ENEMY_KING_DISTANCE: W = W(0, 5);
+/// Scaled by the distance of the own king to the square in front of a passed pawn
+pub(super) const PASSED_PAWN_OWN_KING_DISTANCE: W = W(0, -2);
+
+/// Pawn which can become a passed pawn by advancing, as it has enough supporters
+pub(super) const CANDIDATE_PASSER: [W; 8] = [
+    W(0, 0),
+    W(3, 5),
+    W(5, 8),
+    W(8, 12),
+    W(12, 20),
+    W(20, 35),
+    W(0, 0),
+    W(0, 0),
+];
+
+/// Pawn defended by another pawn
+pub(super) const SUPPORTED_PAWN: [W; 8] = [
+    W(0, 0),
+    W(0, 0),
+    W(5, 3),
+    W(8, 5),
+    W(12, 10),
+    W(20, 25),
+    W(35, 45),
+    W(0, 0),
+];
+
+/// Pawn with a pawn of the same color next to it
+pub(super) const PHALANX_PAWN: [W; 8] = [
+    W(0, 0),
+    W(3, 0),
+    W(5, 3),
+    W(8, 6),
+    W(15, 15),
+    W(30, 35),
+    W(50, 60),
+    W(0, 0),
+];
+
+/// Pawn without any pawns of the same color on the adjacent files
+pub(super) const ISOLATED_PAWN: W = W(-10, -15);
+/// Pawn with a pawn of the same color in front of it
+pub(super) const DOUBLED_PAWN: W = W(-10, -25);
+/// Pawn which can't be protected by adjacent pawns and whose stop square is controlled by an enemy pawn
+pub(super) const BACKWARD_PAWN: W = W(-8, -12);
+
+#[inline(always)]
+/// Mask of all ranks that are level with or behind the given rank from the perspective of the color
+fn level_or_behind_mask(rank: u8, color: Color) -> u64 {
+    match color {
+        Color::White => u64::MAX >> ((7 - rank) * 8),
+        Color::Black => u64::MAX << (rank * 8),
+    }
+}
+
+/// #### Pawn structure evaluation
+/// Evaluates the pawn structure of both colors and returns the weighted scores indexed by color
+/// The following terms are considered:
+/// - Passed pawns: scaled by rank, free path and king distance (end game)
+/// - Candidate passers: pawns on a half open file with more supporters than sentries
+/// - Supported and phalanx pawns
+/// - Isolated, doubled and backward pawns
+pub(super) fn evaluate_pawns(board: &Board, params: &EvalParams) -> [W; 2] {
+    [
+        evaluate_pawns_for(board, params, Color::White),
+        evaluate_pawns_for(board, params, Color::Black),
+    ]
+}
+
+fn evaluate_pawns_for(board: &Board, params: &EvalParams, color: Color) -> W {
+    let mut score = W::ZERO;
+
+    let own_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color));
+    let enemy_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color.opposite()));
+    let occupied = *board.get_bb_all_occupied();
+    let own_pawn_attacks = pawn_attacks(own_pawns, color);
+    let enemy_pawn_attacks = pawn_attacks(enemy_pawns, color.opposite());
+    let own_king = board.get_king_pos(color);
+    let enemy_king = board.get_king_pos(color.opposite());
+
+    for sq in iter_set_bits(own_pawns) {
+        let square = Square::new(sq);
+        let mask = square.to_mask();
+        let rank = relative_rank(square.rank(), color);
+        let adjacent_files = ADJACENT_FILE_MASKS[square.file() as usize];
+        let forward_file = FORWARD_FILE_MASKS[color][square];
+
+        let doubled = forward_file & own_pawns != 0;
+        let passed = PASSED_PAWN_MASKS[color][square] & enemy_pawns == 0;
+        let isolated = adjacent_files & own_pawns == 0;
+        let supporters = adjacent_files & own_pawns & level_or_behind_mask(square.rank(), color);
+
+        if doubled {
+            score += params.doubled_pawn;
+        }
+
+        if isolated {
+            score += params.isolated_pawn;
+        } else if supporters == 0 {
+            //the pawn can't be supported by adjacent pawns, so it is backward if it can't advance safely
+            let stop_square = square + color.perspective() * 8;
+            if enemy_pawn_attacks & stop_square.to_mask() != 0 {
+                score += params.backward_pawn;
+            }
+        }
+
+        if own_pawn_attacks & mask != 0 {
+            score += params.supported_pawn[rank];
+        }
+
+        if (east(mask, 1) | west(mask, 1)) & own_pawns != 0 {
+            score += params.phalanx_pawn[rank];
+        }
+
+        //only the front pawn of doubled pawns can be a passed pawn
+        if passed && !doubled {
+            score += params.passed_pawn[rank];
+
+            if forward_file & occupied == 0 {
+                score += params.passed_pawn_free_path[rank];
+            }
+
+            //the closer the pawn is to promotion the more important the king distances are
+            if rank >= 3 {
+                let block_square = square + color.perspective() * 8;
+                let rank_weight = rank as Eval - 2;
+                score += params.passed_pawn_enemy_king_distance
+                    * (enemy_king.distance(block_square) as Eval * rank_weight);
+                score += params.passed_pawn_own_king_distance
+                    * (own_king.distance(block_square) as Eval * rank_weight);
+            }
+        } else if !passed && forward_file & enemy_pawns == 0 {
+            let sentries = PASSED_PAWN_MASKS[color][square] & enemy_pawns;
+            if supporters.count_ones() >= sentries.count_ones() {
+                score += params.candidate_passer[rank];
+            }
+        }
+    }
+
+    score
+}
+
+/// Upper bound of the pawn structure score of the color, every pawn gets at most the largest weight of each term
+/// and only passed pawns can get the passed pawn weights
+pub(super) fn pawn_structure_bound(board: &Board, params: &EvalParams, color: Color) -> Eval {
+    let own_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color));
+    let enemy_pawns = *board.get_bb_for(PieceType::Pawn.as_colored_piece(color.opposite()));
+
+    let pawn_bound = max_weight(&[params.doubled_pawn])
+        + max_weight(&[params.isolated_pawn, params.backward_pawn])
+        + max_weight(&params.supported_pawn)
+        + max_weight(&params.phalanx_pawn)
+        + max_weight(&params.candidate_passer);
+    //king distances are at most 7 squares weighted by at most 4 ranks
+    let passed_bound = max_weight(&params.passed_pawn)
+        + max_weight(&params.passed_pawn_free_path)
+        + 7 * 4
+            * (max_weight(&[params.passed_pawn_enemy_king_distance])
+                + max_weight(&[params.passed_pawn_own_king_distance]));
+
+    let passed_pawns = iter_set_bits(own_pawns)
+        .filter(|&sq| PASSED_PAWN_MASKS[color][Square::new(sq)] & enemy_pawns == 0)
+        .count();
+    own_pawns.count_ones() as Eval * pawn_bound + passed_pawns as Eval * passed_bound
+}
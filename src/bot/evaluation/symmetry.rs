@@ -0,0 +1,100 @@
+use crate::game::Board;
+
+use super::{eval::Eval, evaluate_board, params::EvalParams};
+
+/// Position which is evaluated differently than the same position with flipped colors
+#[derive(Debug)]
+pub struct SymmetryError {
+    pub fen: String,
+    /// Evaluation in the perspective of the side to move
+    pub eval: Eval,
+    /// Evaluation of the color flipped board in the perspective of its side to move
+    pub flipped_eval: Eval,
+}
+
+impl std::fmt::Display for SymmetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is evaluated with {}, but the flipped position with {}",
+            self.fen, self.eval, self.flipped_eval
+        )
+    }
+}
+
+/// #### Evaluation symmetry
+/// Evaluates every board and the board with flipped colors (see [`Board::flip_colors`]),
+/// both evaluations are in the perspective of the side to move and must be equal.
+/// Returns all positions where the evaluation is not symmetric.
+pub fn verify_symmetry<'a>(
+    boards: impl IntoIterator<Item = &'a Board>,
+    params: &EvalParams,
+) -> Result<(), Vec<SymmetryError>> {
+    let errors: Vec<SymmetryError> = boards
+        .into_iter()
+        .filter_map(|board| {
+            let eval = evaluate_board(board, params);
+            let flipped_eval = evaluate_board(&board.flip_colors(), params);
+            (eval != flipped_eval).then(|| SymmetryError {
+                fen: board.to_fen(),
+                eval,
+                flipped_eval,
+            })
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Asserts that the evaluation is symmetric for every board, see [`verify_symmetry`].
+/// Panics with all asymmetric positions.
+pub fn assert_symmetric<'a>(boards: impl IntoIterator<Item = &'a Board>, params: &EvalParams) {
+    if let Err(errors) = verify_symmetry(boards, params) {
+        let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        panic!("Evaluation is not symmetric:\n{}", errors.join("\n"));
+    }
+}
+
+mod test {
+    #[test]
+    fn test_evaluation_symmetry() {
+        use crate::game::{Board, MoveGeneration};
+
+        let params = super::EvalParams::default();
+        let mut boards: Vec<Board> = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "4k3/8/8/8/3b4/8/8/R3K3 w - - 0 1",
+            "6k1/5ppp/8/8/8/8/1Q3PPP/6K1 b - - 0 1",
+        ]
+        .iter()
+        .map(|fen| Board::from_fen(fen).unwrap())
+        .collect();
+
+        //add the positions after every legal move to cover unbalanced material
+        let children: Vec<Board> = boards
+            .iter()
+            .flat_map(|board| {
+                MoveGeneration::generate_legal_moves(board)
+                    .iter()
+                    .map(|mov| {
+                        let mut child = board.clone();
+                        child.make_move(mov, true, false).unwrap();
+                        child
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        boards.extend(children);
+
+        super::assert_symmetric(boards.iter(), &params);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::game::Color;
+
+use super::{endgame::SCALE_NORMAL, eval::Eval, piece_square_table::W};
+
+/// Single evaluation term of the trace
+pub struct TraceTerm {
+    pub name: &'static str,
+    /// Unweighted scores indexed by color
+    pub scores: [W; 2],
+    /// Tapered score of the term from whites perspective
+    pub score: Eval,
+}
+
+/// #### Evaluation trace
+/// Breakdown of the classic evaluation into its terms, recorded while evaluating the board.
+/// Every term added to the evaluation is recorded, so the trace doesn't need to know the terms.
+#[derive(Default)]
+pub struct EvalTrace {
+    pub terms: Vec<TraceTerm>,
+    /// Mid game and end game factors indexed by color
+    pub phase: [(f32, f32); 2],
+    /// Description of the specialized endgame evaluation which replaced the terms
+    pub endgame: Option<String>,
+    /// Scale factor for drawish material (out of [`SCALE_NORMAL`])
+    pub scale_factor: Eval,
+    /// Final score from whites perspective
+    pub score: Eval,
+    pub side_to_move: Color,
+}
+
+impl EvalTrace {
+    pub(super) fn add(&mut self, name: &'static str, scores: [W; 2], score: Eval) {
+        self.terms.push(TraceTerm {
+            name,
+            scores,
+            score,
+        });
+    }
+}
+
+impl std::fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = format!("{:-<16}+{:-<15}+{:-<15}+{:-<8}", "", "", "", "");
+
+        writeln!(
+            f,
+            "{:<16}|{:^15}|{:^15}|{:>7}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:<16}|{:>7}{:>7} |{:>7}{:>7} |",
+            "", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{line}")?;
+
+        if let Some(endgame) = &self.endgame {
+            writeln!(f, "Specialized endgame evaluation: {endgame}")?;
+        }
+
+        for term in self.terms.iter() {
+            let [white, black] = &term.scores;
+            writeln!(
+                f,
+                "{:<16}|{:>7}{:>7} |{:>7}{:>7} |{:>7}",
+                term.name, white.0, white.1, black.0, black.1, term.score
+            )?;
+        }
+
+        writeln!(f, "{line}")?;
+        let [white, black] = &self.phase;
+        writeln!(
+            f,
+            "{:<16}|{:>7.2}{:>7.2} |{:>7.2}{:>7.2} |",
+            "Phase", white.0, white.1, black.0, black.1
+        )?;
+        writeln!(f, "Scale factor: {}/{SCALE_NORMAL}", self.scale_factor)?;
+        writeln!(f, "Final score (white): {}", self.score)?;
+        write!(
+            f,
+            "Final score (side to move): {}",
+            self.score * self.side_to_move.perspective() as Eval
+        )
+    }
+}
+
+mod test {
+    #[test]
+    fn test_trace_matches_evaluation() {
+        use super::SCALE_NORMAL;
+        use crate::bot::{evaluate_board, trace_board, Eval, EvalParams};
+        use crate::game::Board;
+
+        let params = EvalParams::default();
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+            "8/8/8/4k3/8/8/4P3/4K3 b - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let trace = trace_board(&board, &params);
+            let eval = evaluate_board(&board, &params);
+            assert_eq!(
+                trace.score * board.side_to_move().perspective() as Eval,
+                eval
+            );
+
+            //the tapered terms only differ by rounding from the unscaled score
+            if trace.endgame.is_none() && trace.scale_factor == SCALE_NORMAL {
+                let sum: Eval = trace.terms.iter().map(|term| term.score).sum();
+                assert!((sum - trace.score).abs() <= 2);
+            }
+        }
+    }
+}
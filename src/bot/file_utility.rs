@@ -0,0 +1,23 @@
+use std::fs::File;
+
+/// Reads exactly `buf.len()` bytes at the offset without moving the cursor of the file,
+/// so the file can be shared between threads which read different offsets
+#[cfg(unix)]
+pub(crate) fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// Reads exactly `buf.len()` bytes at the offset, `seek_read` may return less bytes than requested
+#[cfg(windows)]
+pub(crate) fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    let mut read = 0;
+    while read < buf.len() {
+        let n =
+            std::os::windows::fs::FileExt::seek_read(file, &mut buf[read..], offset + read as u64)?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        read += n;
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub(crate) mod calculation_thread;
+mod file_utility;
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -9,21 +10,33 @@ use std::{
 };
 
 use calculation_thread::thread_loop;
-use evaluation::{eval::display_eval, evaluate_board};
+use evaluation::{eval::display_eval, params::ParamsError};
 use search::{limit::Limits, AbortFlag};
 
 use crate::{
-    game::{Board, Color, GameResult, Move, MoveGeneration},
+    game::{Board, Color, GameResult, Move, MoveGeneration, Variant},
     perft,
     uci::commands::{command_go::TimeControl, command_set_option::OptionType},
 };
 
 mod evaluation;
 pub mod search;
+pub mod tablebase;
+pub use evaluation::evaluator::{DEFAULT_EVALUATOR, REGISTERED_EVALUATORS};
+pub(crate) use evaluation::lazy_static_kpk_bitbase_init;
+pub use evaluation::{
+    eval::Eval, evaluate_board, params::EvalParams, symmetry, trace::EvalTrace, trace_board, W,
+};
+
+/// Environment variable with the path of the evaluation parameter file loaded at startup
+const EVAL_PARAMS_ENV: &str = "EVAL_PARAMS";
 
 pub enum ActionMessage {
     Think(Board, Limits, Option<Vec<Move>>),
     SetOption(OptionType),
+    SetEvalParams(Arc<EvalParams>),
+    /// The game ended with the result, used to learn the played book moves
+    GameOver(GameResult),
 }
 
 pub enum ReactionMessage {
@@ -39,7 +52,10 @@ pub struct Bot {
     thinking: bool,
     action_sender: Sender<ActionMessage>,
     reaction_receiver: Receiver<ReactionMessage>,
-    _thread_handle: thread::JoinHandle<()>,
+    eval_params: Arc<EvalParams>,
+    chess960: bool,
+    variant: Variant,
+    thread_handle: thread::JoinHandle<()>,
 }
 
 impl Bot {
@@ -48,8 +64,19 @@ impl Bot {
         let (reaction_tx, reaction_rx) = std::sync::mpsc::channel();
         let abort_flag = Arc::new(AtomicBool::new(false));
 
+        let eval_params = match std::env::var(EVAL_PARAMS_ENV) {
+            Ok(path) if !path.is_empty() => EvalParams::from_file(&path).unwrap_or_else(|err| {
+                warn!("Failed to load evaluation parameters {path}, using the defaults: {err}");
+                EvalParams::default()
+            }),
+            _ => EvalParams::default(),
+        };
+        let eval_params = Arc::new(eval_params);
+
         let flag = Arc::clone(&abort_flag);
-        let calculation_thread = thread::spawn(move || thread_loop(action_rx, reaction_tx, flag));
+        let params = Arc::clone(&eval_params);
+        let calculation_thread =
+            thread::spawn(move || thread_loop(action_rx, reaction_tx, flag, params));
 
         Bot {
             board: Board::default(),
@@ -57,14 +84,46 @@ impl Bot {
             thinking: false,
             action_sender: action_tx,
             reaction_receiver: reaction_rx,
-            _thread_handle: calculation_thread,
+            eval_params,
+            chess960: false,
+            variant: Variant::Standard,
+            thread_handle: calculation_thread,
         }
     }
 
-    pub fn set_board(&mut self, board: Board) {
+    /// Sets the board, positions with castling only possible in Chess960 stay Chess960 boards
+    pub fn set_board(&mut self, mut board: Board) {
+        self.apply_rules(&mut board);
         self.board = board;
     }
 
+    /// Applies Chess960 and the variant of the bot to the board, should be called before moves are played on it.
+    /// Boards already set to a variant by their fen keep it
+    pub fn apply_rules(&self, board: &mut Board) {
+        if self.chess960 {
+            board.set_chess960(true);
+        }
+        if board.variant() == Variant::Standard {
+            board.set_variant(self.variant);
+        }
+    }
+
+    /// Enables Chess960, castling moves are written as the king capturing its own rook
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+        self.board.set_chess960(chess960);
+    }
+
+    /// Sets the variant played by the bot, applied to the next position
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.board.set_variant(variant);
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
     pub fn get_board(&self) -> &Board {
         &self.board
     }
@@ -73,6 +132,30 @@ impl Bot {
         self.action_sender.send(msg).unwrap();
     }
 
+    /// Loads the evaluation parameters from the file and passes them to the search,
+    /// an empty path restores the compiled in defaults.
+    /// If the file can't be loaded the defaults are used and the error is returned.
+    pub fn load_eval_params(&mut self, path: &str) -> Result<(), ParamsError> {
+        let result = if path.is_empty() {
+            Ok(EvalParams::default())
+        } else {
+            EvalParams::from_file(path)
+        };
+
+        let (params, result) = match result {
+            Ok(params) => (params, Ok(())),
+            Err(err) => (EvalParams::default(), Err(err)),
+        };
+        self.eval_params = Arc::new(params);
+        self.send_message(ActionMessage::SetEvalParams(Arc::clone(&self.eval_params)));
+        result
+    }
+
+    /// Breakdown of the classic evaluation of the current board into its terms
+    pub fn trace_eval(&self) -> String {
+        trace_board(&self.board, &self.eval_params).to_string()
+    }
+
     pub fn eval_board(&mut self, divide: bool) -> String {
         let mut eval_str = String::new();
         if divide {
@@ -95,7 +178,7 @@ impl Bot {
                             result.color_lost().unwrap()
                         )),
                         _ => {
-                            let eval = -evaluate_board(&self.board);
+                            let eval = -evaluate_board(&self.board, &self.eval_params);
                             eval_str.push_str(&format!(
                                 "{} : {}\n",
                                 mv.as_uci_notation(),
@@ -111,7 +194,7 @@ impl Bot {
 
         eval_str.push_str(&format!(
             "Current eval: {}",
-            display_eval(evaluate_board(&self.board))
+            display_eval(evaluate_board(&self.board, &self.eval_params))
         ));
 
         eval_str
@@ -186,6 +269,29 @@ impl Bot {
         str
     }
 
+    /// Reports the end of the game to the search, without a result it is taken from the current board.
+    /// Games still in progress are reported without result, so only the evaluation after the book is learned
+    pub fn game_over(&mut self, result: Option<GameResult>) {
+        let result = result.unwrap_or_else(|| GameResult::get_game_result(&self.board, None));
+        self.send_message(ActionMessage::GameOver(result));
+    }
+
+    /// Stops the search, reports the end of the game and waits for the calculation thread to finish
+    pub fn quit(mut self) {
+        self.stop();
+        self.game_over(None);
+
+        let Bot {
+            action_sender,
+            thread_handle,
+            ..
+        } = self;
+        drop(action_sender);
+        if thread_handle.join().is_err() {
+            error!("Calculation thread panicked");
+        }
+    }
+
     pub fn stop(&mut self) {
         self.abort_flag.store(true, Ordering::Relaxed);
     }
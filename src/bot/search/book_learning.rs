@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use crate::{
+    bot::Eval,
+    game::{Board, Color, GameResult, Move},
+};
+
+use super::opening_book::OpeningBookError;
+
+/// File extension of the learned weights, appended to the path of the book
+pub const LEARN_EXTENSION: &str = "learn";
+/// Change of the weight adjustment in percent for a won or lost game
+const LEARN_STEP: f64 = 20.0;
+/// Evaluation after leaving the book which counts as won
+const WON_EVAL: f64 = 200.0;
+/// First word of the header line with the key of the start position
+const START_KEY_HEADER: &str = "start";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Learned {
+    /// Adjustment of the book weight in percent, -100 removes the move from the book
+    adjustment: i32,
+    games: u32,
+}
+
+/// #### Learned book weights
+/// Adjustments of the book weights learned from the outcome of the played games,
+/// stored as sidecar file next to the book keyed by the zobrist key of the position and the move.
+/// The first line contains *start \<key of the start position as hex>*,
+/// files created with different zobrist keys are rejected like hashed books.
+/// Each following line contains *\<key as hex> \<move> \<adjustment> \<games>*.
+#[derive(Default)]
+pub struct LearnedWeights {
+    path: String,
+    entries: HashMap<(u64, String), Learned>,
+}
+
+impl LearnedWeights {
+    /// Loads the learned weights of the book, a missing file is an empty set of weights.
+    /// Fails if the weights were learned with different zobrist keys, as the keys of the positions wouldn't match.
+    pub fn load(book_path: &str) -> Result<LearnedWeights, OpeningBookError> {
+        let path = format!("{book_path}.{LEARN_EXTENSION}");
+        let mut weights = LearnedWeights {
+            path,
+            entries: HashMap::new(),
+        };
+
+        let file = match File::open(&weights.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(weights),
+            Err(err) => return Err(err.into()),
+        };
+        let mut lines = BufReader::new(file).lines();
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let start_key = header
+            .strip_prefix(START_KEY_HEADER)
+            .and_then(|key| u64::from_str_radix(key.trim(), 16).ok());
+        if start_key != Some(Board::default().cur_state().zobrist) {
+            return Err(OpeningBookError::IncompatibleKeys);
+        }
+
+        for line in lines {
+            let line = line?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [key, mov, adjustment, games] = parts[..] else {
+                continue;
+            };
+            let (Ok(key), Ok(adjustment), Ok(games)) = (
+                u64::from_str_radix(key, 16),
+                adjustment.parse(),
+                games.parse(),
+            ) else {
+                continue;
+            };
+            weights
+                .entries
+                .insert((key, mov.to_string()), Learned { adjustment, games });
+        }
+        Ok(weights)
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut file = BufWriter::new(File::create(&self.path)?);
+        writeln!(
+            file,
+            "{START_KEY_HEADER} {:016x}",
+            Board::default().cur_state().zobrist
+        )?;
+        for ((key, mov), learned) in entries {
+            writeln!(
+                file,
+                "{key:016x} {mov} {} {}",
+                learned.adjustment, learned.games
+            )?;
+        }
+        file.flush()
+    }
+
+    /// Weight of the book move with the learned adjustment applied
+    pub fn weight(&self, key: u64, mov: &Move, num_plays: u32) -> u32 {
+        let adjustment = self
+            .entries
+            .get(&(key, mov.to_string()))
+            .map_or(0, |learned| learned.adjustment);
+        (num_plays as u64 * (100 + adjustment) as u64 / 100) as u32
+    }
+
+    /// Adjusts the weights of the book moves by the score of the game between -1 (lost) and 1 (won)
+    pub fn learn(&mut self, book_moves: &[(u64, Move)], score: f64) {
+        let step = (score * LEARN_STEP).round() as i32;
+        for (key, mov) in book_moves {
+            let learned = self.entries.entry((*key, mov.to_string())).or_default();
+            learned.adjustment = (learned.adjustment + step).clamp(-100, 100);
+            learned.games += 1;
+        }
+    }
+}
+
+/// The book moves the engine played in the current game and the evaluation after leaving the book
+#[derive(Default)]
+pub struct GameRecord {
+    book_moves: Vec<(u64, Move)>,
+    color: Option<Color>,
+    eval_after_book: Option<Eval>,
+}
+
+impl GameRecord {
+    pub fn add_book_move(&mut self, key: u64, mov: Move, color: Color) {
+        self.book_moves.push((key, mov));
+        self.color = Some(color);
+    }
+
+    pub fn book_moves(&self) -> &[(u64, Move)] {
+        &self.book_moves
+    }
+
+    /// Records the evaluation of the first search after the book moves from the perspective of the engine
+    pub fn add_search_eval(&mut self, eval: Eval) {
+        if !self.book_moves.is_empty() && self.eval_after_book.is_none() {
+            self.eval_after_book = Some(eval);
+        }
+    }
+
+    /// Score of the game for the engine between -1 and 1, the result and the evaluation after leaving the book
+    /// are averaged if both are known. Returns None without book moves or if neither is known.
+    pub fn score(&self, result: GameResult) -> Option<f64> {
+        let color = self.color?;
+        let result_score = if result.is_draw() {
+            Some(0.0)
+        } else {
+            result
+                .color_lost()
+                .map(|lost| if lost == color { -1.0 } else { 1.0 })
+        };
+        let eval_score = self
+            .eval_after_book
+            .map(|eval| (eval as f64 / WON_EVAL).clamp(-1.0, 1.0));
+
+        match (result_score, eval_score) {
+            (Some(result), Some(eval)) => Some((result + eval) / 2.0),
+            (Some(score), None) | (None, Some(score)) => Some(score),
+            (None, None) => None,
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_book_learning() {
+        use super::{GameRecord, LearnedWeights, OpeningBookError};
+        use crate::game::{Board, Color, GameResult, Move};
+
+        let board = Board::default();
+        let key = board.cur_state().zobrist;
+        let mov = Move::from_uci_notation("e2e4", &board).unwrap();
+        let path = std::env::temp_dir().join("chess_bot_test_learning.txt");
+        let path = path.to_string_lossy().to_string();
+
+        let mut record = GameRecord::default();
+        record.add_book_move(key, mov, Color::White);
+        record.add_search_eval(-400);
+        //lost game with a bad evaluation after the book
+        assert_eq!(record.score(GameResult::Mate(Color::White)), Some(-1.0));
+
+        let mut weights = LearnedWeights::load(&path).unwrap();
+        for _ in 0..3 {
+            weights.learn(record.book_moves(), -1.0);
+        }
+        assert_eq!(weights.weight(key, &mov, 10), 4);
+        weights.save().unwrap();
+
+        let mut weights = LearnedWeights::load(&path).unwrap();
+        assert_eq!(weights.weight(key, &mov, 10), 4);
+        weights.learn(record.book_moves(), -1.0);
+        weights.learn(record.book_moves(), -1.0);
+        assert_eq!(weights.weight(key, &mov, 10), 0);
+
+        //weights learned with different zobrist keys are rejected
+        let learn_path = format!("{path}.{}", super::LEARN_EXTENSION);
+        let content = std::fs::read_to_string(&learn_path).unwrap();
+        let header = format!("start {key:016x}");
+        assert!(content.starts_with(&header));
+        let other_keys = content.replacen(&header, &format!("start {:016x}", key ^ 1), 1);
+        std::fs::write(&learn_path, other_keys).unwrap();
+        assert!(matches!(
+            LearnedWeights::load(&path),
+            Err(OpeningBookError::IncompatibleKeys)
+        ));
+        std::fs::write(&learn_path, format!("{key:016x} e2e4 -60 3\n")).unwrap();
+        assert!(matches!(
+            LearnedWeights::load(&path),
+            Err(OpeningBookError::IncompatibleKeys)
+        ));
+        std::fs::remove_file(learn_path).ok();
+    }
+}
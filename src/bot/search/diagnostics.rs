@@ -3,6 +3,7 @@ pub struct SearchDiagnostics {
     pub(crate) node_count_qs: u64,
     pub(crate) cut_offs: u64,
     pub(crate) tt_hits: u64,
+    pub(crate) tb_hits: u64,
 }
 
 impl std::default::Default for SearchDiagnostics {
@@ -12,6 +13,7 @@ impl std::default::Default for SearchDiagnostics {
             node_count_qs: 0,
             cut_offs: 0,
             tt_hits: 0,
+            tb_hits: 0,
         }
     }
 }
@@ -20,12 +22,13 @@ impl std::fmt::Display for SearchDiagnostics {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Total: {} || Nodes: {}, Nodes QS: {}, Cut offs: {}, TT hits: {}",
+            "Total: {} || Nodes: {}, Nodes QS: {}, Cut offs: {}, TT hits: {}, TB hits: {}",
             self.total_nodes(),
             self.node_count,
             self.node_count_qs,
             self.cut_offs,
-            self.tt_hits
+            self.tt_hits,
+            self.tb_hits
         )
     }
 }
@@ -55,10 +58,16 @@ impl SearchDiagnostics {
         self.tt_hits += 1;
     }
 
+    #[inline(always)]
+    pub fn inc_tb_hits(&mut self) {
+        self.tb_hits += 1;
+    }
+
     pub fn reset(&mut self) {
         self.node_count = 0;
         self.node_count_qs = 0;
         self.cut_offs = 0;
         self.tt_hits = 0;
+        self.tb_hits = 0;
     }
 }
@@ -0,0 +1,237 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+};
+
+use crate::{
+    bot::file_utility::read_exact_at,
+    game::{Board, Move, MoveGeneration},
+};
+
+use super::opening_book::{BookMove, OpeningBook, OpeningBookError, StoredMove};
+
+const MAGIC: [u8; 4] = *b"CBOK";
+const VERSION: u8 = 1;
+/// Magic, version, key of the start position and number of entries
+const HEADER_SIZE: u64 = 4 + 1 + 8 + 8;
+/// Key, packed move and number of plays
+const ENTRY_SIZE: usize = 8 + 2 + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashedEntry {
+    pub key: u64,
+    pub mov: Move,
+    pub num_plays: u32,
+}
+
+impl HashedEntry {
+    fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> HashedEntry {
+        HashedEntry {
+            key: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            mov: Move::from_u16(u16::from_le_bytes(bytes[8..10].try_into().unwrap())),
+            num_plays: u32::from_le_bytes(bytes[10..14].try_into().unwrap()),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.mov.as_u16().to_le_bytes());
+        bytes[10..14].copy_from_slice(&self.num_plays.to_le_bytes());
+        bytes
+    }
+}
+
+/// #### Hashed opening book
+/// Binary book keyed by the zobrist key of the position with packed moves and their number of plays.
+/// The entries are sorted by the key and read from the file on lookup with a binary search,
+/// so opening the book is independent of its size.
+///
+/// The key of the start position is stored in the header, books created with different zobrist keys are rejected.
+pub struct HashedBook {
+    file: File,
+    len: u64,
+}
+
+impl HashedBook {
+    /// Checks if the file starts with the magic of a hashed book
+    pub fn is_hashed_book(path: &str) -> bool {
+        let mut magic = [0; 4];
+        File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok_and(|_| magic == MAGIC)
+    }
+
+    pub fn open(path: &str) -> Result<HashedBook, OpeningBookError> {
+        let mut file = File::open(path)?;
+        let mut header = [0; HEADER_SIZE as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| OpeningBookError::InvalidHeader)?;
+        if header[..4] != MAGIC || header[4] != VERSION {
+            return Err(OpeningBookError::InvalidHeader);
+        }
+
+        let start_key = u64::from_le_bytes(header[5..13].try_into().unwrap());
+        if start_key != Board::default().cur_state().zobrist {
+            return Err(OpeningBookError::IncompatibleKeys);
+        }
+
+        let len = u64::from_le_bytes(header[13..21].try_into().unwrap());
+        //a corrupted number of entries must not overflow the expected file size
+        let size = len
+            .checked_mul(ENTRY_SIZE as u64)
+            .and_then(|entries| entries.checked_add(HEADER_SIZE));
+        if size != Some(file.metadata()?.len()) {
+            return Err(OpeningBookError::InvalidHeader);
+        }
+        Ok(HashedBook { file, len })
+    }
+
+    /// Writes the entries sorted by their key
+    pub fn write(path: &str, mut entries: Vec<HashedEntry>) -> Result<(), OpeningBookError> {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.num_plays.cmp(&a.num_plays)));
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&MAGIC)?;
+        file.write_all(&[VERSION])?;
+        file.write_all(&Board::default().cur_state().zobrist.to_le_bytes())?;
+        file.write_all(&(entries.len() as u64).to_le_bytes())?;
+        for entry in entries {
+            file.write_all(&entry.to_bytes())?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn entry(&self, index: u64) -> std::io::Result<HashedEntry> {
+        let mut bytes = [0; ENTRY_SIZE];
+        read_exact_at(
+            &self.file,
+            &mut bytes,
+            HEADER_SIZE + index * ENTRY_SIZE as u64,
+        )?;
+        Ok(HashedEntry::from_bytes(&bytes))
+    }
+
+    /// All entries of the position key
+    pub fn entries(&self, key: u64) -> std::io::Result<Vec<HashedEntry>> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.entry(mid)?.key < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        let mut entries = Vec::new();
+        for index in low..self.len {
+            let entry = self.entry(index)?;
+            if entry.key != key {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Legal book moves of the position
+    pub fn book_moves(&self, board: &Board) -> std::io::Result<Vec<BookMove>> {
+        let legal_moves = MoveGeneration::generate_legal_moves(board);
+        Ok(self
+            .entries(board.cur_state().zobrist)?
+            .into_iter()
+            .filter(|entry| legal_moves.iter().any(|mov| *mov == entry.mov))
+            .map(|entry| BookMove {
+                mov: StoredMove::Packed(entry.mov),
+                num_plays: entry.num_plays,
+            })
+            .collect())
+    }
+}
+
+/// Converts a book in the text format to a hashed book, returns the number of written entries
+pub fn convert_text_book(text_book: &str, output: &str) -> Result<usize, OpeningBookError> {
+    let positions = OpeningBook::read_text_book(text_book)?;
+
+    let mut entries = Vec::new();
+    for (fen, moves) in positions {
+        let board = Board::from_fen(&format!("{fen} 0 1"))
+            .map_err(|_| OpeningBookError::InvalidPosition(fen.clone()))?;
+        for book_move in moves {
+            let mov = book_move
+                .to_move(&board)
+                .ok_or_else(|| OpeningBookError::InvalidPosition(fen.clone()))?;
+            entries.push(HashedEntry {
+                key: board.cur_state().zobrist,
+                mov,
+                num_plays: book_move.num_plays,
+            });
+        }
+    }
+
+    let len = entries.len();
+    HashedBook::write(output, entries)?;
+    Ok(len)
+}
+
+mod test {
+    #[test]
+    fn test_convert_text_book() {
+        use super::{convert_text_book, HashedBook, OpeningBookError};
+        use crate::game::{Board, Move};
+
+        let dir = std::env::temp_dir();
+        let text_path = dir.join("chess_bot_test_convert.txt");
+        let book_path = dir.join("chess_bot_test_convert.book");
+        let (text_path, book_path) = (
+            text_path.to_string_lossy().to_string(),
+            book_path.to_string_lossy().to_string(),
+        );
+        std::fs::write(
+            &text_path,
+            "pos rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\ne2e4 10\nd2d4 4\n\
+            pos rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3\nc7c5 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(convert_text_book(&text_path, &book_path).unwrap(), 3);
+        assert!(HashedBook::is_hashed_book(&book_path));
+        let book = HashedBook::open(&book_path).unwrap();
+
+        let mut board = Board::default();
+        let moves = |board: &Board| {
+            book.book_moves(board)
+                .unwrap()
+                .iter()
+                .map(|mov| (mov.to_move(board).unwrap().to_string(), mov.num_plays))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(moves(&board), vec![("e2e4".into(), 10), ("d2d4".into(), 4)]);
+
+        let mov = Move::from_uci_notation("e2e4", &board).unwrap();
+        board.make_move(&mov, false, true).unwrap();
+        assert_eq!(moves(&board), vec![("c7c5".into(), 3)]);
+
+        //a number of entries which overflows the file size is rejected
+        let mut bytes = std::fs::read(&book_path).unwrap();
+        bytes[13..21].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&book_path, bytes).unwrap();
+        assert!(matches!(
+            HashedBook::open(&book_path),
+            Err(OpeningBookError::InvalidHeader)
+        ));
+
+        std::fs::remove_file(text_path).ok();
+        std::fs::remove_file(book_path).ok();
+    }
+}
@@ -1,12 +1,15 @@
+mod book_learning;
 mod diagnostics;
+pub mod hashed_book;
 pub mod limit;
 mod move_ordering;
 mod opening_book;
+pub mod polyglot;
 mod pv_line;
 pub mod repetition_history;
 pub mod searcher;
 pub mod transposition_table;
-pub use opening_book::DEFAULT_OPENING_BOOK_ENABLED;
+pub use opening_book::{BookMode, DEFAULT_BOOK_DEPTH, DEFAULT_OPENING_BOOK_ENABLED};
 use std::sync::{atomic::AtomicBool, Arc};
 
 pub(super) type AbortFlag = Arc<AtomicBool>;
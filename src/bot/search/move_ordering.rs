@@ -2,7 +2,7 @@ use crate::game::{board::move_gen::LegalMoveList, Board, Move, PieceType};
 
 use super::pv_line::PVLine;
 
-const MAX_NUMBER_OF_MOVES_PER_POSITION: usize = 218;
+const MAX_NUMBER_OF_MOVES_PER_POSITION: usize = 512;
 type Score = u16;
 type MoveIdx = usize;
 
@@ -62,9 +62,7 @@ impl<'a> MoveOrdering<'a> {
                 move_ordering.inc_score(i, 999);
             }
 
-            let moved_piece = board
-                .get_sq_piece_variation(mv.source())
-                .expect("Move has no piece");
+            let moved_piece = mv.moved_piece_type(board).expect("Move has no piece");
 
             //capture moves
             if let Some(victim) = board.get_sq_piece_variation(mv.dest()) {
@@ -89,7 +87,7 @@ impl<'a> MoveOrdering<'a> {
             }
 
             //increase score if the move was at a dangerous square
-            if moves.get_masks().king_danger & mv.source().to_mask() != 0 {
+            if !mv.flag().is_drop() && moves.get_masks().king_danger & mv.source().to_mask() != 0 {
                 move_ordering.inc_score(i, 1);
             }
 
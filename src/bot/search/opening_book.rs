@@ -1,42 +1,197 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fs::File,
     io::{BufRead, BufReader},
+    time::SystemTime,
 };
 
 use lazy_static::lazy_static;
 use rand::Rng;
+use thiserror::Error;
 
 use crate::game::{Board, Move};
 
+use super::{
+    book_learning::LearnedWeights,
+    hashed_book::HashedBook,
+    polyglot::{PolyglotBook, PolyglotError},
+};
+
 lazy_static! {
     pub static ref DEFAULT_OPENING_BOOK_ENABLED: bool =
         std::env::var("OWN_BOOK").unwrap_or("1".into()) == "1";
 }
 
+pub const DEFAULT_BOOK_DEPTH: usize = 255;
+
+#[derive(Debug, Error)]
+pub enum OpeningBookError {
+    #[error("Failed to read the book: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid book entry in line {0}")]
+    InvalidLine(usize),
+    #[error("Invalid position or move in the book: {0}")]
+    InvalidPosition(String),
+    #[error("Invalid header of the hashed book")]
+    InvalidHeader,
+    #[error("Hashed book or learned weights were created with different zobrist keys")]
+    IncompatibleKeys,
+    #[error(transparent)]
+    Polyglot(#[from] PolyglotError),
+}
+
+/// Move of a book entry, either in uci notation which needs the board to be parsed or as packed move
+#[derive(Clone)]
+pub enum StoredMove {
+    Uci(String),
+    Packed(Move),
+}
+
+#[derive(Clone)]
 pub struct BookMove {
-    pub uci_move: String,
+    pub mov: StoredMove,
     pub num_plays: u32,
 }
 
+impl BookMove {
+    pub fn to_move(&self, board: &Board) -> Option<Move> {
+        match &self.mov {
+            StoredMove::Uci(uci_move) => Move::from_uci_notation(uci_move, board).ok(),
+            StoredMove::Packed(mov) => Some(*mov),
+        }
+    }
+}
+
+enum BookSource {
+    Text(HashMap<String, Vec<BookMove>>),
+    Polyglot(PolyglotBook),
+    Hashed(HashedBook),
+}
+
 pub struct OpeningBook {
-    moves: HashMap<String, (Vec<BookMove>, u32)>,
-    enabled: bool,
+    source: BookSource,
+    /// Weight adjustments learned from the played games
+    learned: LearnedWeights,
+    path: String,
+    /// Modification time of the file when the book was loaded
+    modified: Option<SystemTime>,
+}
+
+/// How a move is selected from the moves of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookMode {
+    /// The most played move
+    Best,
+    /// A random move weighted by the number of plays
+    #[default]
+    Weighted,
+    /// A random move
+    Uniform,
+}
+
+impl BookMode {
+    pub const ALL: [BookMode; 3] = [BookMode::Best, BookMode::Weighted, BookMode::Uniform];
+}
+
+impl std::fmt::Display for BookMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookMode::Best => write!(f, "best"),
+            BookMode::Weighted => write!(f, "weighted"),
+            BookMode::Uniform => write!(f, "uniform"),
+        }
+    }
+}
+
+impl std::str::FromStr for BookMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BookMode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// Settings of the book usage, independent of the loaded book
+#[derive(Debug, Clone)]
+pub struct BookOptions {
+    pub enabled: bool,
+    pub mode: BookMode,
+    /// Number of plies at the start of the game the book is used for
+    pub max_ply: usize,
+    /// Minimum number of plays (or Polyglot weight) of a move to be played
+    pub min_weight: u32,
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        BookOptions {
+            enabled: *DEFAULT_OPENING_BOOK_ENABLED,
+            mode: BookMode::default(),
+            max_ply: DEFAULT_BOOK_DEPTH,
+            min_weight: 1,
+        }
+    }
 }
 
 /// #### Opening book implementation
-/// The opening book is a simple text file that contains a list of positions and the most played moves in those positions.
+/// The opening book is a simple text file that contains a list of positions and the most played moves in those positions,
+/// a Polyglot book with the `.bin` extension or a hashed book converted from the text format.
 /// Can be used to play the opening phase of the game.
-/// If disabled, will return None for all queries.
+/// If disabled in the options, will return None for all queries.
 impl OpeningBook {
-    /// Creates a new opening book from a file  
-    /// The file must be in the following format:  
+    /// Creates a new opening book from a file, files with the `.bin` extension are read as Polyglot book,
+    /// files starting with the magic of the hashed book as hashed book  
+    /// Other files must be in the following format:  
     /// *pos \<fen without half move and ply clock>  
     /// \<move> \<number of plays>+  
     /// pos \<fen without half move and ply clock>  
     /// ...*  
-    pub fn new(book_file: String) -> Result<Self, ()> {
-        let file = File::open(book_file).map_err(|_| ())?;
+    pub fn new(book_file: String) -> Result<Self, OpeningBookError> {
+        let source = if book_file.ends_with(".bin") {
+            BookSource::Polyglot(PolyglotBook::open(&book_file)?)
+        } else if HashedBook::is_hashed_book(&book_file) {
+            BookSource::Hashed(HashedBook::open(&book_file)?)
+        } else {
+            BookSource::Text(OpeningBook::read_text_book(&book_file)?)
+        };
+
+        Ok(Self {
+            source,
+            learned: LearnedWeights::load(&book_file)?,
+            modified: OpeningBook::modification_time(&book_file),
+            path: book_file,
+        })
+    }
+
+    fn modification_time(path: &str) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Checks if the book file was changed since the book was loaded
+    pub fn is_modified(&self) -> bool {
+        OpeningBook::modification_time(&self.path) != self.modified
+    }
+
+    /// Adjusts the learned weights of the played book moves by the score of the game and stores them next to the book
+    pub fn learn(&mut self, book_moves: &[(u64, Move)], score: f64) -> std::io::Result<()> {
+        self.learned.learn(book_moves, score);
+        self.learned.save()
+    }
+
+    pub(super) fn read_text_book(
+        book_file: &str,
+    ) -> Result<HashMap<String, Vec<BookMove>>, OpeningBookError> {
+        let file = File::open(book_file)?;
 
         let mut moves = HashMap::new();
         let mut reader = BufReader::new(file);
@@ -44,36 +199,40 @@ impl OpeningBook {
 
         let mut cur_pos = String::new();
         let mut cur_moves = Vec::new();
-        let mut plays_counter = 0;
+        let mut line = 0;
         loop {
             buf.clear();
-            let res = reader.read_line(&mut buf).map_err(|_| ())?;
+            let res = reader.read_line(&mut buf)?;
+            line += 1;
 
             if res == 0 {
+                if !cur_pos.is_empty() {
+                    moves.insert(cur_pos, cur_moves);
+                }
                 break;
             }
 
             if buf.starts_with("pos ") {
                 if cur_pos != "" {
-                    moves.insert(cur_pos, (cur_moves, plays_counter));
+                    moves.insert(cur_pos, cur_moves);
                 }
 
                 cur_pos = buf.trim_start_matches("pos ").trim().to_string();
                 cur_moves = Vec::new();
-                plays_counter = 0;
                 continue;
             }
 
-            if let Some(book_move) = OpeningBook::parse_mov(buf.trim().to_string()) {
-                plays_counter += book_move.num_plays;
-                cur_moves.push(book_move);
+            if buf.trim().is_empty() {
+                continue;
+            }
+
+            match OpeningBook::parse_mov(buf.trim().to_string()) {
+                Some(book_move) if !cur_pos.is_empty() => cur_moves.push(book_move),
+                _ => return Err(OpeningBookError::InvalidLine(line)),
             }
         }
 
-        Ok(Self {
-            moves,
-            enabled: *DEFAULT_OPENING_BOOK_ENABLED,
-        })
+        Ok(moves)
     }
 
     fn parse_mov(pos: String) -> Option<BookMove> {
@@ -81,64 +240,139 @@ impl OpeningBook {
 
         let num_plays = num_plays.parse::<u32>().ok()?;
         Some(BookMove {
-            uci_move: mv.trim().to_string(),
+            mov: StoredMove::Uci(mv.trim().to_string()),
             num_plays,
         })
     }
 
-    pub fn is_enabled(&self) -> bool {
-        self.enabled
-    }
+    fn get_moves_entry(&self, board: &Board) -> Option<Cow<'_, [BookMove]>> {
+        match &self.source {
+            BookSource::Text(moves) => {
+                let fen = board.to_fen();
+
+                let fen = fen.split(" ").take(4).collect::<Vec<&str>>().join(" ");
 
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
+                moves.get(&fen).map(|moves| Cow::Borrowed(moves.as_slice()))
+            }
+            BookSource::Polyglot(book) => Some(Cow::Owned(book.book_moves(board))),
+            BookSource::Hashed(book) => match book.book_moves(board) {
+                Ok(moves) => Some(Cow::Owned(moves)),
+                Err(err) => {
+                    warn!("Failed to read the opening book {}: {err}", self.path);
+                    None
+                }
+            },
+        }
     }
 
-    fn get_moves_entry(&self, board: &Board) -> Option<&(Vec<BookMove>, u32)> {
-        if !self.enabled {
+    /// Returns a move from the opening book for the current position selected by the mode of the options.
+    /// The weights of the moves are adjusted by the learned weights, moves with a learned weight of 0 are avoided.
+    /// Returns None if the book is disabled, the position is deeper than the book depth
+    /// or no move of the position reaches the minimum weight
+    pub fn get_book_move(&self, board: &Board, options: &BookOptions) -> Option<Move> {
+        if !options.enabled || board.ply_count() > options.max_ply {
             return None;
         }
 
-        let fen = board.to_fen();
-
-        let fen = fen.split(" ").take(4).collect::<Vec<&str>>().join(" ");
+        let key = board.cur_state().zobrist;
+        let entry = self.get_moves_entry(board)?;
+        let moves: Vec<(Move, u32)> = entry
+            .iter()
+            .filter(|mov| mov.num_plays >= options.min_weight.max(1))
+            .filter_map(|book_move| {
+                let mov = book_move.to_move(board)?;
+                Some((mov, self.learned.weight(key, &mov, book_move.num_plays)))
+            })
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
 
-        self.moves.get(&fen)
+        match options.mode {
+            BookMode::Best => OpeningBook::get_most_played_book_move(&moves),
+            BookMode::Weighted => OpeningBook::get_random_book_move(&moves),
+            BookMode::Uniform => OpeningBook::get_uniform_book_move(&moves),
+        }
     }
 
-    /// Returns the most played move in the opening book for the current position
-    pub fn get_most_played_book_move(&self, board: &Board) -> Option<Move> {
-        let (moves, _) = self.get_moves_entry(board)?;
-
+    /// Returns the move with the highest weight
+    fn get_most_played_book_move(moves: &[(Move, u32)]) -> Option<Move> {
         let mut max = 0;
         let mut best_move = None;
-        for mov in moves {
-            if mov.num_plays > max {
-                max = mov.num_plays;
-                best_move = Some(mov);
+        for (mov, weight) in moves {
+            if *weight > max {
+                max = *weight;
+                best_move = Some(*mov);
             }
         }
-
-        match best_move {
-            Some(mv) => Move::from_uci_notation(&mv.uci_move, board).ok(),
-            None => todo!(),
-        }
+        best_move
     }
 
-    /// Returns a random move from the opening book for the current position weighted by the number of plays
-    pub fn get_random_book_move(&self, board: &Board) -> Option<Move> {
-        let (moves, num_plays) = self.get_moves_entry(board)?;
+    /// Returns a random move weighted by the number of plays
+    fn get_random_book_move(moves: &[(Move, u32)]) -> Option<Move> {
+        let num_plays: u32 = moves.iter().map(|(_, weight)| weight).sum();
+        if num_plays == 0 {
+            return None;
+        }
 
         let mut rng = rand::thread_rng();
-        let rand_num = rng.gen_range(0..*num_plays);
+        let rand_num = rng.gen_range(0..num_plays);
 
         let mut count = 0;
-        for mov in moves {
-            count += mov.num_plays;
-            if rand_num <= count {
-                return Move::from_uci_notation(&mov.uci_move, board).ok();
+        for (mov, weight) in moves {
+            count += weight;
+            if rand_num < count {
+                return Some(*mov);
             }
         }
         None
     }
+
+    /// Returns a random move, every move has the same probability
+    fn get_uniform_book_move(moves: &[(Move, u32)]) -> Option<Move> {
+        if moves.is_empty() {
+            return None;
+        }
+        let mut rng = rand::thread_rng();
+        Some(moves[rng.gen_range(0..moves.len())].0)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_book_options() {
+        use super::{BookMode, BookOptions, OpeningBook, OpeningBookError};
+        use crate::game::Board;
+
+        let path = std::env::temp_dir().join("chess_bot_test_book.txt");
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        std::fs::write(&path, format!("pos {start}\ne2e4 10\nd2d4 4\ng1f3 1\n")).unwrap();
+        let book = OpeningBook::new(path.to_string_lossy().into()).unwrap();
+        let board = Board::default();
+
+        let mut options = BookOptions {
+            enabled: true,
+            mode: BookMode::Best,
+            ..Default::default()
+        };
+        let book_move = |options: &BookOptions| {
+            book.get_book_move(&board, options)
+                .map(|mov| mov.to_string())
+        };
+        assert_eq!(book_move(&options).as_deref(), Some("e2e4"));
+
+        //only moves reaching the minimum weight are played
+        options.mode = BookMode::Uniform;
+        options.min_weight = 10;
+        assert_eq!(book_move(&options).as_deref(), Some("e2e4"));
+        options.min_weight = 11;
+        assert_eq!(book_move(&options), None);
+
+        options.min_weight = 1;
+        options.max_ply = 0;
+        assert_eq!(book_move(&options), None);
+
+        std::fs::write(&path, format!("pos {start}\ne2e4 ten\n")).unwrap();
+        let result = OpeningBook::new(path.to_string_lossy().into());
+        assert!(matches!(result, Err(OpeningBookError::InvalidLine(2))));
+        std::fs::remove_file(path).ok();
+    }
 }
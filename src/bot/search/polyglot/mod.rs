@@ -0,0 +1,309 @@
+mod random;
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
+
+use thiserror::Error;
+
+use crate::game::{
+    board::move_gen::attacks::ATTACK_PATTERN_PAWN, castle_rights::CastleType, Board, Color, Move,
+    MoveGeneration, PieceType, Square,
+};
+
+use super::opening_book::{BookMove, StoredMove};
+use random::RANDOM64;
+
+const ENTRY_SIZE: usize = 16;
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+#[derive(Debug, Error)]
+pub enum PolyglotError {
+    #[error("Failed to read the book: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Book size of {0} bytes is not a multiple of the entry size")]
+    InvalidSize(usize),
+}
+
+/// One entry of a Polyglot book, all values are stored big endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolyglotEntry {
+    pub key: u64,
+    pub mov: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl PolyglotEntry {
+    fn from_bytes(bytes: &[u8]) -> PolyglotEntry {
+        PolyglotEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mov: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mov.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+
+    /// Encodes the move of the board in the Polyglot format, castling is encoded as the king capturing its own rook
+    pub fn encode_move(mov: &Move, board: &Board) -> u16 {
+        let source = mov.source().square_value() as u16;
+        let dest = match mov.flag().castle_side() {
+            Some(castle_type) => {
+                let rights = board.cur_state().castling_rights;
+                rights.rook_positions(board.side_to_move(), castle_type).0
+            }
+            None => mov.dest(),
+        };
+        let promotion = mov.flag().promotion_type().map_or(0, |ptype| ptype as u16);
+        (promotion << 12) | (source << 6) | dest.square_value() as u16
+    }
+
+    /// Decodes the move into uci notation.
+    /// Polyglot encodes castling as the king capturing its own rook (e1h1), which is converted to the king move (e1g1)
+    pub fn uci_move(&self, board: &Board) -> String {
+        let from = Square::new(((self.mov >> 6) & 63) as u8);
+        let mut to = Square::new((self.mov & 63) as u8);
+
+        let king_move = board
+            .get_sq_piece(from)
+            .is_some_and(|piece| piece.ptype() == PieceType::King);
+        if king_move {
+            let color = board.side_to_move();
+            let rights = board.cur_state().castling_rights;
+            let castle_type = [CastleType::KingSide, CastleType::QueenSide]
+                .into_iter()
+                .find(|castle_type| {
+                    rights.has(color, *castle_type)
+                        && rights.rook_positions(color, *castle_type).0 == to
+                });
+            if let Some(castle_type) = castle_type {
+                to = castle_type.king_dest(color);
+            }
+        }
+
+        let promotion = match (self.mov >> 12) & 7 {
+            1 => "n",
+            2 => "b",
+            3 => "r",
+            4 => "q",
+            _ => "",
+        };
+        format!("{from}{to}{promotion}")
+    }
+}
+
+/// #### Polyglot opening book
+/// Reads the standard Polyglot `.bin` format: entries of 16 bytes sorted by the position key,
+/// the entries of a position are found with a binary search over the key.
+pub struct PolyglotBook {
+    entries: Vec<PolyglotEntry>,
+}
+
+impl PolyglotBook {
+    pub fn open(path: &str) -> Result<PolyglotBook, PolyglotError> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        PolyglotBook::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<PolyglotBook, PolyglotError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(PolyglotError::InvalidSize(bytes.len()));
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(PolyglotEntry::from_bytes)
+            .collect();
+        Ok(PolyglotBook { entries })
+    }
+
+    /// Creates a book from unsorted entries, the entries of a position are ordered by their weight
+    pub fn from_entries(mut entries: Vec<PolyglotEntry>) -> PolyglotBook {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        PolyglotBook { entries }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), PolyglotError> {
+        let mut file = std::io::BufWriter::new(File::create(path)?);
+        for entry in &self.entries {
+            file.write_all(&entry.to_bytes())?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries of the position key
+    pub fn entries(&self, key: u64) -> &[PolyglotEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+        &self.entries[start..end]
+    }
+
+    /// Legal book moves of the position with their weight as number of plays,
+    /// entries with weight 0 are not meant to be played and are skipped
+    pub fn book_moves(&self, board: &Board) -> Vec<BookMove> {
+        let legal_moves = MoveGeneration::generate_legal_moves(board);
+        self.entries(polyglot_key(board))
+            .iter()
+            .filter(|entry| entry.weight > 0)
+            .filter_map(|entry| {
+                let uci_move = entry.uci_move(board);
+                let mov = legal_moves.iter().find(|mov| mov.to_string() == uci_move)?;
+                Some(BookMove {
+                    mov: StoredMove::Packed(*mov),
+                    num_plays: entry.weight as u32,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Computes the Polyglot key of the position.
+/// The en passant file is only part of the key if a pawn of the side to move can capture en passant
+pub fn polyglot_key(board: &Board) -> u64 {
+    let mut key = 0;
+
+    let pieces = board.get_bb_pieces();
+    for color in [Color::White, Color::Black] {
+        for ptype in PieceType::iter() {
+            let kind = 2 * ptype as usize + (color == Color::White) as usize;
+            for square in pieces[color][ptype].get_occupied() {
+                key ^= RANDOM64[64 * kind + square.square_value() as usize];
+            }
+        }
+    }
+
+    let castling_rights = board.cur_state().castling_rights;
+    let castling = [
+        (Color::White, CastleType::KingSide),
+        (Color::White, CastleType::QueenSide),
+        (Color::Black, CastleType::KingSide),
+        (Color::Black, CastleType::QueenSide),
+    ];
+    for (i, (color, castle_type)) in castling.into_iter().enumerate() {
+        if castling_rights.has(color, castle_type) {
+            key ^= RANDOM64[CASTLING_OFFSET + i];
+        }
+    }
+
+    let side_to_move = board.side_to_move();
+    if let Some(en_passant) = board.cur_state().en_passant {
+        let capturers = ATTACK_PATTERN_PAWN[side_to_move.opposite()]
+            [en_passant.square_value() as usize]
+            & *pieces[side_to_move][PieceType::Pawn];
+        if capturers != 0 {
+            key ^= RANDOM64[EN_PASSANT_OFFSET + en_passant.file() as usize];
+        }
+    }
+
+    if side_to_move == Color::White {
+        key ^= RANDOM64[TURN_OFFSET];
+    }
+    key
+}
+
+mod test {
+    #[test]
+    fn test_polyglot_key() {
+        use super::polyglot_key;
+        use crate::game::Board;
+
+        //reference keys of the polyglot format specification
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ];
+        for (fen, key) in positions {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(polyglot_key(&board), key, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_polyglot_castling_move() {
+        use super::{polyglot_key, PolyglotBook, PolyglotEntry};
+        use crate::game::{Board, Move};
+
+        //castling is stored as the king capturing its own rook, the rooks of Chess960 are taken from the castle rights
+        for (fen, uci, castles, encoded) in [
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                ["e1g1", "e1c1"],
+                ["e1g1", "e1c1"],
+                [(4 << 6) | 7, 4 << 6],
+            ),
+            (
+                "rk5r/8/8/8/8/8/8/RK5R w KQkq - 0 1",
+                ["b1h1", "b1a1"],
+                ["b1g1", "b1c1"],
+                [(1 << 6) | 7, 1 << 6],
+            ),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let entries = uci
+                .iter()
+                .map(|uci| {
+                    let mov = Move::from_uci_notation(uci, &board).unwrap();
+                    PolyglotEntry {
+                        key: polyglot_key(&board),
+                        mov: PolyglotEntry::encode_move(&mov, &board),
+                        weight: 1,
+                        learn: 0,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!([entries[0].mov, entries[1].mov], encoded);
+
+            let book = PolyglotBook::from_entries(entries);
+            let moves: Vec<String> = book
+                .book_moves(&board)
+                .into_iter()
+                .map(|mov| mov.to_move(&board).unwrap().to_string())
+                .collect();
+            assert_eq!(moves, castles);
+        }
+    }
+}
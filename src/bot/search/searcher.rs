@@ -1,18 +1,32 @@
 use crate::{
     bot::{
-        evaluation::{eval::*, evaluate_board},
+        evaluate_board,
+        evaluation::{
+            eval::*,
+            evaluator::{create_evaluator, Evaluator, DEFAULT_EVALUATOR},
+            nnue::{NNUEEvaluator, Network},
+            params::EvalParams,
+        },
+        tablebase::{
+            dtm::{Dtm, DtmTablebases},
+            syzygy::{Tablebases, Wdl},
+        },
         AbortFlag, ReactionMessage, INFINITY_DEPTH,
     },
-    game::{board::move_gen::MoveGeneration, Board, Move},
+    game::{
+        board::{board_error::MoveError, move_gen::MoveGeneration},
+        Board, GameResult, Move, Variant,
+    },
     uci::commands::command_set_option::OptionType,
 };
-use std::sync::{atomic::Ordering, mpsc::Sender};
+use std::sync::{atomic::Ordering, mpsc::Sender, Arc};
 
 use super::{
+    book_learning::GameRecord,
     diagnostics::SearchDiagnostics,
     limit::Limits,
     move_ordering::MoveOrdering,
-    opening_book::OpeningBook,
+    opening_book::{BookOptions, OpeningBook},
     pv_line::PVLine,
     repetition_history::RepetitionHistory,
     transposition_table::{NodeType, TranspositionTable},
@@ -30,9 +44,16 @@ pub struct Searcher {
     tt: TranspositionTable,
     pv_line: PVLine,
     opening_book: Option<OpeningBook>,
+    book_options: BookOptions,
+    game_record: GameRecord,
     repetition_history: RepetitionHistory,
     limits: Limits,
     search_moves: Option<Vec<Move>>,
+    evaluator: Box<dyn Evaluator>,
+    network: Option<Arc<Network>>,
+    params: Arc<EvalParams>,
+    tablebases: Option<Tablebases>,
+    dtm_tablebases: Option<DtmTablebases>,
 }
 
 impl Searcher {
@@ -41,8 +62,11 @@ impl Searcher {
         msg_channel: Sender<ReactionMessage>,
         flag: AbortFlag,
         opening_book_file: String,
+        eval_file: String,
+        syzygy_path: String,
+        params: Arc<EvalParams>,
     ) -> Self {
-        Self {
+        let mut searcher = Self {
             best: None,
             board: Board::default(),
             aborted: false,
@@ -51,13 +75,146 @@ impl Searcher {
             flag,
             tt,
             pv_line: PVLine::default(),
-            opening_book: OpeningBook::new(opening_book_file).ok(),
+            opening_book: None,
+            book_options: BookOptions::default(),
+            game_record: GameRecord::default(),
             repetition_history: RepetitionHistory::new(),
             limits: Limits::default(),
             search_moves: None,
+            evaluator: create_evaluator(DEFAULT_EVALUATOR, None, params.clone())
+                .expect("Default evaluator must be registered"),
+            network: None,
+            params,
+            tablebases: None,
+            dtm_tablebases: None,
+        };
+        if !opening_book_file.is_empty() {
+            searcher.load_opening_book(&opening_book_file);
+        }
+        if !eval_file.is_empty() {
+            searcher.load_network(&eval_file);
+        }
+        if !syzygy_path.is_empty() {
+            searcher.load_tablebases(&syzygy_path);
+        }
+        searcher
+    }
+
+    /// Loads the opening book, text or Polyglot `.bin` book, an empty path unloads the book.
+    /// Setting the same path again reloads the book from disk.
+    fn load_opening_book(&mut self, path: &str) {
+        if path.is_empty() {
+            self.opening_book = None;
+            info!("Opening book unloaded");
+            return;
+        }
+
+        match OpeningBook::new(path.to_string()) {
+            Ok(book) => {
+                info!("Opening book loaded from {path}");
+                self.opening_book = Some(book);
+            }
+            Err(err) => {
+                warn!("Failed to load opening book {path}: {err}");
+                self.opening_book = None;
+                self.send_info(format!("string Failed to load opening book {path}: {err}"));
+            }
         }
     }
 
+    /// Loads the syzygy tablebases from the directories of the path,
+    /// an empty path unloads the tablebases.
+    fn load_tablebases(&mut self, path: &str) {
+        if path.is_empty() {
+            self.tablebases = None;
+            info!("Tablebases unloaded");
+            return;
+        }
+
+        match Tablebases::open(path) {
+            Ok(tablebases) => {
+                info!(
+                    "Found {} tablebases with up to {} pieces in {path}",
+                    tablebases.table_count(),
+                    tablebases.max_pieces()
+                );
+                self.tablebases = Some(tablebases);
+            }
+            Err(err) => {
+                warn!("Failed to load tablebases {path}: {err}");
+                self.tablebases = None;
+                self.send_info(format!("string Failed to load tablebases {path}: {err}"));
+            }
+        }
+    }
+
+    /// Loads the generated distance to mate tables from the directories of the path,
+    /// an empty path unloads the tables.
+    fn load_dtm_tablebases(&mut self, path: &str) {
+        if path.is_empty() {
+            self.dtm_tablebases = None;
+            info!("DTM tables unloaded");
+            return;
+        }
+
+        match DtmTablebases::open(path) {
+            Ok(tablebases) => {
+                info!(
+                    "Found {} DTM tables with up to {} pieces in {path}",
+                    tablebases.table_count(),
+                    tablebases.max_pieces()
+                );
+                self.dtm_tablebases = Some(tablebases);
+            }
+            Err(err) => {
+                warn!("Failed to load DTM tables {path}: {err}");
+                self.dtm_tablebases = None;
+                self.send_info(format!("string Failed to load DTM tables {path}: {err}"));
+            }
+        }
+    }
+
+    /// Loads the network file and switches to the NNUE evaluator,
+    /// an empty path unloads the network and switches back to the default evaluator.
+    /// If the network can't be loaded the current evaluator is kept.
+    fn load_network(&mut self, path: &str) {
+        if path.is_empty() {
+            self.network = None;
+            self.evaluator = create_evaluator(DEFAULT_EVALUATOR, None, self.params.clone())
+                .expect("Default evaluator must be registered");
+            info!(
+                "Network unloaded, evaluator set to {}",
+                self.evaluator.name()
+            );
+            return;
+        }
+
+        match Network::from_file(path) {
+            Ok(network) => {
+                let network = Arc::new(network);
+                self.network = Some(network.clone());
+                self.evaluator = Box::new(NNUEEvaluator::new(Some(network), self.params.clone()));
+                info!("Network loaded from {path}, evaluator set to NNUE");
+            }
+            Err(err) => {
+                warn!("Failed to load network {path}: {err}");
+                self.send_info(format!("string Failed to load network {path}: {err}"));
+            }
+        }
+    }
+
+    /// Replaces the evaluation parameters of the current evaluator
+    pub fn set_eval_params(&mut self, params: Arc<EvalParams>) {
+        self.params = params;
+        self.evaluator = create_evaluator(
+            self.evaluator.name(),
+            self.network.clone(),
+            self.params.clone(),
+        )
+        .expect("The current evaluator must be registered");
+        info!("Evaluation parameters updated");
+    }
+
     pub fn handle_set_option(&mut self, option: OptionType) {
         match option {
             OptionType::ClearHash => {
@@ -75,13 +232,70 @@ impl Searcher {
                 todo!("Debug file option not implemented yet");
             }
             OptionType::OwnBook(value) => {
-                if let Some(book) = &mut self.opening_book {
-                    book.set_enabled(value);
-                    info!("Opening book enabled: {value}");
-                } else {
-                    warn!("No opening book loaded, cannot enable or disable it");
+                self.book_options.enabled = value;
+                info!("Opening book enabled: {value}");
+                if self.opening_book.is_none() {
+                    warn!("No opening book loaded");
                 }
             }
+            OptionType::BookFile(path) => self.load_opening_book(&path),
+            OptionType::BookMode(mode) => {
+                self.book_options.mode = mode;
+                info!("Opening book mode set to {mode}");
+            }
+            OptionType::BookDepth(depth) => {
+                self.book_options.max_ply = depth;
+                info!("Opening book depth set to {depth} plies");
+            }
+            OptionType::BookMinWeight(weight) => {
+                self.book_options.min_weight = weight;
+                info!("Opening book minimum weight set to {weight}");
+            }
+            OptionType::Evaluator(name) => {
+                match create_evaluator(&name, self.network.clone(), self.params.clone()) {
+                    Some(evaluator) => {
+                        if evaluator.name() == NNUEEvaluator::NAME && self.network.is_none() {
+                            warn!("No network loaded, NNUE falls back to the classic evaluation");
+                        }
+                        info!("Evaluator set to {}", evaluator.name());
+                        self.evaluator = evaluator;
+                    }
+                    None => warn!("No evaluator registered with the name {name}"),
+                }
+            }
+            OptionType::EvalFile(path) => self.load_network(&path),
+            OptionType::EvalParams(_) => {
+                warn!("Evaluation parameters are loaded by the bot and passed as parameter set");
+            }
+            OptionType::SyzygyPath(path) => self.load_tablebases(&path),
+            OptionType::DtmPath(path) => self.load_dtm_tablebases(&path),
+            OptionType::Chess960(_) => {
+                warn!("Chess960 is set on the board passed to the search");
+            }
+            OptionType::Variant(_) => {
+                warn!("The variant is set on the board passed to the search");
+            }
+        }
+    }
+
+    /// Learns the weights of the book moves played in the finished game from the result
+    /// and the evaluation after leaving the book, then starts recording the next game
+    pub fn game_over(&mut self, result: GameResult) {
+        let record = std::mem::take(&mut self.game_record);
+        let (Some(opening_book), Some(score)) = (&mut self.opening_book, record.score(result))
+        else {
+            return;
+        };
+
+        info!(
+            "Learning {} book moves with score {score:.2} from game result {result:?}",
+            record.book_moves().len()
+        );
+        if let Err(err) = opening_book.learn(record.book_moves(), score) {
+            warn!("Failed to store the learned book weights: {err}");
+            self.send_info(format!(
+                "string Failed to store the learned book weights: {err}"
+            ));
         }
     }
 
@@ -94,16 +308,40 @@ impl Searcher {
         self.repetition_history.init(&self.board);
         self.limits = limits;
         self.search_moves = search_moves;
+        if self.board.variant().has_royal_king() {
+            self.evaluator.on_new_position(&self.board);
+        }
 
         info!(
             "Transposition Table Usage: {:.2}%",
             self.tt.get_usage() * 100_f64
         );
 
-        // Check if we have a move in the opening book
-        if let Some(opening_book) = &self.opening_book {
-            if let Some(mv) = opening_book.get_random_book_move(&self.board) {
+        // Reload the opening book if the file changed
+        if let Some(path) = self
+            .opening_book
+            .as_ref()
+            .filter(|book| book.is_modified())
+            .map(|book| book.path().to_string())
+        {
+            info!("Opening book {path} changed, reloading");
+            self.load_opening_book(&path);
+        }
+
+        // Check if we have a move in the opening book, books are only built for standard chess
+        // and encode castling from the standard start position
+        if let Some(opening_book) = self
+            .opening_book
+            .as_ref()
+            .filter(|_| self.board.variant() == Variant::Standard && !self.board.is_chess960())
+        {
+            if let Some(mv) = opening_book.get_book_move(&self.board, &self.book_options) {
                 info!("Play opening book move: {:?}", mv);
+                self.game_record.add_book_move(
+                    self.board.cur_state().zobrist,
+                    mv,
+                    self.board.side_to_move(),
+                );
                 self.msg_channel
                     .send(ReactionMessage::BestMove(mv))
                     .unwrap();
@@ -111,6 +349,8 @@ impl Searcher {
             }
         }
 
+        self.filter_tablebase_root_moves();
+
         self.iterative_deepening();
         info!("Search Diagnostics: {}", self.diagnostics);
 
@@ -120,6 +360,7 @@ impl Searcher {
 
             moves.get(0).map(|m| (m, 0)).unwrap_or_default()
         });
+        self.game_record.add_search_eval(result.1);
 
         self.msg_channel
             .send(ReactionMessage::BestMove(result.0))
@@ -143,6 +384,146 @@ impl Searcher {
         false
     }
 
+    #[inline(always)]
+    /// Makes the move on the search board and informs the evaluator about it
+    fn make_move(&mut self, mov: &Move, validate: bool) -> Result<(), MoveError> {
+        self.board.make_move(mov, true, validate)?;
+        if self.board.variant().has_royal_king() {
+            self.evaluator.on_make_move(&self.board, mov);
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    /// Undoes the move on the search board and informs the evaluator about it
+    fn undo_move(&mut self, mov: &Move) {
+        self.board
+            .undo_move(mov, true)
+            .expect("Search must only undo moves it made");
+        if self.board.variant().has_royal_king() {
+            self.evaluator.on_undo_move(&self.board, mov);
+        }
+    }
+
+    /// Evaluation of positions ended by the rules of the variant
+    fn variant_eval(&self, ply_from_root: u8) -> Option<Eval> {
+        let result = self.board.variant().game_result(&self.board)?;
+        Some(self.result_eval(result, ply_from_root))
+    }
+
+    /// Evaluation of a finished game, wins and losses are scored like mates
+    fn result_eval(&self, result: GameResult, ply_from_root: u8) -> Eval {
+        match result.color_lost() {
+            Some(color) if color == self.board.side_to_move() => -(MATE - ply_from_root as Eval),
+            Some(_) => MATE - ply_from_root as Eval,
+            None => DRAW,
+        }
+    }
+
+    /// Static evaluation of the search board. Variants without a royal king have inverted goals
+    /// the evaluators don't know, they are evaluated by the classic evaluation
+    fn evaluate(&mut self, alpha: Eval, beta: Eval) -> Eval {
+        if !self.board.variant().has_royal_king() {
+            return evaluate_board(&self.board, &self.params);
+        }
+
+        //lazy evaluation, skip the full evaluation if the estimate is far outside of the window
+        match self.evaluator.lazy_bound(&self.board) {
+            Some((estimate, margin)) if estimate - margin >= beta || estimate + margin <= alpha => {
+                estimate
+            }
+            _ => self.evaluator.evaluate(&self.board),
+        }
+    }
+
+    /// Restricts the root moves to the best moves of the tablebase ranking if the root position is in the tablebases.
+    /// The ranking considers the ply clock, so a won ending is converted without running into the fifty move rule.
+    fn filter_tablebase_root_moves(&mut self) {
+        let Some(tablebases) = &self.tablebases else {
+            return;
+        };
+        if self.board.variant() != Variant::Standard || !tablebases.can_probe(&self.board) {
+            return;
+        }
+
+        let moves: Vec<Move> = MoveGeneration::generate_legal_moves(&self.board)
+            .iter()
+            .filter(|mov| {
+                self.search_moves
+                    .as_ref()
+                    .is_none_or(|search_moves| search_moves.contains(mov))
+            })
+            .copied()
+            .collect();
+
+        let ranked = tablebases
+            .rank_root_moves(&mut self.board, &moves)
+            .or_else(|err| {
+                info!("DTZ root probe failed, ranking by WDL: {err}");
+                tablebases.rank_root_moves_wdl(&mut self.board, &moves)
+            });
+        match ranked {
+            Ok(ranked) => {
+                self.diagnostics.tb_hits += ranked.len() as u64;
+                let Some(best_rank) = ranked.iter().map(|mov| mov.rank).max() else {
+                    return;
+                };
+                let best_moves: Vec<Move> = ranked
+                    .iter()
+                    .filter(|mov| mov.rank == best_rank)
+                    .map(|mov| mov.mov)
+                    .collect();
+                info!(
+                    "Tablebase root moves with rank {best_rank}: {}",
+                    best_moves
+                        .iter()
+                        .map(|mov| mov.as_uci_notation())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                );
+                self.search_moves = Some(best_moves);
+            }
+            Err(err) => warn!("Tablebase root probe failed: {err}"),
+        }
+    }
+
+    /// Probes the WDL tables if the position may be stored in the tablebases.
+    /// Positions are only probed directly after a capture or pawn move as the tables don't know the ply clock.
+    fn probe_tablebases(&mut self) -> Option<Wdl> {
+        let tablebases = self.tablebases.as_ref()?;
+        if self.board.cur_state().ply_clock != 0
+            || self.board.variant() != Variant::Standard
+            || !tablebases.can_probe(&self.board)
+        {
+            return None;
+        }
+
+        let wdl = tablebases.probe_wdl(&mut self.board).ok()?;
+        self.diagnostics.inc_tb_hits();
+        Some(wdl)
+    }
+
+    /// Probes the generated tables, the distance to mate ignores the fifty move rule,
+    /// so wins and losses are only used if the mate happens before the ply clock runs out.
+    fn probe_dtm_tablebases(&mut self) -> Option<Dtm> {
+        let tablebases = self.dtm_tablebases.as_ref()?;
+        if self.board.variant() != Variant::Standard || !tablebases.can_probe(&self.board) {
+            return None;
+        }
+
+        let dtm = tablebases.probe(&mut self.board).ok()?;
+        let ply_clock = self.board.cur_state().ply_clock as usize;
+        let in_time = match dtm {
+            Dtm::Win(plies) | Dtm::Loss(plies) => ply_clock + (plies as usize) < 100,
+            Dtm::Draw => true,
+        };
+        if !in_time {
+            return None;
+        }
+        self.diagnostics.inc_tb_hits();
+        Some(dtm)
+    }
+
     fn send_info(&self, msg: String) {
         self.msg_channel.send(ReactionMessage::Info(msg)).ok();
     }
@@ -160,13 +541,18 @@ impl Searcher {
 
             if let Some((_, eval)) = best_this_iteration {
                 self.send_info(format!(
-                    "depth {} score {} nodes {:?} time {} hashfull {:.2} pv {}",
+                    "depth {} score {} nodes {:?} tbhits {} time {} hashfull {:.2} pv {}",
                     depth,
                     display_eval(eval),
                     self.diagnostics.node_count,
+                    self.diagnostics.tb_hits,
                     elapsed,
                     self.tt.get_usage() * 100_f64,
                     self.pv_line
+                        .moves()
+                        .iter()
+                        .map(|mov| format!("{} ", mov.to_uci(&self.board)))
+                        .collect::<String>()
                 ));
 
                 //close the search if a mate score is found
@@ -206,7 +592,7 @@ impl Searcher {
                 Some(mv) => mv,
                 None => break,
             };
-            let valid = self.board.make_move(&mv, true, true);
+            let valid = self.make_move(&mv, true);
 
             if valid.is_err() {
                 warn!("Stopping PVLine due to invalid move in TT entry: {}", mv);
@@ -218,9 +604,8 @@ impl Searcher {
 
         for i in 0..self.pv_line.len() {
             let idx: usize = self.pv_line.len() - i - 1;
-            self.board
-                .undo_move(&self.pv_line.get_move(idx).unwrap(), true)
-                .unwrap();
+            let mv = *self.pv_line.get_move(idx).unwrap();
+            self.undo_move(&mv);
         }
     }
 
@@ -245,6 +630,10 @@ impl Searcher {
             return DRAW;
         }
 
+        if let Some(eval) = self.variant_eval(ply_from_root) {
+            return eval;
+        }
+
         let mut tt_move = None;
         if let Some(entry) = self.tt.get_entry(key, ply_remaining) {
             self.diagnostics.inc_tt_hits();
@@ -277,6 +666,36 @@ impl Searcher {
             }
         }
 
+        //the generated tables know the exact distance to mate
+        if ply_from_root > 0 {
+            if let Some(dtm) = self.probe_dtm_tablebases() {
+                return match dtm {
+                    Dtm::Win(plies) => MATE - ply_from_root as Eval - plies as Eval,
+                    Dtm::Loss(plies) => -MATE + ply_from_root as Eval + plies as Eval,
+                    Dtm::Draw => DRAW,
+                };
+            }
+        }
+
+        //tablebase wins and losses are bounds, the search may still find a mate
+        if ply_from_root > 0 {
+            if let Some(wdl) = self.probe_tablebases() {
+                let eval = match wdl {
+                    Wdl::Win => TB_WIN - ply_from_root as Eval,
+                    Wdl::Loss => -TB_WIN + ply_from_root as Eval,
+                    //wins and losses drawn by the fifty move rule are scored slightly above or below a draw
+                    _ => return DRAW + 2 * wdl as Eval,
+                };
+                match wdl {
+                    Wdl::Win => alpha = alpha.max(eval),
+                    _ => beta = beta.min(eval),
+                }
+                if alpha >= beta {
+                    return eval;
+                }
+            }
+        }
+
         if self
             .limits
             .is_any_terminal(self.diagnostics.node_count, ply_from_root)
@@ -286,11 +705,12 @@ impl Searcher {
         }
 
         let moves = MoveGeneration::generate_legal_moves(&self.board);
-        if moves.is_checkmate() {
-            return -(MATE - ply_from_root as Eval);
-        }
-        if moves.is_stalemate() {
-            return DRAW;
+        if moves.is_empty() {
+            let result = self
+                .board
+                .variant()
+                .no_moves_result(&self.board, moves.get_masks().in_check);
+            return self.result_eval(result, ply_from_root);
         }
 
         let mut ordered_moves =
@@ -309,12 +729,12 @@ impl Searcher {
             }
 
             self.repetition_history.push_hash(key, false);
-            self.board.make_move(&mov, true, false).unwrap();
+            self.make_move(&mov, false).unwrap();
 
             let eval = -self.nega_max(ply_remaining - 1, ply_from_root + 1, -beta, -alpha);
 
             self.repetition_history.pop_hash();
-            self.board.undo_move(&mov, true).unwrap();
+            self.undo_move(&mov);
 
             if self.search_cancelled() {
                 return 0;
@@ -387,12 +807,17 @@ impl Searcher {
             return DRAW;
         }
 
-        let moves = MoveGeneration::generate_legal_moves(&self.board);
-        if moves.is_checkmate() {
-            return -(MATE - ply_from_root as Eval);
+        if let Some(eval) = self.variant_eval(ply_from_root) {
+            return eval;
         }
-        if moves.is_stalemate() {
-            return DRAW;
+
+        let moves = MoveGeneration::generate_legal_moves(&self.board);
+        if moves.is_empty() {
+            let result = self
+                .board
+                .variant()
+                .no_moves_result(&self.board, moves.get_masks().in_check);
+            return self.result_eval(result, ply_from_root);
         }
 
         //check extensions
@@ -400,19 +825,24 @@ impl Searcher {
             ply_remaining += 1;
         }
 
-        let mut eval = evaluate_board(&self.board);
-        if eval >= beta {
-            return beta;
-        }
-        if eval > alpha {
-            alpha = eval;
+        let moves = moves.to_captures_only();
+        //standing pat isn't possible if a capture is compulsory
+        let forced_capture = self.board.variant().forced_captures() && !moves.is_empty();
+
+        let mut eval = self.evaluate(alpha, beta);
+        if !forced_capture {
+            if eval >= beta {
+                return beta;
+            }
+            if eval > alpha {
+                alpha = eval;
+            }
         }
 
         if ply_remaining == 0 || ply_from_root >= MAX_QS_DEPTH {
             return eval;
         }
 
-        let moves = moves.to_captures_only();
         let mut move_order = MoveOrdering::score_moves(
             &moves,
             &self.pv_line,
@@ -422,12 +852,12 @@ impl Searcher {
         );
         while let Some(mv) = move_order.pick_next_move() {
             self.repetition_history.push_hash(key, false);
-            self.board.make_move(&mv, true, false).unwrap();
+            self.make_move(&mv, false).unwrap();
 
             eval = -self.quiescence_search(ply_from_root + 1, ply_remaining - 1, -beta, -alpha);
 
             self.repetition_history.pop_hash();
-            self.board.undo_move(&mv, true).unwrap();
+            self.undo_move(&mv);
 
             if eval >= beta {
                 self.diagnostics.inc_cut_offs();
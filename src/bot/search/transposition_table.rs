@@ -1,5 +1,5 @@
 use crate::{
-    bot::evaluation::eval::{is_mate_score, Eval, MATE},
+    bot::evaluation::eval::{is_mate_score, is_tb_score, Eval, MATE},
     game::Move,
 };
 
@@ -109,10 +109,10 @@ impl TranspositionTable {
             return;
         }
 
-        //correct mate score
+        //correct mate score (tablebase scores are corrected the same way)
         //see more about correcting mate scores:
         //https://github.com/maksimKorzh/chess_programming/blob/9f2dbc2c1bb1f5e405aa9c88cac18840829a29eb/src/bbc/tt_search_mating_scores/TT_mate_scoring.txt
-        if is_mate_score(eval) {
+        if is_mate_score(eval) || is_tb_score(eval) {
             let n = MATE - eval.abs();
             let k = ply_from_root as Eval;
             let distance = n - k;
@@ -172,7 +172,7 @@ impl TranspositionTable {
     /// see more about correcting mate scores:
     /// https://github.com/maksimKorzh/chess_programming/blob/9f2dbc2c1bb1f5e405aa9c88cac18840829a29eb/src/bbc/tt_search_mating_scores/TT_mate_scoring.txt
     pub fn correct_retrieved_mate_score(eval: &mut Eval, ply_from_root: u8) {
-        if is_mate_score(*eval) {
+        if is_mate_score(*eval) || is_tb_score(*eval) {
             *eval = (eval.abs() - ply_from_root as Eval) * eval.signum();
         }
     }
@@ -0,0 +1,200 @@
+use crate::game::{Board, Color, Move, MoveGeneration};
+
+use super::{
+    index::{DtmMaterial, Squares},
+    Dtm, DtmError, DtmTable, DtmTablebases, DRAW, INVALID, MAX_PLIES,
+};
+
+/// Value of legal positions which are not resolved yet
+const UNRESOLVED: u8 = INVALID - 1;
+
+/*
+    Retrograde analysis of all positions of one material.
+
+    Every position is first searched one ply with the move generation of the board:
+    mates are lost in 0 plies, captures and promotions are looked up in the tables of the smaller materials
+    and the remaining moves are counted per distinct successor position.
+    The positions are then resolved in order of their distance to mate. Starting from the resolved positions
+    the un-moves are generated to find their predecessors: a predecessor of a lost position is won one ply later,
+    a predecessor of a won position is lost once all of its successors are won for the other side.
+    Positions which are never resolved are draws.
+*/
+struct Generator<'a> {
+    material: &'a DtmMaterial,
+    values: Vec<u8>,
+    /// Number of successor positions which are not known to be won for the other side
+    remaining: Vec<u8>,
+    /// Longest loss in plies of the moves resolved so far
+    loss_plies: Vec<u8>,
+    /// The position has a move which doesn't lose (a drawing or winning capture or promotion)
+    escape: Vec<bool>,
+    /// Positions to resolve indexed by their distance to mate, the parity tells if the position is won
+    buckets: Vec<Vec<u32>>,
+}
+
+/// Generates the table of the material, the tables of all materials reachable by captures and promotions must be loaded
+pub fn generate(material: &DtmMaterial, tablebases: &DtmTablebases) -> Result<DtmTable, DtmError> {
+    let size = material.size();
+    let mut generator = Generator {
+        material,
+        values: vec![INVALID; size],
+        remaining: vec![0; size],
+        loss_plies: vec![0; size],
+        escape: vec![false; size],
+        buckets: vec![Vec::new(); MAX_PLIES as usize + 2],
+    };
+
+    for index in 0..size {
+        generator.init_position(index, tablebases)?;
+    }
+
+    let mut plies = 0;
+    while plies < generator.buckets.len() {
+        let bucket = std::mem::take(&mut generator.buckets[plies]);
+        for index in bucket {
+            generator.resolve(index as usize, plies)?;
+        }
+        plies += 1;
+    }
+
+    for value in generator.values.iter_mut() {
+        if *value == UNRESOLVED {
+            *value = DRAW;
+        }
+    }
+    Ok(DtmTable::from_values(material.clone(), generator.values))
+}
+
+impl Generator<'_> {
+    /// Searches the moves of the position, resolves mates and stalemates and
+    /// schedules the results of captures and promotions
+    fn init_position(&mut self, index: usize, tablebases: &DtmTablebases) -> Result<(), DtmError> {
+        let Some((squares, side_to_move)) = self.material.decode(index) else {
+            return Ok(());
+        };
+        let mut board = self.material.board(&squares, side_to_move);
+        self.values[index] = UNRESOLVED;
+
+        let moves = MoveGeneration::generate_legal_moves(&board);
+        if moves.is_empty() {
+            if moves.get_masks().in_check {
+                self.buckets[0].push(index as u32);
+            } else {
+                self.values[index] = DRAW;
+            }
+            return Ok(());
+        }
+
+        let mut fastest_win = None;
+        let mut successors = Vec::with_capacity(moves.len());
+        for mov in moves.iter() {
+            let conversion = is_conversion(&board, mov);
+            board
+                .make_move(mov, true, false)
+                .expect("Move generation should generate only legal moves");
+            let result = if conversion {
+                tablebases.probe(&mut board).map(|dtm| Some(dtm.parent()))
+            } else {
+                let squares = self
+                    .material
+                    .squares(&board)
+                    .expect("Quiet moves keep the material");
+                successors.push(self.material.index(&squares, board.side_to_move()));
+                Ok(None)
+            };
+            board
+                .undo_move(mov, true)
+                .expect("Generation must only undo moves it made");
+
+            match result? {
+                Some(Dtm::Win(plies)) => {
+                    fastest_win = Some(fastest_win.map_or(plies, |fastest: u8| fastest.min(plies)));
+                    self.escape[index] = true;
+                }
+                Some(Dtm::Draw) => self.escape[index] = true,
+                Some(Dtm::Loss(plies)) => {
+                    self.loss_plies[index] = self.loss_plies[index].max(plies)
+                }
+                None => {}
+            }
+        }
+
+        successors.sort_unstable();
+        successors.dedup();
+        self.remaining[index] = successors.len() as u8;
+
+        if let Some(plies) = fastest_win {
+            self.schedule(index, plies as usize);
+        } else if successors.is_empty() && !self.escape[index] {
+            self.schedule(index, self.loss_plies[index] as usize);
+        }
+        Ok(())
+    }
+
+    /// Resolves the position with the distance to mate and updates all predecessors
+    fn resolve(&mut self, index: usize, plies: usize) -> Result<(), DtmError> {
+        if self.values[index] != UNRESOLVED {
+            return Ok(());
+        }
+        if plies > MAX_PLIES as usize {
+            return Err(DtmError::DistanceOverflow(self.material.name().to_string()));
+        }
+        let won = plies % 2 == 1;
+        let dtm = if won {
+            Dtm::Win(plies as u8)
+        } else {
+            Dtm::Loss(plies as u8)
+        };
+        self.values[index] = dtm.value();
+
+        let (squares, side_to_move) = self
+            .material
+            .decode(index)
+            .expect("Only legal positions are resolved");
+        for predecessor in self.predecessors(&squares, side_to_move) {
+            if self.values[predecessor] != UNRESOLVED {
+                continue;
+            }
+            if !won {
+                self.schedule(predecessor, plies + 1);
+                continue;
+            }
+
+            self.remaining[predecessor] -= 1;
+            self.loss_plies[predecessor] = self.loss_plies[predecessor].max(plies as u8 + 1);
+            if self.remaining[predecessor] == 0 && !self.escape[predecessor] {
+                self.schedule(predecessor, self.loss_plies[predecessor] as usize);
+            }
+        }
+        Ok(())
+    }
+
+    /// Distinct indices of the positions with a move to the position or one of its symmetric images
+    fn predecessors(&self, squares: &Squares, side_to_move: Color) -> Vec<usize> {
+        let mover = side_to_move.opposite();
+        let mut predecessors: Vec<usize> = self
+            .material
+            .images(squares)
+            .iter()
+            .flat_map(|image| self.material.un_moves(image, side_to_move))
+            .map(|predecessor| self.material.index(&predecessor, mover))
+            .collect();
+        predecessors.sort_unstable();
+        predecessors.dedup();
+        predecessors
+    }
+
+    fn schedule(&mut self, index: usize, plies: usize) {
+        if plies >= self.buckets.len() {
+            self.buckets.resize(plies + 1, Vec::new());
+        }
+        self.buckets[plies].push(index as u32);
+    }
+}
+
+/// Captures and promotions change the material
+fn is_conversion(board: &Board, mov: &Move) -> bool {
+    mov.flag().is_promotion()
+        || mov.flag().is_en_passant()
+        || board.get_sq_piece(mov.dest()).is_some()
+}
@@ -0,0 +1,440 @@
+use crate::game::{
+    board::move_gen::{
+        attacks::{ATTACK_PATTERN_KING, ATTACK_PATTERN_KNIGHT, ATTACK_PATTERN_PAWN},
+        attacks_bishop, attacks_rook,
+    },
+    Board, Color, Piece, PieceType, Square,
+};
+
+/// Maximum number of pieces of a generated table
+pub const MAX_PIECES: usize = 5;
+/// Marks squares which are not part of the king region
+const UNMAPPED: usize = usize::MAX;
+
+/// Pieces of one color in the order they are listed in a material name
+const PIECE_ORDER: [(PieceType, char); 6] = [
+    (PieceType::King, 'K'),
+    (PieceType::Queen, 'Q'),
+    (PieceType::Rook, 'R'),
+    (PieceType::Bishop, 'B'),
+    (PieceType::Knight, 'N'),
+    (PieceType::Pawn, 'P'),
+];
+
+/// Squares of one position in the order of the material pieces
+pub(super) type Squares = [u8; MAX_PIECES];
+
+/// #### DTM Material
+/// Material of a table derived from its name, e.g. KRvKN, the white pieces are listed first.
+/// The tables are stored with the stronger side as white, the other color is probed by flipping the board.
+///
+/// A position is indexed by the side to move and the squares of all pieces, the white king is normalized
+/// by symmetry to the a1-d1-d4 triangle (a-d files for tables with pawns), so every position is stored once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DtmMaterial {
+    name: String,
+    pieces: Vec<Piece>,
+    has_pawns: bool,
+    /// Ranges of pieces with the same type and color, their squares are stored in ascending order
+    groups: Vec<std::ops::Range<usize>>,
+    /// Squares the white king is normalized to
+    king_squares: Vec<u8>,
+    /// Index of the square in the king region
+    king_region: [usize; 64],
+}
+
+impl DtmMaterial {
+    pub fn from_name(name: &str) -> Option<DtmMaterial> {
+        let (white, black) = name.split_once('v')?;
+        let mut pieces = Vec::new();
+        for (color, side) in [(Color::White, white), (Color::Black, black)] {
+            if !side.starts_with('K') || side.matches('K').count() != 1 {
+                return None;
+            }
+            let mut last_order = 0;
+            for c in side.chars() {
+                let order = PIECE_ORDER.iter().position(|&(_, p)| p == c)?;
+                //only the canonical order K Q R B N P is accepted, so every material has one name
+                if order < last_order {
+                    return None;
+                }
+                last_order = order;
+                pieces.push(Piece::new(PIECE_ORDER[order].0, color));
+            }
+        }
+        if pieces.len() > MAX_PIECES {
+            return None;
+        }
+
+        let mut groups: Vec<std::ops::Range<usize>> = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            match groups.last_mut() {
+                Some(group) if pieces[group.start] == *piece => group.end = i + 1,
+                _ => groups.push(i..i + 1),
+            }
+        }
+
+        let has_pawns = pieces.iter().any(|p| p.ptype() == PieceType::Pawn);
+        let king_squares: Vec<u8> = (0..64u8)
+            .filter(|sq| {
+                let (file, rank) = (sq % 8, sq / 8);
+                if has_pawns {
+                    file < 4
+                } else {
+                    file < 4 && rank <= file
+                }
+            })
+            .collect();
+        let mut king_region = [UNMAPPED; 64];
+        for (i, &sq) in king_squares.iter().enumerate() {
+            king_region[sq as usize] = i;
+        }
+
+        Some(DtmMaterial {
+            name: name.to_string(),
+            pieces,
+            has_pawns,
+            groups,
+            king_squares,
+            king_region,
+        })
+    }
+
+    /// Name of the material on the board seen from white and from black, e.g. (KRvKN, KNvKR)
+    pub fn names(board: &Board) -> (String, String) {
+        let bb = board.get_bb_pieces();
+        let pieces = |color: Color| {
+            PIECE_ORDER
+                .iter()
+                .map(|&(piece_type, c)| {
+                    c.to_string()
+                        .repeat(bb[color][piece_type].count_ones() as usize)
+                })
+                .collect::<String>()
+        };
+        let (white, black) = (pieces(Color::White), pieces(Color::Black));
+        (format!("{white}v{black}"), format!("{black}v{white}"))
+    }
+
+    /// Name of the material with the stronger side as white, the sides are compared by the piece values
+    pub fn canonical_name(name: &str) -> Option<String> {
+        let (white, black) = name.split_once('v')?;
+        let value = |side: &str| {
+            side.chars()
+                .map(|c| match c {
+                    'Q' => 9,
+                    'R' => 5,
+                    'B' | 'N' => 3,
+                    'P' => 1,
+                    _ => 0,
+                })
+                .sum::<u32>()
+        };
+        if (value(white), white) >= (value(black), black) {
+            Some(name.to_string())
+        } else {
+            Some(format!("{black}v{white}"))
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Number of indices of the table, including the ones of illegal positions
+    pub fn size(&self) -> usize {
+        2 * self.king_squares.len() * 64usize.pow(self.pieces.len() as u32 - 1)
+    }
+
+    /// Canonical names of all materials which can be reached by a capture or a promotion
+    pub fn sub_materials(&self) -> Vec<String> {
+        let side = |pieces: &[Piece], color: Color| {
+            pieces
+                .iter()
+                .filter(|p| p.color() == color)
+                .map(|p| PIECE_ORDER.iter().find(|(t, _)| *t == p.ptype()).unwrap().1)
+                .collect::<Vec<char>>()
+        };
+        let name = |pieces: &[Piece]| {
+            let mut sides = [side(pieces, Color::White), side(pieces, Color::Black)];
+            for side in sides.iter_mut() {
+                side.sort_by_key(|c| PIECE_ORDER.iter().position(|(_, p)| p == c));
+            }
+            let [white, black] = sides.map(|s| s.into_iter().collect::<String>());
+            DtmMaterial::canonical_name(&format!("{white}v{black}")).unwrap()
+        };
+
+        let mut materials = Vec::new();
+        for (i, piece) in self.pieces.iter().enumerate() {
+            if piece.ptype() == PieceType::King {
+                continue;
+            }
+            //captures of the piece
+            let mut captured = self.pieces.clone();
+            captured.remove(i);
+            materials.push(name(&captured));
+
+            if piece.ptype() != PieceType::Pawn {
+                continue;
+            }
+            //promotions of the pawn, optionally with a capture
+            for promotion in [
+                PieceType::Queen,
+                PieceType::Rook,
+                PieceType::Bishop,
+                PieceType::Knight,
+            ] {
+                let mut promoted = self.pieces.clone();
+                promoted[i] = Piece::new(promotion, piece.color());
+                materials.push(name(&promoted));
+                for (j, other) in self.pieces.iter().enumerate() {
+                    if other.color() != piece.color() && other.ptype() != PieceType::King {
+                        let mut captured = promoted.clone();
+                        captured.remove(j);
+                        materials.push(name(&captured));
+                    }
+                }
+            }
+        }
+        materials.sort();
+        materials.dedup();
+        materials
+    }
+
+    /// Index of the position, the squares are normalized by symmetry
+    pub(super) fn index(&self, squares: &Squares, side_to_move: Color) -> usize {
+        (0..self.symmetry_count())
+            .filter_map(|symmetry| {
+                let mut transformed = [0; MAX_PIECES];
+                for i in 0..self.pieces.len() {
+                    transformed[i] = transform(squares[i], symmetry);
+                }
+                self.raw_index(&mut transformed, side_to_move)
+            })
+            .min()
+            .expect("Every position can be normalized by symmetry")
+    }
+
+    /// Index of the squares without normalization, None if the white king is outside of the king region
+    fn raw_index(&self, squares: &mut Squares, side_to_move: Color) -> Option<usize> {
+        let king = self.king_region[squares[0] as usize];
+        if king == UNMAPPED {
+            return None;
+        }
+        for group in self.groups.iter() {
+            squares[group.clone()].sort_unstable();
+        }
+
+        let mut index = side_to_move as usize * self.king_squares.len() + king;
+        for &sq in squares[1..self.pieces.len()].iter() {
+            index = index * 64 + sq as usize;
+        }
+        Some(index)
+    }
+
+    /// Squares and side to move of the index, None if the index doesn't belong to a legal normalized position
+    pub(super) fn decode(&self, index: usize) -> Option<(Squares, Color)> {
+        let original = index;
+        let mut index = index;
+        let mut squares = [0; MAX_PIECES];
+        for i in (1..self.pieces.len()).rev() {
+            squares[i] = (index % 64) as u8;
+            index /= 64;
+        }
+        squares[0] = self.king_squares[index % self.king_squares.len()];
+        let side_to_move = if index / self.king_squares.len() == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let count = self.pieces.len();
+        let occupied = occupancy(&squares[..count]);
+        if occupied.count_ones() as usize != count {
+            return None;
+        }
+        let pawn_on_back_rank = (0..count).any(|i| {
+            self.pieces[i].ptype() == PieceType::Pawn && (squares[i] < 8 || squares[i] >= 56)
+        });
+        if pawn_on_back_rank {
+            return None;
+        }
+        if self
+            .groups
+            .iter()
+            .any(|group| squares[group.clone()].windows(2).any(|w| w[0] > w[1]))
+        {
+            return None;
+        }
+
+        //the side not to move can't be in check
+        let king = self.king_square(&squares, side_to_move.opposite());
+        if self.attacked(&squares, king, side_to_move)
+            || self.index(&squares, side_to_move) != original
+        {
+            return None;
+        }
+        Some((squares, side_to_move))
+    }
+
+    /// Positions (not normalized) from which the position is reached by a move which doesn't capture or promote
+    pub(super) fn un_moves(&self, squares: &Squares, side_to_move: Color) -> Vec<Squares> {
+        let count = self.pieces.len();
+        let mover = side_to_move.opposite();
+        let occupied = occupancy(&squares[..count]);
+        let king = self.king_square(squares, side_to_move);
+
+        let mut predecessors = Vec::new();
+        for i in 0..count {
+            let piece = self.pieces[i];
+            if piece.color() != mover {
+                continue;
+            }
+            let from = squares[i];
+            let targets = match piece.ptype() {
+                PieceType::Pawn => un_moves_pawn(from, occupied, mover),
+                other => attacks(other, mover, from, occupied) & !occupied,
+            };
+
+            let mut targets = targets;
+            while targets != 0 {
+                let target = targets.trailing_zeros() as u8;
+                targets &= targets - 1;
+
+                let mut predecessor = *squares;
+                predecessor[i] = target;
+                //the side to move of the position must not be in check before the move of the other side
+                if !self.attacked(&predecessor, king, mover) {
+                    predecessors.push(predecessor);
+                }
+            }
+        }
+        predecessors
+    }
+
+    /// All symmetric images of the position
+    pub(super) fn images(&self, squares: &Squares) -> Vec<Squares> {
+        let mut images: Vec<Squares> = (0..self.symmetry_count())
+            .map(|symmetry| {
+                let mut transformed = [0; MAX_PIECES];
+                for i in 0..self.pieces.len() {
+                    transformed[i] = transform(squares[i], symmetry);
+                }
+                transformed
+            })
+            .collect();
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+
+    /// Squares of the pieces on the board, None if the material of the board doesn't match
+    pub(super) fn squares(&self, board: &Board) -> Option<Squares> {
+        let mut squares = [0; MAX_PIECES];
+        for group in self.groups.iter() {
+            let positions: Vec<Square> = board
+                .get_piece_positions(self.pieces[group.start])
+                .collect();
+            if positions.len() != group.len() {
+                return None;
+            }
+            for (i, sq) in group.clone().zip(positions) {
+                squares[i] = sq.square_value();
+            }
+        }
+        Some(squares)
+    }
+
+    /// Creates the board of the position
+    pub(super) fn board(&self, squares: &Squares, side_to_move: Color) -> Board {
+        Board::from_pieces(
+            self.pieces
+                .iter()
+                .zip(squares.iter())
+                .map(|(&piece, &sq)| (piece, Square::new(sq))),
+            side_to_move,
+        )
+    }
+
+    fn symmetry_count(&self) -> u8 {
+        if self.has_pawns {
+            2
+        } else {
+            8
+        }
+    }
+
+    fn king_square(&self, squares: &Squares, color: Color) -> u8 {
+        let king = self
+            .pieces
+            .iter()
+            .position(|p| p.ptype() == PieceType::King && p.color() == color)
+            .expect("Every material has two kings");
+        squares[king]
+    }
+
+    /// Checks if the square is attacked by a piece of the color
+    fn attacked(&self, squares: &Squares, sq: u8, color: Color) -> bool {
+        let occupied = occupancy(&squares[..self.pieces.len()]);
+        self.pieces
+            .iter()
+            .zip(squares.iter())
+            .filter(|(piece, _)| piece.color() == color)
+            .any(|(piece, &from)| attacks(piece.ptype(), color, from, occupied) & 1 << sq != 0)
+    }
+}
+
+/// Applies one of the 8 board symmetries to the square, the first two don't change the ranks
+fn transform(mut sq: u8, symmetry: u8) -> u8 {
+    if symmetry & 1 != 0 {
+        sq ^= 7;
+    }
+    if symmetry & 2 != 0 {
+        sq ^= 56;
+    }
+    if symmetry & 4 != 0 {
+        sq = (sq % 8) * 8 + sq / 8;
+    }
+    sq
+}
+
+fn occupancy(squares: &[u8]) -> u64 {
+    squares.iter().fold(0, |bb, &sq| bb | 1 << sq)
+}
+
+/// Attacked squares of the piece including the occupied ones
+fn attacks(piece_type: PieceType, color: Color, from: u8, occupied: u64) -> u64 {
+    let square = Square::new(from);
+    match piece_type {
+        PieceType::Pawn => ATTACK_PATTERN_PAWN[color][from as usize],
+        PieceType::Knight => ATTACK_PATTERN_KNIGHT[from as usize],
+        PieceType::King => ATTACK_PATTERN_KING[from as usize],
+        PieceType::Bishop => attacks_bishop(square, occupied, 0),
+        PieceType::Rook => attacks_rook(square, occupied, 0),
+        PieceType::Queen => attacks_bishop(square, occupied, 0) | attacks_rook(square, occupied, 0),
+    }
+}
+
+/// Squares the pawn was pushed from, pawns can't come from the back rank
+fn un_moves_pawn(from: u8, occupied: u64, color: Color) -> u64 {
+    let (back, start_rank, double_rank) = match color {
+        Color::White => (-8i8, 1, 3),
+        Color::Black => (8, 6, 4),
+    };
+    let single = (from as i8 + back) as u8;
+    if !(8..56).contains(&single) || occupied & 1 << single != 0 {
+        return 0;
+    }
+    let mut targets = 1 << single;
+    if from / 8 == double_rank {
+        let double = (single as i8 + back) as u8;
+        if double / 8 == start_rank && occupied & 1 << double == 0 {
+            targets |= 1 << double;
+        }
+    }
+    targets
+}
@@ -0,0 +1,399 @@
+mod generator;
+mod index;
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    bot::file_utility::read_exact_at,
+    game::{Board, GameResult, MoveGeneration},
+};
+
+pub use generator::generate;
+pub use index::{DtmMaterial, MAX_PIECES};
+
+/// Magic bytes at the start of every table file
+const MAGIC: [u8; 4] = *b"CDTM";
+const VERSION: u8 = 1;
+/// File extension of the table files
+pub const EXTENSION: &str = "dtm";
+
+/// Value of a draw in the table file
+const DRAW: u8 = 0;
+/// Value of indices which don't belong to a legal normalized position
+const INVALID: u8 = u8::MAX;
+/// Longest distance to mate in plies which can be stored in one byte
+pub const MAX_PLIES: u8 = 252;
+
+#[derive(Debug, Error)]
+pub enum DtmError {
+    #[error("Failed to read or write the table: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No tables found in {0}")]
+    NoTables(String),
+    #[error("Missing table for {0}")]
+    MissingTable(String),
+    #[error("Invalid material {0}")]
+    InvalidMaterial(String),
+    #[error("Not a DTM table, invalid header in {0}")]
+    InvalidHeader(String),
+    #[error("Distance to mate of {0} exceeds {MAX_PLIES} plies")]
+    DistanceOverflow(String),
+    #[error("Tables don't contain positions with castling rights")]
+    CastlingRights,
+}
+
+/// Distance to mate in plies from the perspective of the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dtm {
+    /// The side to move mates in the given plies
+    Win(u8),
+    /// The side to move is mated in the given plies (0 if mated)
+    Loss(u8),
+    Draw,
+}
+
+impl Dtm {
+    /// Decodes the value of the table file, the parity of the plies tells if the side to move wins
+    fn from_value(value: u8) -> Option<Dtm> {
+        match value {
+            DRAW => Some(Dtm::Draw),
+            INVALID => None,
+            value if value % 2 == 0 => Some(Dtm::Win(value - 1)),
+            value => Some(Dtm::Loss(value - 1)),
+        }
+    }
+
+    fn value(&self) -> u8 {
+        match self {
+            Dtm::Win(plies) | Dtm::Loss(plies) => plies + 1,
+            Dtm::Draw => DRAW,
+        }
+    }
+
+    /// The result from the perspective of the side which made the move to this position
+    pub fn parent(&self) -> Dtm {
+        match *self {
+            Dtm::Win(plies) => Dtm::Loss(plies + 1),
+            Dtm::Loss(plies) => Dtm::Win(plies + 1),
+            Dtm::Draw => Dtm::Draw,
+        }
+    }
+
+    /// Higher is better for the side to move, faster wins and slower losses are preferred
+    pub fn rank(&self) -> i32 {
+        match *self {
+            Dtm::Win(plies) => 1000 - plies as i32,
+            Dtm::Loss(plies) => -1000 + plies as i32,
+            Dtm::Draw => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Dtm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dtm::Win(plies) => write!(f, "win in {} plies", plies),
+            Dtm::Loss(plies) => write!(f, "loss in {} plies", plies),
+            Dtm::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+/// #### DTM Table
+/// Distance to mate of every position of one material, one byte per index.
+///
+/// File format (little endian):
+/// - magic bytes `CDTM` and the format version (u8)
+/// - length of the material name (u8) and the name
+/// - number of indices (u64) followed by one value per index
+///
+/// The generated tables are kept in memory, tables opened from a file are read on every probe.
+pub struct DtmTable {
+    material: DtmMaterial,
+    data: TableData,
+}
+
+enum TableData {
+    Memory(Vec<u8>),
+    File { file: File, offset: u64 },
+}
+
+impl DtmTable {
+    fn from_values(material: DtmMaterial, values: Vec<u8>) -> DtmTable {
+        DtmTable {
+            material,
+            data: TableData::Memory(values),
+        }
+    }
+
+    /// Opens the table file and checks the header, the values are not read
+    pub fn open(path: &Path) -> Result<DtmTable, DtmError> {
+        let invalid = || DtmError::InvalidHeader(path.display().to_string());
+        let mut file = File::open(path)?;
+
+        let mut header = [0u8; 6];
+        file.read_exact(&mut header)?;
+        if header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid());
+        }
+        let mut name = vec![0u8; header[5] as usize];
+        file.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| invalid())?;
+        let material = DtmMaterial::from_name(&name).ok_or_else(invalid)?;
+
+        let mut size = [0u8; 8];
+        file.read_exact(&mut size)?;
+        let offset = 6 + name.len() as u64 + 8;
+        if u64::from_le_bytes(size) != material.size() as u64
+            || file.metadata()?.len() != offset + material.size() as u64
+        {
+            return Err(invalid());
+        }
+
+        Ok(DtmTable {
+            material,
+            data: TableData::File { file, offset },
+        })
+    }
+
+    /// Writes the table to the file, only generated tables can be written
+    pub fn write(&self, path: &Path) -> Result<(), DtmError> {
+        let TableData::Memory(values) = &self.data else {
+            return Err(DtmError::InvalidHeader(path.display().to_string()));
+        };
+        let mut file = std::io::BufWriter::new(File::create(path)?);
+        file.write_all(&MAGIC)?;
+        file.write_all(&[VERSION, self.material.name().len() as u8])?;
+        file.write_all(self.material.name().as_bytes())?;
+        file.write_all(&(values.len() as u64).to_le_bytes())?;
+        file.write_all(values)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn material(&self) -> &DtmMaterial {
+        &self.material
+    }
+
+    /// Number of indices of the table
+    pub fn len(&self) -> usize {
+        self.material.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Distance to mate of the index, None if the index doesn't belong to a legal position
+    pub fn get(&self, index: usize) -> Result<Option<Dtm>, DtmError> {
+        let value = match &self.data {
+            TableData::Memory(values) => values[index],
+            TableData::File { file, offset } => {
+                let mut value = [0u8];
+                read_exact_at(file, &mut value, offset + index as u64)?;
+                value[0]
+            }
+        };
+        Ok(Dtm::from_value(value))
+    }
+
+    /// Board of the index, None if the index doesn't belong to a legal position
+    pub fn position(&self, index: usize) -> Option<Board> {
+        let (squares, side_to_move) = self.material.decode(index)?;
+        Some(self.material.board(&squares, side_to_move))
+    }
+
+    /// Looks up the position, the board must have the material of the table
+    fn probe(&self, board: &Board, flipped: bool) -> Result<Option<Dtm>, DtmError> {
+        let lookup = |board: &Board| {
+            let Some(squares) = self.material.squares(board) else {
+                return Err(DtmError::MissingTable(self.material.name().to_string()));
+            };
+            self.get(self.material.index(&squares, board.side_to_move()))
+        };
+        if flipped {
+            lookup(&board.flip_colors())
+        } else {
+            lookup(board)
+        }
+    }
+}
+
+/// #### DTM Tablebases
+/// Prober for the distance to mate tables generated with `gen_tb`.
+///
+/// The tables ignore the fifty move rule and en passant rights after double pawn pushes during generation,
+/// en passant captures of the probed position are searched before looking up the table.
+/// As the result is exact, the searcher returns mate scores for the probed positions.
+#[derive(Default)]
+pub struct DtmTablebases {
+    tables: HashMap<String, DtmTable>,
+    max_pieces: usize,
+}
+
+impl DtmTablebases {
+    /// Searches the directories for table files, multiple directories are separated like in the PATH variable
+    pub fn open(paths: &str) -> Result<DtmTablebases, DtmError> {
+        let mut tablebases = DtmTablebases::default();
+        for directory in std::env::split_paths(paths) {
+            for entry in std::fs::read_dir(directory)? {
+                let path: PathBuf = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION) {
+                    continue;
+                }
+                tablebases.insert(DtmTable::open(&path)?);
+            }
+        }
+
+        if tablebases.tables.is_empty() {
+            return Err(DtmError::NoTables(paths.to_string()));
+        }
+        Ok(tablebases)
+    }
+
+    pub fn insert(&mut self, table: DtmTable) {
+        self.max_pieces = self.max_pieces.max(table.material.piece_count());
+        self.tables.insert(table.material.name().to_string(), table);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DtmTable> {
+        self.tables.get(name)
+    }
+
+    pub fn table_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Returns true if the position may be stored in the tables
+    pub fn can_probe(&self, board: &Board) -> bool {
+        board.get_bb_all_occupied().count_ones() as usize <= self.max_pieces
+            && board.cur_state().castling_rights.as_u8() == 0
+    }
+
+    /// Probes the distance to mate of the position, the board is restored after probing en passant captures
+    pub fn probe(&self, board: &mut Board) -> Result<Dtm, DtmError> {
+        if board.cur_state().castling_rights.as_u8() != 0 {
+            return Err(DtmError::CastlingRights);
+        }
+
+        let (white, black) = DtmMaterial::names(board);
+        let (table, flipped) = match (self.tables.get(&white), self.tables.get(&black)) {
+            (Some(table), _) => (table, false),
+            (None, Some(table)) => (table, true),
+            (None, None) => return Err(DtmError::MissingTable(white)),
+        };
+        let mut dtm = table
+            .probe(board, flipped)?
+            .ok_or_else(|| DtmError::MissingTable(white.clone()))?;
+
+        //the table values are the result without the en passant capture
+        if board.cur_state().en_passant.is_some() {
+            let moves = MoveGeneration::generate_legal_moves(board);
+            for mov in moves.iter().filter(|mov| mov.flag().is_en_passant()) {
+                board
+                    .make_move(mov, true, false)
+                    .expect("Move generation should generate only legal moves");
+                let result = self.probe(board);
+                board
+                    .undo_move(mov, true)
+                    .expect("Probing must only undo moves it made");
+                let capture = result?.parent();
+                if capture.rank() > dtm.rank() {
+                    dtm = capture;
+                }
+            }
+        }
+        Ok(dtm)
+    }
+
+    /// Result of the game with perfect play from both sides, None if the position is not in the tables
+    pub fn adjudicate(&self, board: &mut Board) -> Option<GameResult> {
+        let side_to_move = board.side_to_move();
+        match self.probe(board).ok()? {
+            Dtm::Win(_) => Some(GameResult::Mate(side_to_move.opposite())),
+            Dtm::Loss(_) => Some(GameResult::Mate(side_to_move)),
+            Dtm::Draw => Some(GameResult::Draw),
+        }
+    }
+
+    /// Generates the table of the material and all tables it depends on which are not loaded yet,
+    /// the callback is called for every generated table
+    pub fn generate_all(
+        &mut self,
+        name: &str,
+        on_generated: &mut impl FnMut(&DtmTable),
+    ) -> Result<(), DtmError> {
+        let name = DtmMaterial::canonical_name(name)
+            .filter(|name| DtmMaterial::from_name(name).is_some())
+            .ok_or_else(|| DtmError::InvalidMaterial(name.to_string()))?;
+        if self.contains(&name) {
+            return Ok(());
+        }
+
+        let material = DtmMaterial::from_name(&name)
+            .ok_or_else(|| DtmError::InvalidMaterial(name.to_string()))?;
+        for sub_material in material.sub_materials() {
+            self.generate_all(&sub_material, on_generated)?;
+        }
+
+        let table = generate(&material, self)?;
+        on_generated(&table);
+        self.insert(table);
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_generate_tables() {
+        use super::{Dtm, DtmTablebases};
+        use crate::game::Board;
+
+        let mut tablebases = DtmTablebases::default();
+        tablebases.generate_all("KQvK", &mut |_| {}).unwrap();
+        tablebases.generate_all("KvKR", &mut |_| {}).unwrap();
+        assert_eq!(tablebases.table_count(), 3);
+
+        //the longest mates are 10 moves for the queen and 16 moves for the rook
+        for (name, longest) in [("KQvK", 19), ("KRvK", 31)] {
+            let table = tablebases.get(name).unwrap();
+            let max = (0..table.len())
+                .filter_map(|index| table.get(index).unwrap())
+                .filter_map(|dtm| match dtm {
+                    Dtm::Win(plies) => Some(plies),
+                    _ => None,
+                })
+                .max();
+            assert_eq!(max, Some(longest));
+        }
+
+        let probe = |fen: &str| {
+            tablebases
+                .probe(&mut Board::from_fen(fen).unwrap())
+                .unwrap()
+        };
+        assert_eq!(probe("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"), Dtm::Win(1));
+        assert_eq!(probe("K7/8/1k6/8/8/8/8/6q1 b - - 0 1"), Dtm::Win(1));
+        assert_eq!(probe("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Dtm::Draw);
+        assert_eq!(probe("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), Dtm::Loss(0));
+        assert_eq!(
+            probe("8/8/8/3k4/8/8/8/K6r w - - 0 1"),
+            probe("8/8/8/3K4/8/8/8/k6R b - - 0 1")
+        );
+    }
+}
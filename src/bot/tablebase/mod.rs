@@ -0,0 +1,2 @@
+pub mod dtm;
+pub mod syzygy;
@@ -0,0 +1,176 @@
+use lazy_static::lazy_static;
+
+use crate::game::board::move_gen::attacks::ATTACK_PATTERN_KING;
+
+/// Maximum number of pieces in a syzygy table
+pub(super) const TB_PIECES: usize = 7;
+/// Marks squares which are not part of a mapping
+const UNMAPPED: usize = usize::MAX;
+
+/// Number of ways to place the leading group of 3 unique pieces (including the kings)
+pub(super) const UNIQUE_PIECES_SIZE: u64 = 31332;
+/// Number of legal ways to place both kings with the first king in the a1-d1-d4 triangle
+pub(super) const KINGS_SIZE: u64 = 462;
+
+lazy_static! {
+    pub(super) static ref ENCODING: Encoding = Encoding::new();
+}
+
+/*
+    Index tables to map a position to the index of the syzygy tables.
+    The position is normalized by symmetry, the leading pieces are placed in a triangle of the board
+    and the remaining groups of equal pieces are encoded with binomial coefficients.
+*/
+pub(super) struct Encoding {
+    /// Encodes the pawn squares a2-h7 to 0..47, the leading pawn has the highest value
+    pub map_pawns: [usize; 64],
+    /// Encodes the squares below the a1-h8 diagonal to 0..27
+    pub map_b1h1h7: [usize; 64],
+    /// Encodes the squares of the a1-d1-d4 triangle to 0..9, the diagonal squares last
+    pub map_a1d1d4: [usize; 64],
+    /// Encodes the 462 legal positions of both kings with the first king in the a1-d1-d4 triangle
+    pub map_kk: [[u64; 64]; 10],
+    /// Binomial coefficients indexed by k and n
+    pub binomial: [[u64; 64]; TB_PIECES],
+    /// Start index of the leading pawns indexed by the number of leading pawns and the square of the first one
+    pub lead_pawn_idx: [[u64; 64]; 6],
+    /// Number of leading pawn placements indexed by the number of leading pawns and the file (a to d)
+    pub lead_pawns_size: [[u64; 4]; 6],
+}
+
+/// Distance of the square to the a1-h8 diagonal, positive above the diagonal
+#[inline(always)]
+pub(super) fn off_a1h8(sq: u8) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+impl Encoding {
+    fn new() -> Encoding {
+        let mut encoding = Encoding {
+            map_pawns: [0; 64],
+            map_b1h1h7: [UNMAPPED; 64],
+            map_a1d1d4: [UNMAPPED; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; TB_PIECES],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for sq in 0..64 {
+            if off_a1h8(sq) < 0 {
+                encoding.map_b1h1h7[sq as usize] = code;
+                code += 1;
+            }
+        }
+
+        //the squares below the diagonal first, followed by the diagonal squares
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in 0..=27u8 {
+            if sq % 8 > 3 {
+                continue;
+            }
+            if off_a1h8(sq) < 0 {
+                encoding.map_a1d1d4[sq as usize] = code;
+                code += 1;
+            } else if off_a1h8(sq) == 0 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            encoding.map_a1d1d4[sq as usize] = code;
+            code += 1;
+        }
+
+        //the positions with both kings on the diagonal are encoded last
+        let mut both_on_diagonal = Vec::new();
+        let mut code = 0;
+        for idx in 0..10 {
+            for s1 in 0..=27u8 {
+                if encoding.map_a1d1d4[s1 as usize] != idx {
+                    continue;
+                }
+                for s2 in 0..64u8 {
+                    if (ATTACK_PATTERN_KING[s1 as usize] | 1 << s1) & 1 << s2 != 0
+                        || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0)
+                    {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        encoding.map_kk[idx][s2 as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            encoding.map_kk[idx][s2 as usize] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..TB_PIECES.min(n + 1) {
+                encoding.binomial[k][n] = if k > 0 {
+                    encoding.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n {
+                    encoding.binomial[k][n - 1]
+                } else {
+                    0
+                };
+            }
+        }
+
+        //the pawn squares are ordered by file (edge first) and rank,
+        //the leading pawn is the one nearest to the edge with the lowest rank
+        let mut available_squares = 47;
+        for lead_pawns in 1..=5 {
+            for file in 0..4u8 {
+                let mut idx = 0;
+                for rank in 1..=6u8 {
+                    let sq = (rank * 8 + file) as usize;
+                    if lead_pawns == 1 {
+                        encoding.map_pawns[sq] = available_squares;
+                        encoding.map_pawns[sq ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+                    encoding.lead_pawn_idx[lead_pawns][sq] = idx;
+                    idx += encoding.binomial[lead_pawns - 1][encoding.map_pawns[sq]];
+                }
+                encoding.lead_pawns_size[lead_pawns][file as usize] = idx;
+            }
+        }
+
+        encoding
+    }
+}
+
+mod test {
+    #[test]
+    fn test_encoding_sizes() {
+        use super::{off_a1h8, ENCODING, KINGS_SIZE};
+
+        let max_kk = ENCODING
+            .map_kk
+            .iter()
+            .flat_map(|row| row.iter())
+            .max()
+            .unwrap();
+        assert_eq!(*max_kk + 1, KINGS_SIZE);
+
+        //every pawn square has a unique code
+        let mut pawn_codes: Vec<_> = (8..56).map(|sq| ENCODING.map_pawns[sq]).collect();
+        pawn_codes.sort();
+        assert_eq!(pawn_codes, (0..48).collect::<Vec<_>>());
+
+        assert_eq!(ENCODING.binomial[2][6], 15);
+        assert_eq!(ENCODING.binomial[5][63], 7028847);
+        assert_eq!(ENCODING.lead_pawns_size[1], [6; 4]);
+        assert_eq!(off_a1h8(2), -2);
+    }
+}
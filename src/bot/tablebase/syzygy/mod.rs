@@ -0,0 +1,540 @@
+mod encoding;
+mod table;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use thiserror::Error;
+
+use crate::game::{Board, Move, MoveGeneration, PieceType};
+
+use table::{Material, Table, TableType};
+
+#[derive(Debug, Error)]
+pub enum SyzygyError {
+    #[error("Failed to read the tablebase: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No tablebase files found in {0}")]
+    NoTables(String),
+    #[error("Missing tablebase for {0}")]
+    MissingTable(String),
+    #[error("Not a syzygy tablebase, invalid magic bytes in {0}")]
+    InvalidMagic(String),
+    #[error("Corrupted tablebase {0}")]
+    Corrupted(String),
+    #[error("Tablebases don't contain positions with castling rights")]
+    CastlingRights,
+}
+
+/// Win/Draw/Loss result of a tablebase position from the perspective of the side to move.
+/// Cursed wins and blessed losses are wins and losses which are drawn by the fifty move rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            2.. => Wdl::Win,
+        }
+    }
+}
+
+impl std::ops::Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Self::Output {
+        Wdl::from_value(-(self as i32))
+    }
+}
+
+/// Root move ranked by the distance to zeroing (in plies) after the move
+#[derive(Debug, Clone, Copy)]
+pub struct RankedMove {
+    pub mov: Move,
+    /// Distance to zeroing from the root position, positive if the move wins
+    pub dtz: i32,
+    /// Higher is better, wins which can be converted within the fifty move rule are ranked equally
+    pub rank: i32,
+}
+
+/// Table of one material combination, the files are opened on the first probe
+struct TableEntry {
+    material: Material,
+    wdl_path: PathBuf,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+/// #### Syzygy Tablebases
+/// Native prober for the WDL (.rtbw) and DTZ (.rtbz) files of the syzygy tablebases.
+///
+/// The tables don't store positions with castling rights and rely on the prober to resolve captures
+/// (including en passant), so the probes search the captures of the position before looking up the tables.
+/// The WDL tables are used to cut off the search, the DTZ tables to rank the root moves.
+pub struct Tablebases {
+    tables: HashMap<String, TableEntry>,
+    max_pieces: usize,
+}
+
+impl Tablebases {
+    /// Searches the directories for tablebase files, multiple directories are separated like in the PATH variable
+    pub fn open(paths: &str) -> Result<Tablebases, SyzygyError> {
+        let directories: Vec<PathBuf> = std::env::split_paths(paths).collect();
+        let mut tables = HashMap::new();
+        let mut max_pieces = 0;
+
+        for directory in directories.iter() {
+            for entry in std::fs::read_dir(directory)? {
+                let path = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some(TableType::Wdl.extension())
+                {
+                    continue;
+                }
+                let Some(material) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(Material::from_name)
+                else {
+                    continue;
+                };
+
+                let dtz_path = directories
+                    .iter()
+                    .map(|dir| {
+                        dir.join(format!("{}.{}", material.name, TableType::Dtz.extension()))
+                    })
+                    .find(|path| path.is_file());
+
+                max_pieces = max_pieces.max(material.piece_count);
+                tables.insert(
+                    material.name.clone(),
+                    TableEntry {
+                        material,
+                        wdl_path: path,
+                        dtz_path,
+                        wdl: OnceLock::new(),
+                        dtz: OnceLock::new(),
+                    },
+                );
+            }
+        }
+
+        if tables.is_empty() {
+            return Err(SyzygyError::NoTables(paths.to_string()));
+        }
+
+        Ok(Tablebases { tables, max_pieces })
+    }
+
+    /// Number of WDL tables found
+    pub fn table_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Largest number of pieces of the found tables
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Returns true if the position may be stored in the tablebases
+    pub fn can_probe(&self, board: &Board) -> bool {
+        board.get_bb_all_occupied().count_ones() as usize <= self.max_pieces
+            && board.cur_state().castling_rights.as_u8() == 0
+    }
+
+    /// Probes the WDL tables, the board is restored after probing the captures.
+    pub fn probe_wdl(&self, board: &mut Board) -> Result<Wdl, SyzygyError> {
+        self.check_castling(board)?;
+        Ok(self.search(board, false)?.0)
+    }
+
+    /// Probes the DTZ tables and returns the distance to zeroing in plies from the perspective of the side to move:
+    /// - `n < -100` loss which is drawn by the fifty move rule
+    /// - `-100 <= n <= -1` loss in n plies (-1 if mated)
+    /// - `0` draw
+    /// - `1 <= n <= 100` win in n plies
+    /// - `100 < n` win which is drawn by the fifty move rule
+    ///
+    /// The result can be off by one ply, a win is certain if `dtz + ply_clock <= 99`.
+    pub fn probe_dtz(&self, board: &mut Board) -> Result<i32, SyzygyError> {
+        self.check_castling(board)?;
+        let (wdl, zeroing_best) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+        //the tables store a don't care value if the best move is zeroing
+        if zeroing_best {
+            return Ok(dtz_before_zeroing(wdl));
+        }
+
+        if let Some(dtz) = self.probe_dtz_table(board, wdl)? {
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Ok((dtz + 100 * cursed as i32) * (wdl as i32).signum());
+        }
+
+        //the table stores the other side to move, search one ply for the move with the best dtz
+        let mut best_dtz = i32::MAX;
+        let moves = MoveGeneration::generate_legal_moves(board);
+        for mov in moves.iter() {
+            let zeroing = is_zeroing(board, mov);
+            board
+                .make_move(mov, true, false)
+                .expect("Move generation should generate only legal moves");
+            let result = if zeroing {
+                self.search(board, false)
+                    .map(|(wdl, _)| -dtz_before_zeroing(wdl))
+            } else {
+                self.probe_dtz(board).map(|dtz| -dtz)
+            };
+            let mate = MoveGeneration::generate_legal_moves(board).is_checkmate();
+            board
+                .undo_move(mov, true)
+                .expect("Probing must only undo moves it made");
+
+            let mut dtz = result?;
+            if dtz == 1 && mate {
+                best_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < best_dtz && dtz.signum() == (wdl as i32).signum() {
+                best_dtz = dtz;
+            }
+        }
+
+        Ok(if best_dtz == i32::MAX { -1 } else { best_dtz })
+    }
+
+    /// Ranks the root moves by the distance to zeroing after the move and the ply clock of the root position.
+    /// Wins which can be converted before the fifty move rule draws the game are ranked equally,
+    /// slower wins and losses which are saved by the fifty move rule are ranked by the distance.
+    pub fn rank_root_moves(
+        &self,
+        board: &mut Board,
+        moves: &[Move],
+    ) -> Result<Vec<RankedMove>, SyzygyError> {
+        self.check_castling(board)?;
+        let ply_clock = board.cur_state().ply_clock as i32;
+
+        let mut ranked = Vec::with_capacity(moves.len());
+        for mov in moves {
+            board
+                .make_move(mov, true, false)
+                .expect("Root moves must be legal");
+            let result = if board.cur_state().ply_clock == 0 {
+                self.probe_wdl(board).map(|wdl| dtz_before_zeroing(-wdl))
+            } else {
+                self.probe_dtz(board).map(|dtz| -dtz + (-dtz).signum())
+            };
+            let mate = MoveGeneration::generate_legal_moves(board).is_checkmate();
+            board
+                .undo_move(mov, true)
+                .expect("Probing must only undo moves it made");
+
+            let mut dtz = result?;
+            if mate && dtz == 2 {
+                dtz = 1;
+            }
+
+            let rank = if dtz > 0 {
+                if dtz + ply_clock <= 99 {
+                    1000
+                } else {
+                    1000 - (dtz + ply_clock)
+                }
+            } else if dtz < 0 {
+                if -dtz * 2 + ply_clock < 100 {
+                    -1000
+                } else {
+                    -1000 + (-dtz + ply_clock)
+                }
+            } else {
+                0
+            };
+
+            ranked.push(RankedMove {
+                mov: *mov,
+                dtz,
+                rank,
+            });
+        }
+        Ok(ranked)
+    }
+
+    /// Ranks the root moves by the WDL result after the move, used if the DTZ tables are missing.
+    /// All wins are ranked equally, so the search has to make progress on its own.
+    pub fn rank_root_moves_wdl(
+        &self,
+        board: &mut Board,
+        moves: &[Move],
+    ) -> Result<Vec<RankedMove>, SyzygyError> {
+        self.check_castling(board)?;
+
+        let mut ranked = Vec::with_capacity(moves.len());
+        for mov in moves {
+            board
+                .make_move(mov, true, false)
+                .expect("Root moves must be legal");
+            let result = self.probe_wdl(board);
+            board
+                .undo_move(mov, true)
+                .expect("Probing must only undo moves it made");
+
+            let wdl = -result?;
+            ranked.push(RankedMove {
+                mov: *mov,
+                dtz: dtz_before_zeroing(wdl),
+                rank: match wdl {
+                    Wdl::Win => 1000,
+                    Wdl::CursedWin => 899,
+                    Wdl::Draw => 0,
+                    Wdl::BlessedLoss => -899,
+                    Wdl::Loss => -1000,
+                },
+            });
+        }
+        Ok(ranked)
+    }
+
+    fn check_castling(&self, board: &Board) -> Result<(), SyzygyError> {
+        if board.cur_state().castling_rights.as_u8() != 0 {
+            return Err(SyzygyError::CastlingRights);
+        }
+        Ok(())
+    }
+
+    /// Searches the captures (and pawn moves for DTZ probes) of the position and combines them with the stored value.
+    /// The tables store don't care values for positions where a capture is the best move,
+    /// so the best result of the captures and the stored value is the result of the position.
+    /// Returns the result and whether a zeroing move is the best move.
+    fn search(&self, board: &mut Board, zeroing_moves: bool) -> Result<(Wdl, bool), SyzygyError> {
+        let moves = MoveGeneration::generate_legal_moves(board);
+        let mut best = Wdl::Loss;
+        let mut move_count = 0;
+
+        for mov in moves.iter() {
+            let capture = is_capture(board, mov);
+            if !(capture || zeroing_moves && is_zeroing(board, mov)) {
+                continue;
+            }
+            move_count += 1;
+
+            board
+                .make_move(mov, true, false)
+                .expect("Move generation should generate only legal moves");
+            let result = self.search(board, false);
+            board
+                .undo_move(mov, true)
+                .expect("Probing must only undo moves it made");
+
+            let value = -result?.0;
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Ok((value, true));
+                }
+            }
+        }
+
+        //the stored value can be wrong if all moves are captures (e.g. positions with en passant)
+        let no_more_moves = move_count > 0 && move_count == moves.len();
+        let value = if no_more_moves {
+            best
+        } else {
+            self.probe_wdl_table(board)?
+        };
+
+        if best >= value {
+            return Ok((best, best > Wdl::Draw || no_more_moves));
+        }
+        Ok((value, false))
+    }
+
+    fn probe_wdl_table(&self, board: &Board) -> Result<Wdl, SyzygyError> {
+        if board.get_bb_all_occupied().count_ones() == 2 {
+            return Ok(Wdl::Draw);
+        }
+        let (entry, flipped) = self.entry(board)?;
+        let table = entry
+            .wdl
+            .get_or_init(|| Self::load(&entry.wdl_path, TableType::Wdl, &entry.material))
+            .as_ref()
+            .ok_or_else(|| SyzygyError::MissingTable(entry.material.name.clone()))?;
+
+        let (value, _) = table
+            .probe(board, &entry.material, flipped)?
+            .ok_or_else(|| SyzygyError::Corrupted(entry.material.name.clone()))?;
+        Ok(Wdl::from_value(value - 2))
+    }
+
+    /// Returns None if the table stores the other side to move
+    fn probe_dtz_table(&self, board: &Board, wdl: Wdl) -> Result<Option<i32>, SyzygyError> {
+        let (entry, flipped) = self.entry(board)?;
+        let table = entry
+            .dtz
+            .get_or_init(|| {
+                entry
+                    .dtz_path
+                    .as_ref()
+                    .and_then(|path| Self::load(path, TableType::Dtz, &entry.material))
+            })
+            .as_ref()
+            .ok_or_else(|| SyzygyError::MissingTable(entry.material.name.clone()))?;
+
+        Ok(table
+            .probe(board, &entry.material, flipped)?
+            .map(|(value, file)| table.map_dtz(file, value, wdl)))
+    }
+
+    /// Returns the table of the material on the board and whether the colors are flipped
+    fn entry(&self, board: &Board) -> Result<(&TableEntry, bool), SyzygyError> {
+        let (white, black) = Material::names(board);
+        if let Some(entry) = self.tables.get(&white) {
+            return Ok((entry, false));
+        }
+        self.tables
+            .get(&black)
+            .map(|entry| (entry, true))
+            .ok_or(SyzygyError::MissingTable(white))
+    }
+
+    fn load(path: &Path, table_type: TableType, material: &Material) -> Option<Table> {
+        match Table::open(path, table_type, material) {
+            Ok(table) => Some(table),
+            Err(err) => {
+                warn!("Failed to load tablebase {}: {err}", path.display());
+                None
+            }
+        }
+    }
+}
+
+/// Distance to zeroing of the move before a zeroing move which leads to the result
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+fn is_capture(board: &Board, mov: &Move) -> bool {
+    mov.flag().is_en_passant() || board.get_sq_piece(mov.dest()).is_some()
+}
+
+/// Captures and pawn moves reset the fifty move rule
+fn is_zeroing(board: &Board, mov: &Move) -> bool {
+    is_capture(board, mov) || board.get_sq_piece_variation(mov.source()) == Some(PieceType::Pawn)
+}
+
+mod test {
+    #[test]
+    fn test_single_value_tables() {
+        use super::{Tablebases, Wdl};
+        use crate::game::Board;
+        use std::io::Write;
+
+        //KQvK table where every position stores the same value: a win with white to move
+        //and a loss with black to move (header, order, pieces, sizes of both sides)
+        let dir = std::env::temp_dir().join(format!("syzygy_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut data = vec![0x71, 0xE8, 0x23, 0x5D, 0x01, 0x00, 0x66, 0x55, 0xEE];
+        data.extend([0x00, 0x80, 0x04, 0x80, 0x00]);
+        data.resize(80, 0);
+        std::fs::File::create(dir.join("KQvK.rtbw"))
+            .unwrap()
+            .write_all(&data)
+            .unwrap();
+
+        let tablebases = Tablebases::open(dir.to_str().unwrap()).unwrap();
+        assert_eq!(tablebases.max_pieces(), 3);
+
+        let mut board = Board::from_fen("8/8/3k4/8/8/8/8/K6Q w - - 0 1").unwrap();
+        assert_eq!(tablebases.probe_wdl(&mut board).unwrap(), Wdl::Win);
+        let mut board = Board::from_fen("8/8/3K4/8/8/8/8/k6q b - - 0 1").unwrap();
+        assert_eq!(tablebases.probe_wdl(&mut board).unwrap(), Wdl::Win);
+        let mut board = Board::from_fen("8/8/3k4/8/8/8/8/K6Q b - - 0 1").unwrap();
+        assert_eq!(tablebases.probe_wdl(&mut board).unwrap(), Wdl::Loss);
+
+        //the queen can be captured, the capture leads to the drawn KvK
+        let mut board = Board::from_fen("8/8/8/8/8/8/6Qk/4K3 b - - 0 1").unwrap();
+        assert_eq!(tablebases.probe_wdl(&mut board).unwrap(), Wdl::Draw);
+
+        //missing tables are reported
+        let mut board = Board::from_fen("8/8/3k4/8/8/8/8/K6R w - - 0 1").unwrap();
+        assert!(tablebases.probe_wdl(&mut board).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_generated_tables() {
+        use super::{Tablebases, Wdl};
+        use crate::bot::tablebase::dtm::{Dtm, DtmTablebases};
+        use crate::game::Board;
+
+        //KQvK and KRvK tables written by resources/syzygy/generate.py, the values are decoded
+        //from huffman coded blocks found with the sparse index, the DTZ values through the DTZ map
+        let tablebases = Tablebases::open("resources/syzygy").unwrap();
+        assert_eq!(tablebases.table_count(), 2);
+
+        let probe = |fen: &str| {
+            let mut board = Board::from_fen(fen).unwrap();
+            (
+                tablebases.probe_wdl(&mut board).unwrap(),
+                tablebases.probe_dtz(&mut board).unwrap(),
+            )
+        };
+        assert_eq!(probe("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"), (Wdl::Win, 1));
+        assert_eq!(probe("K7/8/1k6/8/8/8/8/6q1 b - - 0 1"), (Wdl::Win, 1));
+        assert_eq!(probe("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), (Wdl::Loss, -1));
+        assert_eq!(probe("k7/8/1K6/8/8/8/8/6R1 b - - 0 1"), (Wdl::Loss, -2));
+        //stalemate and the capture of the unprotected queen
+        assert_eq!(probe("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), (Wdl::Draw, 0));
+        assert_eq!(probe("8/8/8/8/8/8/6Qk/4K3 b - - 0 1"), (Wdl::Draw, 0));
+
+        //the winning side never zeroes, so the DTZ is the distance to mate of the DTM tables
+        let mut dtm_tablebases = DtmTablebases::default();
+        dtm_tablebases.generate_all("KQvK", &mut |_| {}).unwrap();
+        dtm_tablebases.generate_all("KRvK", &mut |_| {}).unwrap();
+        for name in ["KQvK", "KRvK"] {
+            let table = dtm_tablebases.get(name).unwrap();
+            let mut longest = 0;
+            for index in (0..table.len()).step_by(7) {
+                let (Some(dtm), Some(mut board)) =
+                    (table.get(index).unwrap(), table.position(index))
+                else {
+                    continue;
+                };
+                let expected = match dtm {
+                    Dtm::Win(plies) => (Wdl::Win, plies as i32),
+                    Dtm::Loss(0) => (Wdl::Loss, -1),
+                    Dtm::Loss(plies) => (Wdl::Loss, -(plies as i32)),
+                    Dtm::Draw => (Wdl::Draw, 0),
+                };
+                let wdl = tablebases.probe_wdl(&mut board).unwrap();
+                let dtz = tablebases.probe_dtz(&mut board).unwrap();
+                assert_eq!((wdl, dtz), expected, "{}", board.to_fen());
+                longest = longest.max(dtz);
+            }
+            assert!(longest > 15, "{name}");
+        }
+    }
+}
@@ -0,0 +1,799 @@
+use std::{fs::File, path::Path};
+
+use crate::{
+    bot::file_utility::read_exact_at,
+    game::{bit_manipulation::iter_set_bits, Board, Color, PieceType, Square},
+};
+
+use super::{
+    encoding::{off_a1h8, ENCODING, KINGS_SIZE, TB_PIECES, UNIQUE_PIECES_SIZE},
+    SyzygyError, Wdl,
+};
+
+/// The side to move is stored in the flags of DTZ tables
+const FLAG_STM: u8 = 1;
+/// The DTZ values are remapped by frequency
+const FLAG_MAPPED: u8 = 2;
+/// Winning DTZ values are stored in plies instead of moves
+const FLAG_WIN_PLIES: u8 = 4;
+/// Losing DTZ values are stored in plies instead of moves
+const FLAG_LOSS_PLIES: u8 = 8;
+/// The DTZ map stores 16 bit values
+const FLAG_WIDE: u8 = 16;
+/// Every position of the table stores the same value
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+/// Flags of the file header
+const HEADER_SPLIT: u8 = 1;
+const HEADER_HAS_PAWNS: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TableType {
+    Wdl,
+    Dtz,
+}
+
+impl TableType {
+    pub(super) fn extension(&self) -> &'static str {
+        match self {
+            TableType::Wdl => "rtbw",
+            TableType::Dtz => "rtbz",
+        }
+    }
+
+    fn magic(&self) -> [u8; 4] {
+        match self {
+            TableType::Wdl => [0x71, 0xE8, 0x23, 0x5D],
+            TableType::Dtz => [0xD7, 0x66, 0x0C, 0xA5],
+        }
+    }
+}
+
+/// #### Material
+/// Material of a table derived from its name, e.g. KRPvKR.
+/// The tables are stored with the stronger side as white, the other color is probed by flipping the board.
+#[derive(Debug, Clone)]
+pub(super) struct Material {
+    pub name: String,
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    /// At least one piece apart from the kings is the only one of its kind
+    pub has_unique_pieces: bool,
+    /// Pawns of the leading color and the other color
+    pub pawn_count: [usize; 2],
+    /// Both sides have the same pieces
+    pub symmetric: bool,
+}
+
+impl Material {
+    pub(super) fn from_name(name: &str) -> Option<Material> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0usize; 6]; 2];
+        for (color, pieces) in [white, black].iter().enumerate() {
+            if !pieces.starts_with('K') || pieces.matches('K').count() != 1 {
+                return None;
+            }
+            for c in pieces.chars() {
+                let piece_type = match c {
+                    'P' => PieceType::Pawn,
+                    'N' => PieceType::Knight,
+                    'B' => PieceType::Bishop,
+                    'R' => PieceType::Rook,
+                    'Q' => PieceType::Queen,
+                    'K' => PieceType::King,
+                    _ => return None,
+                };
+                counts[color][piece_type] += 1;
+            }
+        }
+
+        let piece_count = white.len() + black.len();
+        if piece_count > TB_PIECES {
+            return None;
+        }
+
+        let [white_pawns, black_pawns] = [counts[0][PieceType::Pawn], counts[1][PieceType::Pawn]];
+        //the leading color is the one with less pawns as it leads to a better compression
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        Some(Material {
+            name: name.to_string(),
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: counts
+                .iter()
+                .any(|color| color[..PieceType::King as usize].contains(&1)),
+            pawn_count,
+            symmetric: white == black,
+        })
+    }
+
+    /// Name of the material on the board seen from white and from black, e.g. (KRPvKR, KRvKRP)
+    pub(super) fn names(board: &Board) -> (String, String) {
+        let bb = board.get_bb_pieces();
+        let pieces = |color: Color| {
+            [
+                (PieceType::King, 'K'),
+                (PieceType::Queen, 'Q'),
+                (PieceType::Rook, 'R'),
+                (PieceType::Bishop, 'B'),
+                (PieceType::Knight, 'N'),
+                (PieceType::Pawn, 'P'),
+            ]
+            .iter()
+            .map(|&(piece_type, c)| {
+                c.to_string()
+                    .repeat(bb[color][piece_type].count_ones() as usize)
+            })
+            .collect::<String>()
+        };
+        let (white, black) = (pieces(Color::White), pieces(Color::Black));
+        (format!("{white}v{black}"), format!("{black}v{white}"))
+    }
+}
+
+/// Indexing and decompression information of one sub table, there is one per side to move
+/// and per file of the leading pawn (a to d) for tables with pawns
+#[derive(Default)]
+struct PairsData {
+    flags: u8,
+    /// Minimum length of the huffman symbols, the stored value of single value tables
+    min_sym_len: u8,
+    block_size: usize,
+    /// About every span values there is an entry in the sparse index
+    span: u64,
+    num_blocks: u32,
+    /// Lowest symbol of each symbol length
+    lowest_sym: Vec<u16>,
+    /// Lowest symbol of each symbol length padded to 64 bit
+    base64: Vec<u64>,
+    /// Left and right child symbols of each symbol, 12 bit each
+    btree: Vec<u8>,
+    /// Number of values (minus one) represented by each symbol
+    symlen: Vec<u8>,
+    /// Block and offset within the block of every span values (6 bytes each)
+    sparse_index: Vec<u8>,
+    sparse_index_size: usize,
+    /// Number of values (minus one) stored in each block
+    block_length: Vec<u16>,
+    block_length_size: usize,
+    /// Offset of the compressed data in the file
+    data: u64,
+    /// Pieces of the table in the order of the encoding
+    pieces: [u8; TB_PIECES],
+    /// Start index of every group of pieces encoded together
+    group_idx: [u64; TB_PIECES + 1],
+    /// Number of pieces of every group, zero terminated
+    group_len: [usize; TB_PIECES + 1],
+    /// Start of the DTZ map for each of the wdl results
+    map_idx: [u16; 4],
+}
+
+impl PairsData {
+    #[inline(always)]
+    fn left(&self, sym: usize) -> usize {
+        ((self.btree[3 * sym + 1] as usize & 0xF) << 8) | self.btree[3 * sym] as usize
+    }
+
+    #[inline(always)]
+    fn right(&self, sym: usize) -> usize {
+        ((self.btree[3 * sym + 2] as usize) << 4) | (self.btree[3 * sym + 1] as usize >> 4)
+    }
+}
+
+/// #### Table
+/// Single WDL or DTZ file of the syzygy tablebases.
+/// Only the headers are read into memory, the compressed blocks are read from the file when probed.
+pub(super) struct Table {
+    file: File,
+    file_len: u64,
+    table_type: TableType,
+    name: String,
+    /// Sub tables indexed by the side to move and the file of the leading pawn
+    pairs: Vec<Vec<PairsData>>,
+    /// Remapping of the DTZ values
+    dtz_map: Vec<u8>,
+}
+
+/// Reads the header of a table file with positioned reads
+struct HeaderReader<'a> {
+    file: &'a File,
+    offset: u64,
+}
+
+impl HeaderReader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<Vec<u8>, SyzygyError> {
+        let mut buf = vec![0; len];
+        read_exact_at(self.file, &mut buf, self.offset)?;
+        self.offset += len as u64;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8, SyzygyError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SyzygyError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SyzygyError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn align(&mut self, alignment: u64) {
+        self.offset = self.offset.next_multiple_of(alignment);
+    }
+}
+
+impl Table {
+    pub(super) fn open(
+        path: &Path,
+        table_type: TableType,
+        material: &Material,
+    ) -> Result<Table, SyzygyError> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let name = material.name.clone();
+        let corrupted = || SyzygyError::Corrupted(format!("{name}.{}", table_type.extension()));
+
+        if file_len % 64 != 16 {
+            return Err(corrupted());
+        }
+        let mut reader = HeaderReader {
+            file: &file,
+            offset: 0,
+        };
+        if reader.bytes(4)? != table_type.magic() {
+            return Err(SyzygyError::InvalidMagic(format!(
+                "{name}.{}",
+                table_type.extension()
+            )));
+        }
+
+        let header = reader.u8()?;
+        if (header & HEADER_HAS_PAWNS != 0) != material.has_pawns
+            || (header & HEADER_SPLIT != 0) == material.symmetric
+        {
+            return Err(corrupted());
+        }
+
+        let sides = if table_type == TableType::Wdl && !material.symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if material.has_pawns { 4 } else { 1 };
+        let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+
+        let mut pairs: Vec<Vec<PairsData>> = (0..sides)
+            .map(|_| (0..files).map(|_| PairsData::default()).collect())
+            .collect();
+
+        for file in 0..files {
+            let order_bytes = reader.bytes(1 + both_pawns as usize)?;
+            let order = |side: usize| {
+                let nibble = |byte: u8| if side == 0 { byte & 0xF } else { byte >> 4 };
+                [
+                    nibble(order_bytes[0]) as usize,
+                    if both_pawns {
+                        nibble(order_bytes[1]) as usize
+                    } else {
+                        0xF
+                    },
+                ]
+            };
+
+            let pieces = reader.bytes(material.piece_count)?;
+            for (side, side_pairs) in pairs.iter_mut().enumerate() {
+                let d = &mut side_pairs[file];
+                for (k, piece) in pieces.iter().enumerate() {
+                    d.pieces[k] = if side == 0 { piece & 0xF } else { piece >> 4 };
+                }
+                set_groups(material, d, order(side), file);
+            }
+        }
+        reader.align(2);
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut() {
+                set_sizes(&mut reader, &mut side_pairs[file]).map_err(|_| corrupted())?;
+            }
+        }
+
+        let dtz_map = if table_type == TableType::Dtz {
+            let map_start = reader.offset;
+            for d in pairs[0].iter_mut() {
+                if d.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                if d.flags & FLAG_WIDE != 0 {
+                    reader.align(2);
+                    for i in 0..4 {
+                        d.map_idx[i] = ((reader.offset - map_start) / 2 + 1) as u16;
+                        reader.offset += 2 * reader.u16()? as u64;
+                    }
+                } else {
+                    for i in 0..4 {
+                        d.map_idx[i] = (reader.offset - map_start + 1) as u16;
+                        reader.offset += reader.u8()? as u64;
+                    }
+                }
+            }
+            reader.align(2);
+            let map_len = (reader.offset - map_start) as usize;
+            reader.offset = map_start;
+            reader.bytes(map_len)?
+        } else {
+            Vec::new()
+        };
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut() {
+                let d = &mut side_pairs[file];
+                d.sparse_index = reader.bytes(d.sparse_index_size * 6)?;
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut() {
+                let d = &mut side_pairs[file];
+                d.block_length = reader
+                    .bytes(d.block_length_size * 2)?
+                    .chunks_exact(2)
+                    .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                    .collect();
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in pairs.iter_mut() {
+                let d = &mut side_pairs[file];
+                reader.align(64);
+                d.data = reader.offset;
+                reader.offset += d.num_blocks as u64 * d.block_size as u64;
+            }
+        }
+        if reader.offset > file_len {
+            return Err(corrupted());
+        }
+
+        Ok(Table {
+            file,
+            file_len,
+            table_type,
+            name: format!("{name}.{}", table_type.extension()),
+            pairs,
+            dtz_map,
+        })
+    }
+
+    /// Looks up the raw value of the position and returns it together with the file of the leading pawn.
+    /// Returns None if the table is a DTZ table which doesn't store the side to move.
+    /// `flipped` is true if black has the pieces of the white side of the table.
+    pub(super) fn probe(
+        &self,
+        board: &Board,
+        material: &Material,
+        flipped: bool,
+    ) -> Result<Option<(i32, usize)>, SyzygyError> {
+        //symmetric tables only store white to move, black to move is probed with flipped colors
+        let flip = flipped || (material.symmetric && board.side_to_move() == Color::Black);
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = flip as usize ^ board.side_to_move() as usize;
+
+        let mut squares = [0u8; TB_PIECES];
+        let mut pieces = [0u8; TB_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut lead_pawns_count = 0;
+        let mut tb_file = 0;
+
+        //the tables with pawns are split by the file of the leading pawn,
+        //the leading pawn is the one nearest to the edge with the lowest rank
+        if material.has_pawns {
+            let pawn = self.pairs[0][0].pieces[0] ^ flip_color;
+            let color = if pawn & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            lead_pawns = *board.get_bb_pieces()[color][PieceType::Pawn];
+            for sq in iter_set_bits(lead_pawns) {
+                squares[size] = sq ^ flip_squares;
+                size += 1;
+            }
+            lead_pawns_count = size;
+
+            let lead = (0..size)
+                .max_by_key(|&i| ENCODING.map_pawns[squares[i] as usize])
+                .unwrap_or(0);
+            squares.swap(0, lead);
+            tb_file = (squares[0] % 8).min(7 - squares[0] % 8) as usize;
+        }
+
+        if self.table_type == TableType::Dtz {
+            let flags = self.pairs[0][tb_file].flags;
+            if (flags & FLAG_STM) as usize != stm && (!material.symmetric || material.has_pawns) {
+                return Ok(None);
+            }
+        }
+
+        for sq in iter_set_bits(*board.get_bb_all_occupied() & !lead_pawns) {
+            let piece = board
+                .get_sq_piece(Square::new(sq))
+                .expect("Occupied square must have a piece");
+            squares[size] = sq ^ flip_squares;
+            pieces[size] = (piece.ptype() as u8 + 1 + 8 * piece.color() as u8) ^ flip_color;
+            size += 1;
+        }
+        if size != material.piece_count {
+            return Err(SyzygyError::Corrupted(self.name.clone()));
+        }
+
+        let d = &self.pairs[stm.min(self.pairs.len() - 1)][tb_file];
+
+        //order the pieces like the encoding of the table
+        for i in lead_pawns_count..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        //the leading piece is normalized to the files a to d
+        if squares[0] % 8 > 3 {
+            squares[..size].iter_mut().for_each(|sq| *sq ^= 7);
+        }
+
+        let mut idx;
+        if material.has_pawns {
+            idx = ENCODING.lead_pawn_idx[lead_pawns_count][squares[0] as usize];
+            squares[1..lead_pawns_count].sort_by_key(|&sq| ENCODING.map_pawns[sq as usize]);
+            for (i, sq) in squares.iter().enumerate().take(lead_pawns_count).skip(1) {
+                idx += ENCODING.binomial[i][ENCODING.map_pawns[*sq as usize]];
+            }
+        } else {
+            //without pawns the leading piece is further normalized to the ranks 1 to 4
+            //and below the a1-h8 diagonal
+            if squares[0] / 8 > 3 {
+                squares[..size].iter_mut().for_each(|sq| *sq ^= 56);
+            }
+            for i in 0..d.group_len[0] {
+                if off_a1h8(squares[i]) == 0 {
+                    continue;
+                }
+                if off_a1h8(squares[i]) > 0 {
+                    squares[i..size]
+                        .iter_mut()
+                        .for_each(|sq| *sq = ((*sq >> 3) | (*sq << 3)) & 63);
+                }
+                break;
+            }
+            idx = encode_leading_pieces(&squares, material.has_unique_pieces);
+        }
+
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = material.has_pawns && material.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[group_start..group_start + len].sort();
+
+            //squares of previous groups are skipped
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|&&s| sq > s).count();
+                n += ENCODING.binomial[i + 1][sq as usize - adjust - 8 * remaining_pawns as usize];
+            }
+
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start += len;
+            next += 1;
+        }
+
+        Ok(Some((self.decompress(d, idx)?, tb_file)))
+    }
+
+    /// Maps the raw DTZ value to plies
+    pub(super) fn map_dtz(&self, file: usize, value: i32, wdl: Wdl) -> i32 {
+        let d = &self.pairs[0][file];
+        let mut value = value as usize;
+        if d.flags & FLAG_MAPPED != 0 {
+            let idx = d.map_idx[match wdl {
+                Wdl::Win => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3,
+                Wdl::Draw => 0,
+            }] as usize
+                + value;
+            value = if d.flags & FLAG_WIDE != 0 {
+                self.dtz_map
+                    .get(2 * idx..2 * idx + 2)
+                    .map_or(0, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+            } else {
+                self.dtz_map.get(idx).map_or(0, |&byte| byte as usize)
+            };
+        }
+
+        //the values are stored in moves unless the flags say otherwise
+        let in_plies = match wdl {
+            Wdl::Win => d.flags & FLAG_WIN_PLIES != 0,
+            Wdl::Loss => d.flags & FLAG_LOSS_PLIES != 0,
+            _ => false,
+        };
+        if !in_plies {
+            value *= 2;
+        }
+        value as i32 + 1
+    }
+
+    /// Decompresses the value at the index of the sub table
+    fn decompress(&self, d: &PairsData, idx: u64) -> Result<i32, SyzygyError> {
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Ok(d.min_sym_len as i32);
+        }
+        let corrupted = || SyzygyError::Corrupted(self.name.clone());
+
+        //the sparse index points to the block of every span values,
+        //from there the block lengths are walked to the block which stores the index
+        let k = (idx / d.span) as usize;
+        let entry = d.sparse_index.get(6 * k..6 * k + 6).ok_or_else(corrupted)?;
+        let mut block = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize;
+        let mut offset = u16::from_le_bytes([entry[4], entry[5]]) as i64 + (idx % d.span) as i64
+            - (d.span / 2) as i64;
+
+        let block_length = |block: usize| {
+            d.block_length
+                .get(block)
+                .map(|&len| len as i64)
+                .ok_or_else(corrupted)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1).ok_or_else(corrupted)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        //the symbols of the block are decoded until the one which contains the offset
+        let start = d.data + (block * d.block_size) as u64;
+        let mut data = vec![0; d.block_size + 8];
+        let len = (data.len() as u64).min(self.file_len.saturating_sub(start)) as usize;
+        read_exact_at(&self.file, &mut data[..len], start)?;
+
+        let read_u32 = |pos: usize| {
+            data.get(pos..pos + 4).map_or(0, |bytes| {
+                u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            })
+        };
+        let mut buf64 = (read_u32(0) as u64) << 32 | read_u32(4) as u64;
+        let mut pos = 8;
+        let mut buf64_size = 64;
+        let min_sym_len = d.min_sym_len as usize;
+
+        let mut sym = loop {
+            let mut len = 0;
+            while buf64 < d.base64[len] {
+                len += 1;
+            }
+            let sym = ((buf64 - d.base64[len]) >> (64 - len - min_sym_len)) as u16;
+            let sym = sym.wrapping_add(d.lowest_sym[len]) as usize;
+            let sym_len = *d.symlen.get(sym).ok_or_else(corrupted)? as i64;
+
+            if offset < sym_len + 1 {
+                break sym;
+            }
+            offset -= sym_len + 1;
+
+            let len = len + min_sym_len;
+            buf64 <<= len;
+            buf64_size -= len;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (read_u32(pos) as u64) << (64 - buf64_size);
+                pos += 4;
+            }
+        };
+
+        //each symbol expands into a pair of symbols until the leaf which stores the value
+        while d.symlen[sym] != 0 {
+            let left = d.left(sym);
+            let left_len = *d.symlen.get(left).ok_or_else(corrupted)? as i64;
+            if offset < left_len + 1 {
+                sym = left;
+            } else {
+                offset -= left_len + 1;
+                sym = d.right(sym);
+            }
+            if sym >= d.symlen.len() {
+                return Err(corrupted());
+            }
+        }
+
+        Ok(d.left(sym) as i32)
+    }
+}
+
+/// Encodes the leading group of a table without pawns, either 3 unique pieces or both kings.
+/// The leading piece is already normalized to the a1-d1-d4 triangle.
+fn encode_leading_pieces(squares: &[u8; TB_PIECES], unique_pieces: bool) -> u64 {
+    let sq = squares.map(|sq| sq as u64);
+    let rank = |sq: u64| sq / 8;
+
+    if !unique_pieces {
+        return ENCODING.map_kk[ENCODING.map_a1d1d4[squares[0] as usize]][squares[1] as usize];
+    }
+
+    let adjust1 = (sq[1] > sq[0]) as u64;
+    let adjust2 = (sq[2] > sq[0]) as u64 + (sq[2] > sq[1]) as u64;
+
+    if off_a1h8(squares[0]) != 0 {
+        (ENCODING.map_a1d1d4[squares[0] as usize] as u64 * 63 + (sq[1] - adjust1)) * 62 + sq[2]
+            - adjust2
+    } else if off_a1h8(squares[1]) != 0 {
+        (6 * 63 + rank(sq[0]) * 28 + ENCODING.map_b1h1h7[squares[1] as usize] as u64) * 62 + sq[2]
+            - adjust2
+    } else if off_a1h8(squares[2]) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(sq[0]) * 7 * 28
+            + (rank(sq[1]) - adjust1) * 28
+            + ENCODING.map_b1h1h7[squares[2] as usize] as u64
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + rank(sq[0]) * 7 * 6
+            + (rank(sq[1]) - adjust1) * 6
+            + (rank(sq[2]) - adjust2)
+    }
+}
+
+/// Splits the pieces into the groups which are encoded together and calculates the start index of every group.
+/// The groups are the leading pawns or pieces, the remaining pawns and groups of equal pieces,
+/// the order of the encoding is stored in the table.
+fn set_groups(material: &Material, d: &mut PairsData, order: [usize; 2], file: usize) {
+    let mut n = 0;
+    let mut first_len: i32 = if material.has_pawns {
+        0
+    } else if material.has_unique_pieces {
+        3
+    } else {
+        2
+    };
+    d.group_len[n] = 1;
+
+    for i in 1..material.piece_count {
+        first_len -= 1;
+        if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+            d.group_len[n] += 1;
+        } else {
+            n += 1;
+            d.group_len[n] = 1;
+        }
+    }
+    n += 1;
+    d.group_len[n] = 0;
+
+    let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+    let mut next = if both_pawns { 2 } else { 1 };
+    let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+    let mut idx = 1u64;
+
+    let mut k = 0;
+    while next < n || k == order[0] || k == order[1] {
+        if k == order[0] {
+            d.group_idx[0] = idx;
+            idx *= if material.has_pawns {
+                ENCODING.lead_pawns_size[d.group_len[0]][file]
+            } else if material.has_unique_pieces {
+                UNIQUE_PIECES_SIZE
+            } else {
+                KINGS_SIZE
+            };
+        } else if k == order[1] {
+            d.group_idx[1] = idx;
+            idx *= ENCODING.binomial[d.group_len[1]][48 - d.group_len[0]];
+        } else {
+            d.group_idx[next] = idx;
+            idx *= ENCODING.binomial[d.group_len[next]][free_squares];
+            free_squares -= d.group_len[next];
+            next += 1;
+        }
+        k += 1;
+    }
+    d.group_idx[n] = idx;
+}
+
+/// Reads the sizes and the huffman code of the sub table
+fn set_sizes(reader: &mut HeaderReader, d: &mut PairsData) -> Result<(), SyzygyError> {
+    d.flags = reader.u8()?;
+    if d.flags & FLAG_SINGLE_VALUE != 0 {
+        d.min_sym_len = reader.u8()?;
+        return Ok(());
+    }
+
+    let groups = d.group_len.iter().position(|&len| len == 0).unwrap_or(0);
+    let tb_size = d.group_idx[groups];
+
+    d.block_size = 1 << reader.u8()?;
+    d.span = 1 << reader.u8()?;
+    d.sparse_index_size = tb_size.div_ceil(d.span) as usize;
+    let padding = reader.u8()?;
+    d.num_blocks = reader.u32()?;
+    d.block_length_size = d.num_blocks as usize + padding as usize;
+    let max_sym_len = reader.u8()?;
+    d.min_sym_len = reader.u8()?;
+    if max_sym_len < d.min_sym_len || d.min_sym_len == 0 || max_sym_len > 32 {
+        return Err(SyzygyError::Corrupted(String::new()));
+    }
+
+    let lengths = (max_sym_len - d.min_sym_len + 1) as usize;
+    d.lowest_sym = (0..lengths)
+        .map(|_| reader.u16())
+        .collect::<Result<_, _>>()?;
+
+    //canonical huffman code, longer symbols have lower values
+    d.base64 = vec![0; lengths];
+    for i in (0..lengths - 1).rev() {
+        d.base64[i] =
+            (d.base64[i + 1] + d.lowest_sym[i] as u64).wrapping_sub(d.lowest_sym[i + 1] as u64) / 2;
+    }
+    for (i, base) in d.base64.iter_mut().enumerate() {
+        *base = base
+            .checked_shl((64 - i - d.min_sym_len as usize) as u32)
+            .unwrap_or(0);
+    }
+
+    let symbols = reader.u16()? as usize;
+    d.btree = reader.bytes(3 * symbols)?;
+    reader.offset += (symbols & 1) as u64;
+
+    d.symlen = vec![0; symbols];
+    let mut visited = vec![false; symbols];
+    for sym in 0..symbols {
+        if !visited[sym] {
+            d.symlen[sym] = set_symlen(d, sym, &mut visited)?;
+        }
+    }
+    Ok(())
+}
+
+/// Calculates the number of values represented by the symbol by expanding its children
+fn set_symlen(d: &mut PairsData, sym: usize, visited: &mut [bool]) -> Result<u8, SyzygyError> {
+    visited[sym] = true;
+    let right = d.right(sym);
+    if right == 0xFFF {
+        return Ok(0);
+    }
+    let left = d.left(sym);
+    if left >= d.symlen.len() || right >= d.symlen.len() {
+        return Err(SyzygyError::Corrupted(String::new()));
+    }
+
+    if !visited[left] {
+        d.symlen[left] = set_symlen(d, left, visited)?;
+    }
+    if !visited[right] {
+        d.symlen[right] = set_symlen(d, right, visited)?;
+    }
+    Ok(d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1))
+}
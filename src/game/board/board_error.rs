@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::game::{castle_rights::CastleType, color::Color, piece::Piece, square::Square};
+use crate::game::{
+    castle_rights::CastleType, color::Color, move_notation::UCIMoveParseError, piece::Piece,
+    piece_type::PieceType, square::Square,
+};
 
 #[derive(Debug, Error)]
 pub enum FENError {
@@ -10,6 +13,32 @@ pub enum FENError {
     MissingGroup(&'static str),
     #[error("Missing King in FEN String for color {0}")]
     MissingKing(Color),
+    #[error("Illegal position: {0}")]
+    IllegalPosition(#[from] PositionError),
+}
+
+#[derive(Debug, Error)]
+pub enum PositionError {
+    #[error("{0:?} has no king")]
+    MissingKing(Color),
+    #[error("{0:?} has more than one king")]
+    TooManyKings(Color),
+    #[error("The kings are on adjacent squares")]
+    AdjacentKings,
+    #[error("{0:?} is in check, but it is not their turn")]
+    OpponentInCheck(Color),
+    #[error("Pawn on the back rank square {0}")]
+    PawnOnBackRank(Square),
+    #[error("{0:?} has more than 16 pieces")]
+    TooManyPieces(Color),
+    #[error("{0:?} has more than 8 pawns")]
+    TooManyPawns(Color),
+    #[error("{0:?} has more promoted pieces than missing pawns")]
+    TooManyPromotedPieces(Color),
+    #[error("{1:?} can not castle {0}, the king or the rook is not on its start square")]
+    InvalidCastleRight(CastleType, Color),
+    #[error("Invalid en passant square {0}")]
+    InvalidEnPassant(Square),
 }
 
 #[derive(Debug, Error)]
@@ -30,6 +59,22 @@ pub enum MoveError {
     WrongColor(Color),
     #[error("{1} does not have the right to castle {0:?}")]
     MissingCastleRight(CastleType, Color),
+    #[error("Castling {0} is blocked or the king passes an attacked square")]
+    CastleBlocked(CastleType),
+    #[error("The move leaves the king in check")]
+    KingInCheck,
+    #[error("A capture is possible and has to be played")]
+    CaptureRequired,
+    #[error("{1:?} has no {0:?} in the pocket")]
+    EmptyPocket(PieceType, Color),
+    #[error("{0} can not be dropped on {1}")]
+    InvalidDrop(Piece, Square),
+    #[error("No legal move matches {0}")]
+    NoMatchingMove(String),
+    #[error("Invalid SAN notation {0}")]
+    SANNotation(String),
+    #[error(transparent)]
+    UCINotation(#[from] UCIMoveParseError),
 }
 
 #[derive(Debug, Error)]
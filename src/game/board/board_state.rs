@@ -1,3 +1,4 @@
+use super::pocket::Pockets;
 use crate::game::{castle_rights::CastleRights, piece_type::PieceType, square::Square};
 
 // BoardState is used to store the state metadata of the board to be able to undo moves
@@ -8,6 +9,8 @@ pub struct BoardState {
     pub en_passant: Option<Square>,
     pub castling_rights: CastleRights,
     pub captured_piece: Option<PieceType>,
+    pub checks: [u8; 2], //checks given by each color, only counted in variants like Three-check
+    pub pockets: Pockets, //captured pieces which can be dropped in Crazyhouse
 }
 
 impl std::default::Default for BoardState {
@@ -18,6 +21,8 @@ impl std::default::Default for BoardState {
             en_passant: None,
             castling_rights: CastleRights::default(),
             captured_piece: None,
+            checks: [0; 2],
+            pockets: Pockets::default(),
         }
     }
 }
@@ -29,6 +34,8 @@ impl BoardState {
         en_passant: Option<Square>,
         castling_rights: CastleRights,
         captured_piece: Option<PieceType>,
+        checks: [u8; 2],
+        pockets: Pockets,
     ) -> BoardState {
         BoardState {
             zobrist,
@@ -36,6 +43,8 @@ impl BoardState {
             en_passant,
             castling_rights,
             captured_piece,
+            checks,
+            pockets,
         }
     }
 }
@@ -0,0 +1,198 @@
+use crate::game::{
+    castle_rights::{CastleRights, CastleType},
+    color::Color,
+    piece::Piece,
+    square::Square,
+    variant::Variant,
+};
+
+use super::{board_error::PositionError, fen_utility::FENUtility, zobrist::ZOBRIST, Board};
+
+/// #### Board builder
+/// Sets up a position piece by piece instead of writing a fen, e.g. for a board editor or generated test positions.
+/// The pieces are put on an internal board, so the piece square table, slider bitboards and incremental scores stay consistent.
+/// The zobrist key is calculated and the position validated when the board is built.
+#[derive(Clone)]
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        let mut board = Board::empty();
+        board.ply_count = 1;
+        BoardBuilder { board }
+    }
+}
+
+impl BoardBuilder {
+    /// Builder starting with the position of the board, the move history is dropped
+    pub fn from_board(board: &Board) -> Self {
+        let mut board = board.clone();
+        board.previous_states.clear();
+        board.repetition_history.clear();
+        BoardBuilder { board }
+    }
+
+    /// Puts the piece on the square, replacing the piece on the square
+    pub fn put_piece(&mut self, piece: Piece, square: Square) -> &mut Self {
+        self.remove_piece(square);
+        self.board.update_bb(piece, square, true);
+        self
+    }
+
+    pub fn remove_piece(&mut self, square: Square) -> &mut Self {
+        if let Some(piece) = self.board.get_sq_piece(square) {
+            self.board.update_bb(piece, square, false);
+            self.board.current_state.pockets.set_promoted(square, false);
+        }
+        self
+    }
+
+    /// Removes all pieces together with the castle rights and the en passant square
+    pub fn clear(&mut self) -> &mut Self {
+        for square in self.board.get_bb_all_occupied().get_occupied() {
+            self.remove_piece(square);
+        }
+        self.board.current_state.castling_rights = CastleRights::default();
+        self.board.current_state.en_passant = None;
+        self
+    }
+
+    /// Sets the side to move, the move number is kept
+    pub fn set_side_to_move(&mut self, color: Color) -> &mut Self {
+        let move_number = self.move_number();
+        self.board.side_to_move = color;
+        self.set_move_number(move_number);
+        self
+    }
+
+    /// Sets the right of the color to castle with the rook on the file of the back rank,
+    /// the right is removed without a rook file
+    pub fn set_castling(
+        &mut self,
+        color: Color,
+        castle_type: CastleType,
+        rook_file: Option<u8>,
+    ) -> &mut Self {
+        let rights = &mut self.board.current_state.castling_rights;
+        rights.update(color, castle_type, rook_file.is_some());
+        if let Some(file) = rook_file {
+            rights.set_rook_file(color, castle_type, file);
+        }
+        self
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) -> &mut Self {
+        self.board.current_state.en_passant = square;
+        self
+    }
+
+    /// Sets the half move clock of the fifty move rule and the move number (starting at 1)
+    pub fn set_clocks(&mut self, half_move_clock: u8, move_number: usize) -> &mut Self {
+        self.board.current_state.ply_clock = half_move_clock;
+        self.set_move_number(move_number.max(1));
+        self
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) -> &mut Self {
+        self.board.variant = variant;
+        self
+    }
+
+    /// Builds the board if the position is legal in the variant of the board
+    pub fn build(&self) -> Result<Board, PositionError> {
+        let mut board = self.board.clone();
+        board.validate()?;
+        board.chess960 = FENUtility::is_chess960_castling(&board);
+        board.current_state.zobrist = ZOBRIST.calculate_zobrist_key(&board);
+        Ok(board)
+    }
+
+    fn move_number(&self) -> usize {
+        self.board.ply_count.div_ceil(2)
+    }
+
+    fn set_move_number(&mut self, move_number: usize) {
+        self.board.ply_count = match self.board.side_to_move {
+            Color::White => move_number * 2 - 1,
+            Color::Black => move_number * 2,
+        };
+    }
+}
+
+mod test {
+    #[test]
+    fn test_board_builder() {
+        use crate::game::{
+            board::{board_error::PositionError, builder::BoardBuilder},
+            castle_rights::CastleType,
+            Board, Color, Piece, PieceType, Square,
+        };
+
+        let mut builder = BoardBuilder::default();
+        let back_rank = [
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ];
+        for (file, piece_type) in back_rank.into_iter().enumerate() {
+            let file = file as u8;
+            builder
+                .put_piece(piece_type.as_colored_piece(Color::White), Square::new(file))
+                .put_piece(Piece::WHITE_PAWN, Square::new(8 + file))
+                .put_piece(Piece::BLACK_PAWN, Square::new(48 + file))
+                .put_piece(
+                    piece_type.as_colored_piece(Color::Black),
+                    Square::new(56 + file),
+                );
+        }
+        //the piece on the square is replaced
+        builder.put_piece(Piece::WHITE_QUEEN, Square::E2);
+        builder.put_piece(Piece::WHITE_PAWN, Square::E2);
+        for color in [Color::White, Color::Black] {
+            builder
+                .set_castling(color, CastleType::KingSide, Some(7))
+                .set_castling(color, CastleType::QueenSide, Some(0));
+        }
+
+        let board = builder.build().unwrap();
+        let start = Board::default();
+        assert_eq!(board.to_fen(), start.to_fen());
+        assert_eq!(board.cur_state().zobrist, start.cur_state().zobrist);
+        assert_eq!(
+            board.psqt_score(Color::White),
+            start.psqt_score(Color::White)
+        );
+
+        let board = builder
+            .set_side_to_move(Color::Black)
+            .set_clocks(3, 20)
+            .remove_piece(Square::H1)
+            .set_castling(Color::White, CastleType::KingSide, None)
+            .build()
+            .unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 b Qkq - 3 20"
+        );
+        assert_eq!(
+            board.cur_state().zobrist,
+            Board::from_fen(&board.to_fen())
+                .unwrap()
+                .cur_state()
+                .zobrist
+        );
+
+        builder.clear().put_piece(Piece::WHITE_KING, Square::E1);
+        assert!(matches!(
+            builder.build(),
+            Err(PositionError::MissingKing(Color::Black))
+        ));
+    }
+}
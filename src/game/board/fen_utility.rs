@@ -1,8 +1,15 @@
 use itertools::Itertools;
 
-use crate::game::{castle_rights::CastleRights, color::Color, piece::Piece, square::Square};
+use crate::game::{
+    castle_rights::{CastleRights, CastleRightsError, CastleType},
+    color::Color,
+    piece::Piece,
+    piece_type::PieceType,
+    square::Square,
+    variant::{Variant, THREE_CHECK_LIMIT},
+};
 
-use super::{board_error::FENError, zobrist::ZOBRIST, Board};
+use super::{board_error::FENError, pocket::Pockets, zobrist::ZOBRIST, Board};
 
 // FEN struct to handle conversion between FEN strings and boards
 pub struct FENUtility();
@@ -11,7 +18,14 @@ impl FENUtility {
     pub const START_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
     pub fn from_fen(fen_string: &str) -> Result<Board, FENError> {
+        FENUtility::from_fen_variant(fen_string, Variant::Standard)
+    }
+
+    /// Parses the fen of a position of the variant, without a royal king any number of kings is allowed
+    /// and the castle rights are ignored
+    pub fn from_fen_variant(fen_string: &str, variant: Variant) -> Result<Board, FENError> {
         let mut board = Board::empty();
+        board.variant = variant;
 
         let mut splits = fen_string.split_whitespace();
 
@@ -19,9 +33,36 @@ impl FENUtility {
             .next()
             .ok_or(FENError::MissingGroup("Figure position"))?;
 
+        //the pockets of Crazyhouse are written in brackets after the pieces, e.g. [Qp]
+        let fen_group = match fen_group.split_once('[') {
+            Some((pieces, pockets)) => {
+                let pockets = pockets
+                    .strip_suffix(']')
+                    .and_then(Pockets::from_fen)
+                    .ok_or(FENError::ParsingError(format!(
+                        "[{} are no valid pockets",
+                        pockets
+                    )))?;
+                board.current_state.pockets = pockets;
+                board.variant = Variant::Crazyhouse;
+                pieces
+            }
+            None => fen_group,
+        };
+
         let mut row: i8 = 7;
         let mut col: i8 = 0;
         for char in fen_group.chars() {
+            //promoted pieces of Crazyhouse are marked with a ~ after the piece
+            if char == '~' {
+                let square = Square::try_from((row * 8 + col - 1) as u8)
+                    .map_err(|_| FENError::ParsingError("~ must follow a piece".to_string()))?;
+                if col == 0 || board.get_sq_piece(square).is_none() {
+                    return Err(FENError::ParsingError("~ must follow a piece".to_string()));
+                }
+                board.current_state.pockets.set_promoted(square, true);
+                continue;
+            }
             if char != '/' && (row < 0 || col > 7) {
                 return Err(FENError::ParsingError(format!(
                     "{} is no valid character",
@@ -57,14 +98,16 @@ impl FENUtility {
             }
         }
 
-        board
-            .get_piece_positions(Piece::WHITE_KING)
-            .exactly_one()
-            .map_err(|_| FENError::MissingKing(Color::White))?;
-        board
-            .get_piece_positions(Piece::BLACK_KING)
-            .exactly_one()
-            .map_err(|_| FENError::MissingKing(Color::Black))?;
+        if variant.has_royal_king() {
+            board
+                .get_piece_positions(Piece::WHITE_KING)
+                .exactly_one()
+                .map_err(|_| FENError::MissingKing(Color::White))?;
+            board
+                .get_piece_positions(Piece::BLACK_KING)
+                .exactly_one()
+                .map_err(|_| FENError::MissingKing(Color::Black))?;
+        }
 
         let fen_group = splits
             .next()
@@ -84,8 +127,12 @@ impl FENUtility {
 
         let fen_group = splits.next().ok_or(FENError::MissingGroup("Castling"))?;
 
-        board.current_state.castling_rights = CastleRights::try_from(fen_group)
-            .map_err(|err| FENError::ParsingError(err.to_string()))?;
+        if variant.has_royal_king() {
+            board.current_state.castling_rights =
+                FENUtility::parse_castling_rights(fen_group, &board)
+                    .map_err(|err| FENError::ParsingError(err.to_string()))?;
+            board.chess960 = FENUtility::is_chess960_castling(&board);
+        }
 
         let fen_group = splits
             .next()
@@ -123,19 +170,171 @@ impl FENUtility {
         let moves: usize = fen_group.parse().map_err(|_| {
             FENError::ParsingError(format!("{} not a valid positive number", fen_group))
         })?;
+        //some tools write the move number 0, which is read as the first move
+        let moves = moves.max(1);
         board.ply_count = match board.side_to_move {
             Color::White => moves * 2 - 1,
             Color::Black => moves * 2,
         };
 
+        //optional checks given by white and black in Three-check, e.g. +1+0
+        if let Some(fen_group) = splits.next() {
+            board.current_state.checks = FENUtility::parse_checks(fen_group)?;
+            board.variant = Variant::ThreeCheck;
+        }
+
         let zobrist = ZOBRIST.calculate_zobrist_key(&board);
         board.current_state.zobrist = zobrist;
 
         Ok(board)
     }
 
-    // Convert the board to a FEN string
+    /// Parses the checks given by white and black written as +W+B
+    fn parse_checks(value: &str) -> Result<[u8; 2], FENError> {
+        let invalid = || FENError::ParsingError(format!("{} are no valid checks", value));
+        let (white, black) = value
+            .strip_prefix('+')
+            .and_then(|checks| checks.split_once('+'))
+            .ok_or_else(invalid)?;
+        let white: u8 = white.parse().map_err(|_| invalid())?;
+        let black: u8 = black.parse().map_err(|_| invalid())?;
+        if white > THREE_CHECK_LIMIT || black > THREE_CHECK_LIMIT {
+            return Err(invalid());
+        }
+        Ok([white, black])
+    }
+
+    /// Parses the castle rights in the standard notation (KQkq), X-FEN or Shredder-FEN.
+    /// K and Q are the outermost rooks on the side of the king, files (A-H, a-h) name the castling rook directly.
+    fn parse_castling_rights(
+        value: &str,
+        board: &Board,
+    ) -> Result<CastleRights, CastleRightsError> {
+        if value == "-" {
+            return Ok(CastleRights::default());
+        }
+
+        let mut rights = CastleRights::default();
+        for c in value.chars() {
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let back_rank = match color {
+                Color::White => 0,
+                Color::Black => 7,
+            };
+            let king_file = board.get_king_pos(color).file();
+            let rook_files: Vec<u8> = board
+                .get_piece_positions(PieceType::Rook.as_colored_piece(color))
+                .filter(|sq| sq.rank() == back_rank)
+                .map(|sq| sq.file())
+                .collect();
+
+            let (castle_type, file) = match c.to_ascii_lowercase() {
+                'k' => (
+                    CastleType::KingSide,
+                    //without a rook the standard file is used to keep the notation of invalid positions
+                    rook_files
+                        .iter()
+                        .copied()
+                        .filter(|file| *file > king_file)
+                        .max()
+                        .unwrap_or(7),
+                ),
+                'q' => (
+                    CastleType::QueenSide,
+                    rook_files
+                        .iter()
+                        .copied()
+                        .filter(|file| *file < king_file)
+                        .min()
+                        .unwrap_or(0),
+                ),
+                file @ 'a'..='h' => {
+                    let file = file as u8 - b'a';
+                    if !rook_files.contains(&file) {
+                        return Err(CastleRightsError::MissingRook(c));
+                    }
+                    if file > king_file {
+                        (CastleType::KingSide, file)
+                    } else {
+                        (CastleType::QueenSide, file)
+                    }
+                }
+                _ => return Err(CastleRightsError::InvalidCharacter(c)),
+            };
+            rights.update(color, castle_type, true);
+            rights.set_rook_file(color, castle_type, file);
+        }
+        Ok(rights)
+    }
+
+    /// Castling of the position is only possible in Chess960 if a king with castle rights
+    /// is not on the e file or a castling rook not on the a or h file
+    pub(super) fn is_chess960_castling(board: &Board) -> bool {
+        let rights = board.current_state.castling_rights;
+        !rights.has_standard_rooks()
+            || [Color::White, Color::Black].into_iter().any(|color| {
+                rights.has_any(color) && board.get_king_pos(color) != CastleType::KING_SOURCE[color]
+            })
+    }
+
+    /// Writes the castle rights in X-FEN, K and Q are used if the castling rook is the outermost rook
+    /// on the side of the king, else the file of the rook. Standard positions are written as KQkq.
+    fn castling_rights_to_fen(board: &Board, shredder: bool) -> String {
+        let rights = board.current_state.castling_rights;
+        let mut fen = String::new();
+        for (color, castle_type) in [
+            (Color::White, CastleType::KingSide),
+            (Color::White, CastleType::QueenSide),
+            (Color::Black, CastleType::KingSide),
+            (Color::Black, CastleType::QueenSide),
+        ] {
+            if !rights.has(color, castle_type) {
+                continue;
+            }
+            let (rook_sq, _) = rights.rook_positions(color, castle_type);
+            let outermost = board
+                .get_piece_positions(PieceType::Rook.as_colored_piece(color))
+                .filter(|sq| sq.rank() == rook_sq.rank())
+                .all(|sq| match castle_type {
+                    CastleType::KingSide => sq.file() <= rook_sq.file(),
+                    CastleType::QueenSide => sq.file() >= rook_sq.file(),
+                });
+
+            if shredder || !outermost {
+                fen.push(CastleRights::file_char(rook_sq.file(), color));
+            } else {
+                let char = match castle_type {
+                    CastleType::KingSide => 'K',
+                    CastleType::QueenSide => 'Q',
+                };
+                fen.push(match color {
+                    Color::White => char,
+                    Color::Black => char.to_ascii_lowercase(),
+                });
+            }
+        }
+
+        if fen.is_empty() {
+            fen.push('-');
+        }
+        fen
+    }
+
+    // Convert the board to a FEN string with the castle rights in X-FEN
     pub fn to_fen(board: &Board) -> String {
+        FENUtility::fen_with_castling(board, false)
+    }
+
+    // Convert the board to a FEN string with the castle rights in Shredder-FEN
+    pub fn to_shredder_fen(board: &Board) -> String {
+        FENUtility::fen_with_castling(board, true)
+    }
+
+    fn fen_with_castling(board: &Board, shredder: bool) -> String {
         let mut fen_string = String::with_capacity(64); // try to avoid reallocations
         let mut empty_squares = 0;
         Square::iter_ah_81().for_each(|square| {
@@ -147,6 +346,9 @@ impl FENUtility {
                         empty_squares = 0;
                     }
                     fen_string.push_str(&format!("{piece}"));
+                    if board.current_state.pockets.is_promoted(square) {
+                        fen_string.push('~');
+                    }
                 }
                 None => {
                     empty_squares += 1;
@@ -163,8 +365,12 @@ impl FENUtility {
             }
         });
 
+        if board.variant.has_drops() {
+            fen_string.push_str(&format!("[{}]", board.current_state.pockets.to_fen()));
+        }
+
         fen_string.push_str(&format!(" {} ", board.side_to_move));
-        fen_string.push_str(&board.current_state.castling_rights.to_string());
+        fen_string.push_str(&FENUtility::castling_rights_to_fen(board, shredder));
 
         match board.current_state.en_passant {
             Some(square) => fen_string.push_str(&format!(" {square} ")),
@@ -172,8 +378,50 @@ impl FENUtility {
         }
 
         fen_string.push_str(&format!("{}", board.current_state.ply_clock));
-        fen_string.push_str(&format!(" {}", board.ply_count() / 2));
+        fen_string.push_str(&format!(" {}", board.ply_count().div_ceil(2)));
+
+        if board.variant.counts_checks() {
+            let checks = board.current_state.checks;
+            fen_string.push_str(&format!(
+                " +{}+{}",
+                checks[Color::White],
+                checks[Color::Black]
+            ));
+        }
 
         fen_string
     }
 }
+
+mod test {
+    #[test]
+    fn test_chess960_fen() {
+        use crate::game::{move_notation::MoveFlag, Board, Move};
+
+        //castling with the inner rook is written with its file in X-FEN
+        let board = Board::from_fen("1k6/8/8/8/8/8/8/RK1R3R w D - 0 1").unwrap();
+        assert!(board.is_chess960());
+        assert_eq!(board.to_fen(), "1k6/8/8/8/8/8/8/RK1R3R w D - 0 1");
+        let board = Board::from_fen("1k6/8/8/8/8/8/8/RK1R3R w KQ - 0 1").unwrap();
+        assert_eq!(board.to_shredder_fen(), "1k6/8/8/8/8/8/8/RK1R3R w HA - 0 1");
+        assert!(Board::from_fen("1k6/8/8/8/8/8/8/RK1R3R w G - 0 1").is_err());
+
+        //the king castles onto the square of the rook
+        let fen = "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w KQ - 1 9"
+        );
+        let mov = Move::from_uci_notation("f1g1", &board).unwrap();
+        assert_eq!(mov.flag(), MoveFlag::KingSideCastle);
+        assert_eq!(mov.to_uci(&board), "f1g1");
+        board.make_move(&mov, false, true).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRRKB b - - 2 9"
+        );
+        board.undo_move(&mov, false).unwrap();
+        assert_eq!(board.to_shredder_fen(), fen);
+    }
+}
@@ -1,19 +1,21 @@
 use super::{move_gen::LegalMoveList, Board};
-use crate::game::{Color, MoveGeneration, PieceType};
+use crate::game::{Color, MoveGeneration, PieceType, Square, Variant};
 
 impl GameResult {
     pub fn get_game_result(board: &Board, move_gen: Option<&LegalMoveList>) -> GameResult {
+        if let Some(result) = board.variant().game_result(board) {
+            return result;
+        }
+
         let moves = match move_gen {
             Some(moves) => moves,
             None => &MoveGeneration::generate_legal_moves(board),
         };
 
         if moves.is_empty() {
-            if moves.get_masks().in_check {
-                return GameResult::Mate(board.side_to_move);
-            } else {
-                return GameResult::Stalemate;
-            }
+            return board
+                .variant()
+                .no_moves_result(board, moves.get_masks().in_check);
         }
 
         if board.current_state.ply_clock >= 100 {
@@ -39,7 +41,20 @@ impl GameResult {
         GameResult::InProgress
     }
 
-    fn check_insufficient_material(board: &Board) -> bool {
+    /// Neither side has enough material to mate, even with the help of the opponent
+    pub(crate) fn check_insufficient_material(board: &Board) -> bool {
+        //pieces in the pockets can still be dropped, without a royal king the kings can be captured
+        if !board.current_state.pockets.is_empty() || !board.variant().has_royal_king() {
+            return false;
+        }
+        match board.variant() {
+            //the king can always walk to the hill
+            Variant::KingOfTheHill => return false,
+            //any piece can give the checks with the help of the opponent, only the bare kings can't
+            Variant::ThreeCheck => return board.get_bb_all_occupied().count_ones() == 2,
+            _ => {}
+        }
+
         //if there are any pawns, rooks, or queens, the game is not in an insufficient material state
         if *board.bb_pieces[Color::White][PieceType::Pawn] > 0
             || *board.bb_pieces[Color::Black][PieceType::Pawn] > 0
@@ -63,11 +78,15 @@ impl GameResult {
         }
 
         if num_minors == 2 && num_white_bishops == 1 && num_black_bishops == 1 {
-            let is_white_bishop_light =
-                board.bb_pieces[Color::White][PieceType::Bishop].trailing_zeros() % 2 == 0;
-            let is_black_bishop_light =
-                board.bb_pieces[Color::Black][PieceType::Bishop].trailing_zeros() % 2 == 0;
-            return is_white_bishop_light == is_black_bishop_light;
+            let white_bishop_square_color = Square::new(
+                board.bb_pieces[Color::White][PieceType::Bishop].trailing_zeros() as u8,
+            )
+            .color();
+            let black_bishop_square_color = Square::new(
+                board.bb_pieces[Color::Black][PieceType::Bishop].trailing_zeros() as u8,
+            )
+            .color();
+            return white_bishop_square_color == black_bishop_square_color;
         }
 
         false
@@ -85,6 +104,7 @@ pub enum GameResult {
     Draw,
     Resign(Color), // can be used to indicate a player has resigned, not returned by get_game_result
     Timeout(Color), // can be used to indicate a player has run out of time, not returned by get_game_result
+    VariantEnd(Color), // the color lost by the rules of the variant, e.g. three checks received
 }
 
 impl GameResult {
@@ -108,6 +128,7 @@ impl GameResult {
             GameResult::Mate(color) => Some(*color),
             GameResult::Resign(color) => Some(*color),
             GameResult::Timeout(color) => Some(*color),
+            GameResult::VariantEnd(color) => Some(*color),
             _ => None,
         }
     }
@@ -134,6 +155,10 @@ impl From<GameResult> for &str {
                 Color::White => "0-1",
                 Color::Black => "1-0",
             },
+            GameResult::VariantEnd(color) => match color {
+                Color::White => "0-1",
+                Color::Black => "1-0",
+            },
         }
     }
 }
@@ -1,22 +1,34 @@
 pub mod bit_board;
 pub mod board_error;
 pub mod board_state;
+pub mod builder;
 pub mod display;
 pub mod fen_utility;
 pub mod game_result;
 pub mod move_gen;
+mod move_validation;
+pub mod pocket;
+mod position_validation;
+mod transform;
 pub mod zobrist;
 
 use bit_board::BitBoard;
-use board_error::{FENError, UndoMoveError};
+use board_error::{FENError, MoveError, UndoMoveError};
 use board_state::BoardState;
 use fen_utility::FENUtility;
-use move_gen::{attacks_bishop, attacks_knight, attacks_pawn, attacks_rook, MoveGeneration};
+use move_gen::{attacks_bishop, attacks_knight, attacks_pawn, attacks_rook};
 use zobrist::ZOBRIST;
 
 use super::{
-    bit_manipulation::bit_scan_lsb, castle_rights::CastleType, color::Color, move_notation::Move,
-    piece::Piece, piece_type::PieceType, square::Square,
+    bit_manipulation::bit_scan_lsb,
+    castle_rights::CastleType,
+    color::Color,
+    move_notation::Move,
+    piece::Piece,
+    piece_square_table::{piece_square_value, Eval, NON_PAWN_MATERIAL, W},
+    piece_type::PieceType,
+    square::Square,
+    variant::Variant,
 };
 #[derive(Clone)]
 pub struct Board {
@@ -24,11 +36,15 @@ pub struct Board {
     bb_occupied: [BitBoard; 2],    //stores the occupied squares for each color
     bb_sliders: [BitBoard; 2],     //stores the occupied squares of sliding pieces for each color
     piece_square_table: [Option<PieceType>; 64], //quickly find the piece type on a square
+    psqt_score: [W; 2],            //material and piece square table score for each color
+    non_pawn_material: [Eval; 2],  //material without pawns for each color
     side_to_move: Color,
     ply_count: usize, //number of half moves played
     current_state: BoardState,
     previous_states: Vec<BoardState>, //stores the previous states of the board to be able to undo moves
     repetition_history: Vec<u64>, //stores the zobrist keys of the previous states to check for repetitions //TODO use a HashMap counter
+    chess960: bool, //castling moves are written as king takes rook and fens with the files of the rooks
+    variant: Variant,
 }
 
 impl std::default::Default for Board {
@@ -47,6 +63,7 @@ impl std::fmt::Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}", display::BoardDisplay::as_ascii(&self))?;
         writeln!(f, "Side to move: {}", self.side_to_move)?;
+        writeln!(f, "Variant: {}", self.variant)?;
         writeln!(f, "Ply count: {}", self.ply_count)?;
         writeln!(f, "Current State: {:?}", self.current_state)?;
         writeln!(f, "Previous States count: {:?}", self.previous_states.len())?;
@@ -65,6 +82,8 @@ impl Board {
             bb_occupied: [BitBoard::default(); 2],
             bb_sliders: [BitBoard::default(); 2],
             piece_square_table: [None; 64],
+            psqt_score: [W::ZERO; 2],
+            non_pawn_material: [0; 2],
             side_to_move: Color::White,
             ply_count: 0,
             current_state: BoardState::default(),
@@ -73,9 +92,26 @@ impl Board {
             // (median game length is about 70 half moves  so it should only reallocate once if even required)
             repetition_history: Vec::with_capacity(32),
             //allocate space for 32 half moves to avoid reallocation (median game length is about 70 and irreversible moves reset the repetition history)
+            chess960: false,
+            variant: Variant::Standard,
         }
     }
 
+    /// Creates a board with the given pieces, without castling rights and en passant square
+    pub(crate) fn from_pieces(
+        pieces: impl IntoIterator<Item = (Piece, Square)>,
+        side_to_move: Color,
+    ) -> Board {
+        let mut board = Board::empty();
+        for (piece, square) in pieces {
+            board.update_bb(piece, square, true);
+        }
+        board.side_to_move = side_to_move;
+        board.ply_count = 1 + side_to_move as usize;
+        board.current_state.zobrist = ZOBRIST.calculate_zobrist_key(&board);
+        board
+    }
+
     pub fn cur_state(&self) -> &BoardState {
         &self.current_state
     }
@@ -92,6 +128,26 @@ impl Board {
         self.side_to_move
     }
 
+    /// Checks if the board is played as Chess960, which changes the notation of castling moves
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    /// The rules played on the board
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Changes the rules played on the board, checks counted by the previous variant are kept
+    /// as they are part of the zobrist key
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+
     /// returns the color on the given square if occupied
     pub fn get_sq_color(&self, square: Square) -> Option<Color> {
         if self.bb_occupied[0].is_occupied(square) {
@@ -126,6 +182,49 @@ impl Board {
         if piece.ptype().is_sliding_piece() {
             self.bb_sliders[piece.color()].update(square, set);
         }
+
+        let value = piece_square_value(piece, square);
+        let material = NON_PAWN_MATERIAL[piece.ptype()];
+        if set {
+            self.psqt_score[piece.color()] += value;
+            self.non_pawn_material[piece.color()] += material;
+        } else {
+            self.psqt_score[piece.color()] -= value;
+            self.non_pawn_material[piece.color()] -= material;
+        }
+    }
+
+    /// Material and piece square table score of the color with the compiled in weights,
+    /// updated incrementally with every piece change
+    pub fn psqt_score(&self, color: Color) -> W {
+        self.psqt_score[color]
+    }
+
+    /// Material of the knights, bishops, rooks and queens of the color,
+    /// updated incrementally with every piece change
+    pub fn non_pawn_material(&self, color: Color) -> Eval {
+        self.non_pawn_material[color]
+    }
+
+    /// Checks the incrementally updated scores and zobrist key against a full recalculation from the bitboards
+    fn incremental_state_valid(&self) -> bool {
+        if self.current_state.zobrist != ZOBRIST.calculate_zobrist_key(self) {
+            return false;
+        }
+
+        [Color::White, Color::Black].into_iter().all(|color| {
+            let mut psqt_score = W::ZERO;
+            let mut non_pawn_material = 0;
+            for piece_type in PieceType::iter() {
+                let piece = piece_type.as_colored_piece(color);
+                for sq in self.get_piece_positions(piece) {
+                    psqt_score += piece_square_value(piece, sq);
+                    non_pawn_material += NON_PAWN_MATERIAL[piece_type];
+                }
+            }
+            psqt_score == self.psqt_score[color]
+                && non_pawn_material == self.non_pawn_material[color]
+        })
     }
 
     pub fn get_bb_pieces(&self) -> [[BitBoard; 6]; 2] {
@@ -177,7 +276,7 @@ impl Board {
         }
 
         //if no sliding pieces are available, we won't need to check for attacks
-        if *self.bb_sliders[self.side_to_move.opposite()] != 0 {
+        if *self.bb_sliders[color] != 0 {
             let attacks = attacks_bishop(square, *enemy, *ally)
                 & (*bb[PieceType::Bishop] | *bb[PieceType::Queen]);
             if attacks != 0 {
@@ -193,7 +292,11 @@ impl Board {
         false
     }
 
+    /// Checks if the king of the side to move is attacked, kings of variants without a royal king are never in check
     pub fn in_check(&self) -> bool {
+        if !self.variant.has_royal_king() {
+            return false;
+        }
         self.sq_attacked(
             self.get_king_pos(self.side_to_move),
             self.side_to_move.opposite(),
@@ -216,6 +319,8 @@ impl Board {
             None,
             self.current_state.castling_rights,
             None,
+            self.current_state.checks,
+            self.current_state.pockets,
         );
         self.previous_states.push(self.current_state.clone());
         self.current_state = new_state;
@@ -231,20 +336,28 @@ impl Board {
         Ok(())
     }
 
-    pub fn make_move(&mut self, mov: &Move, in_search: bool, validate: bool) -> Result<(), ()> {
+    pub fn make_move(
+        &mut self,
+        mov: &Move,
+        in_search: bool,
+        validate: bool,
+    ) -> Result<(), MoveError> {
         if mov.is_null() {
             self.make_null_move();
             return Ok(());
         }
 
         if validate {
-            let moves = MoveGeneration::generate_legal_moves(self);
-            if !moves.has(mov) {
-                error!("Tried playing an invalid move");
-                error!("Legal moves: {}", moves);
-                error!("The illegal move: {}", mov);
-                return Err(());
-            }
+            self.validate_move(mov)?;
+        }
+
+        if let Some(drop_type) = mov.drop_type() {
+            self.make_drop(drop_type, mov.dest(), in_search);
+            debug_assert!(
+                self.incremental_state_valid(),
+                "Incremental state out of sync after {mov:?}"
+            );
+            return Ok(());
         }
 
         let source = mov.source();
@@ -254,58 +367,93 @@ impl Board {
         let cur_state = self.current_state.clone();
         let mut new_zobrist = cur_state.zobrist;
         let mut new_castle_rights = cur_state.castling_rights.clone();
+        let mut new_pockets = cur_state.pockets;
 
         let source_piece = self.get_sq_piece(source).unwrap();
         let move_color = source_piece.color();
         let dest_piece = if is_en_passant {
             Some(PieceType::Pawn.as_colored_piece(move_color.opposite()))
+        } else if move_flag.is_castle() {
+            //in Chess960 the king can move to the square of the castling rook
+            None
         } else {
             self.get_sq_piece(dest)
         };
 
         //handling captures
         if let Some(dest_piece) = dest_piece {
-            if is_en_passant {
-                let dest = dest - move_color.perspective() * 8;
-                self.update_bb(dest_piece, dest, false);
+            let captured_sq = if is_en_passant {
+                dest - move_color.perspective() * 8
             } else {
-                self.update_bb(dest_piece, dest, false);
-                new_zobrist ^= ZOBRIST.get_rn_piece(dest_piece.ptype(), dest);
+                dest
+            };
+            self.update_bb(dest_piece, captured_sq, false);
+            new_zobrist ^= ZOBRIST.get_rn_piece(dest_piece, captured_sq);
+
+            //captured pieces go into the pocket of the capturing color, promoted pieces as pawns
+            if self.variant.has_drops() {
+                let pocket_type = if new_pockets.is_promoted(captured_sq) {
+                    PieceType::Pawn
+                } else {
+                    dest_piece.ptype()
+                };
+                new_pockets.set_promoted(captured_sq, false);
+                let count = new_pockets.count(move_color, pocket_type);
+                new_zobrist ^= ZOBRIST.get_rn_pocket(move_color, pocket_type, count);
+                new_zobrist ^= ZOBRIST.get_rn_pocket(move_color, pocket_type, count + 1);
+                new_pockets.add(move_color, pocket_type);
             }
         }
 
         //handling promotions
         if move_flag.is_promotion() {
             self.update_bb(source_piece, source, false);
-            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece.ptype(), dest);
+            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece, source);
 
             let promoted_piece = move_flag
                 .promotion_type()
                 .expect("Move is flagged as promotion so it must have a promotion type")
                 .as_colored_piece(move_color);
             self.update_bb(promoted_piece, dest, true);
-            new_zobrist ^= ZOBRIST.get_rn_piece(promoted_piece.ptype(), dest);
+            new_zobrist ^= ZOBRIST.get_rn_piece(promoted_piece, dest);
+        } else if let Some(castle_type) = move_flag.castle_side() {
+            //remove king and rook before placing them, in Chess960 the squares can overlap
+            let (rook_source, rook_dest) = cur_state
+                .castling_rights
+                .rook_positions(move_color, castle_type);
+            let rook = PieceType::Rook.as_colored_piece(move_color);
+            self.update_bb(source_piece, source, false);
+            self.update_bb(rook, rook_source, false);
+            self.update_bb(source_piece, dest, true);
+            self.update_bb(rook, rook_dest, true);
+            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece, source);
+            new_zobrist ^= ZOBRIST.get_rn_piece(rook, rook_source);
+            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece, dest);
+            new_zobrist ^= ZOBRIST.get_rn_piece(rook, rook_dest);
         } else {
             //move the source piece to the destination (if promotion the source piece is already removed and promoted)
             self.update_bb(source_piece, source, false);
-            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece.ptype(), source);
+            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece, source);
             self.update_bb(source_piece, dest, true);
-            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece.ptype(), dest);
+            new_zobrist ^= ZOBRIST.get_rn_piece(source_piece, dest);
+        }
+
+        //promoted pieces are tracked as they go into the pocket as pawns when captured
+        if self.variant.has_drops() {
+            if move_flag.is_promotion() {
+                new_pockets.set_promoted(dest, true);
+            } else if new_pockets.is_promoted(source) {
+                new_pockets.set_promoted(source, false);
+                new_pockets.set_promoted(dest, true);
+            }
+            new_zobrist ^=
+                ZOBRIST.get_rn_promoted(cur_state.pockets.promoted() ^ new_pockets.promoted());
         }
 
         //handling king
         if source_piece.ptype() == PieceType::King {
             new_castle_rights.update(move_color, CastleType::KingSide, false);
             new_castle_rights.update(move_color, CastleType::QueenSide, false);
-
-            if let Some(castle_type) = move_flag.castle_side() {
-                let (rook_source, rook_dest) = castle_type.get_rook_positions(move_color);
-                let rook = PieceType::Rook.as_colored_piece(move_color);
-                self.update_bb(rook, rook_source, false);
-                new_zobrist ^= ZOBRIST.get_rn_piece(rook.ptype(), rook_source);
-                self.update_bb(rook, rook_dest, true);
-                new_zobrist ^= ZOBRIST.get_rn_piece(rook.ptype(), rook_dest);
-            }
         }
 
         //double pawn push
@@ -319,17 +467,14 @@ impl Board {
         //update castle rights only if some were available
         if cur_state.castling_rights.as_u8() != 0 {
             //any piece move from or to a rooks starting position will remove the castle rights
-            if source == Square::A1 || dest == Square::A1 {
-                new_castle_rights.update(Color::White, CastleType::QueenSide, false);
-            }
-            if source == Square::H1 || dest == Square::H1 {
-                new_castle_rights.update(Color::White, CastleType::KingSide, false);
-            }
-            if source == Square::A8 || dest == Square::A8 {
-                new_castle_rights.update(Color::Black, CastleType::QueenSide, false);
-            }
-            if source == Square::H8 || dest == Square::H8 {
-                new_castle_rights.update(Color::Black, CastleType::KingSide, false);
+            for color in [Color::White, Color::Black] {
+                for castle_type in [CastleType::KingSide, CastleType::QueenSide] {
+                    let (rook_source, _) =
+                        cur_state.castling_rights.rook_positions(color, castle_type);
+                    if source == rook_source || dest == rook_source {
+                        new_castle_rights.update(color, castle_type, false);
+                    }
+                }
             }
         }
 
@@ -357,6 +502,8 @@ impl Board {
             new_en_passant,
             new_castle_rights,
             dest_piece.map(|p| p.ptype()),
+            cur_state.checks,
+            new_pockets,
         );
         if !in_search {
             self.repetition_history.push(cur_state.zobrist);
@@ -365,9 +512,56 @@ impl Board {
         self.ply_count += 1;
         self.side_to_move = self.side_to_move.opposite();
 
+        if self.variant.counts_checks() && self.in_check() {
+            let checks = &mut self.current_state.checks[move_color];
+            self.current_state.zobrist ^= ZOBRIST.get_rn_checks(move_color, *checks);
+            *checks += 1;
+            self.current_state.zobrist ^= ZOBRIST.get_rn_checks(move_color, *checks);
+        }
+
+        debug_assert!(
+            self.incremental_state_valid(),
+            "Incremental state out of sync after {mov:?}"
+        );
         Ok(())
     }
 
+    /// Drops a piece from the pocket of the side to move onto the empty destination square
+    fn make_drop(&mut self, drop_type: PieceType, dest: Square, in_search: bool) {
+        let cur_state = self.current_state.clone();
+        let color = self.side_to_move;
+        let piece = drop_type.as_colored_piece(color);
+        let mut new_pockets = cur_state.pockets;
+        let mut new_zobrist = cur_state.zobrist;
+
+        let count = new_pockets.count(color, drop_type);
+        new_zobrist ^= ZOBRIST.get_rn_pocket(color, drop_type, count);
+        new_zobrist ^= ZOBRIST.get_rn_pocket(color, drop_type, count - 1);
+        new_pockets.remove(color, drop_type);
+
+        self.update_bb(piece, dest, true);
+        new_zobrist ^= ZOBRIST.get_rn_piece(piece, dest);
+        new_zobrist ^= ZOBRIST.get_rn_side_to_move();
+        new_zobrist ^= ZOBRIST.get_rn_en_passant(cur_state.en_passant.as_ref());
+        new_zobrist ^= ZOBRIST.get_rn_en_passant(None);
+
+        self.current_state = BoardState::new(
+            new_zobrist,
+            cur_state.ply_clock + 1,
+            None,
+            cur_state.castling_rights,
+            None,
+            cur_state.checks,
+            new_pockets,
+        );
+        if !in_search {
+            self.repetition_history.push(cur_state.zobrist);
+        }
+        self.previous_states.push(cur_state);
+        self.ply_count += 1;
+        self.side_to_move = self.side_to_move.opposite();
+    }
+
     pub fn undo_move(
         &mut self,
         mov: &Move,
@@ -390,43 +584,56 @@ impl Board {
         let move_flag = mov.flag();
         let color = self.side_to_move;
 
-        let moved_piece = self
-            .get_sq_piece(dest)
-            .ok_or(UndoMoveError::InvalidLastMove)?;
-
-        self.update_bb(moved_piece, dest, false);
-        self.update_bb(moved_piece, source, true);
-
-        //undo promotions ERROR TODO
-        if let Some(promotion_type) = move_flag.promotion_type() {
-            let promoted_piece = promotion_type.as_colored_piece(color);
-            self.update_bb(promoted_piece, source, false);
-            self.update_bb(PieceType::Pawn.as_colored_piece(color), source, true);
-        }
-
-        //undo captures and en passant
-        if let Some(captured_piece) = captured_piece {
-            let captured_piece = captured_piece.as_colored_piece(color.opposite());
-            if move_flag.is_en_passant() {
-                let captured_sq = dest - color.perspective() * 8;
-                self.update_bb(captured_piece, captured_sq, true);
-            } else {
-                self.update_bb(captured_piece, dest, true);
-            }
-        }
-
-        //undo castling
-        if let Some(castle_type) = move_flag.castle_side() {
-            let (rook_source, rook_dest) = castle_type.get_rook_positions(color);
+        if let Some(drop_type) = mov.drop_type() {
+            //the piece goes back into the pocket with the restored state
+            self.update_bb(drop_type.as_colored_piece(color), dest, false);
+        } else if let Some(castle_type) = move_flag.castle_side() {
+            //undo castling, remove king and rook before placing them as the squares can overlap in Chess960
+            let (rook_source, rook_dest) = self
+                .current_state
+                .castling_rights
+                .rook_positions(color, castle_type);
+            let king = PieceType::King.as_colored_piece(color);
             let rook = PieceType::Rook.as_colored_piece(color);
+            self.update_bb(king, dest, false);
             self.update_bb(rook, rook_dest, false);
+            self.update_bb(king, source, true);
             self.update_bb(rook, rook_source, true);
+        } else {
+            let moved_piece = self
+                .get_sq_piece(dest)
+                .ok_or(UndoMoveError::InvalidLastMove)?;
+
+            self.update_bb(moved_piece, dest, false);
+            self.update_bb(moved_piece, source, true);
+
+            //undo promotions ERROR TODO
+            if let Some(promotion_type) = move_flag.promotion_type() {
+                let promoted_piece = promotion_type.as_colored_piece(color);
+                self.update_bb(promoted_piece, source, false);
+                self.update_bb(PieceType::Pawn.as_colored_piece(color), source, true);
+            }
+
+            //undo captures and en passant
+            if let Some(captured_piece) = captured_piece {
+                let captured_piece = captured_piece.as_colored_piece(color.opposite());
+                if move_flag.is_en_passant() {
+                    let captured_sq = dest - color.perspective() * 8;
+                    self.update_bb(captured_piece, captured_sq, true);
+                } else {
+                    self.update_bb(captured_piece, dest, true);
+                }
+            }
         }
 
         if !in_search {
             self.repetition_history.pop();
         }
 
+        debug_assert!(
+            self.incremental_state_valid(),
+            "Incremental state out of sync after undoing {mov:?}"
+        );
         Ok(())
     }
 
@@ -435,8 +642,28 @@ impl Board {
         fen_utility::FENUtility::from_fen(fen_string)
     }
 
+    /// Parses a FEN string of a position of the variant.
+    /// Three-check and Crazyhouse positions are also recognized by their FEN without the variant
+    pub fn from_fen_variant(fen_string: &str, variant: Variant) -> Result<Board, FENError> {
+        fen_utility::FENUtility::from_fen_variant(fen_string, variant)
+    }
+
+    /// Parses a FEN string of a position of the variant and checks if the position is legal,
+    /// positions of users should be parsed strictly as illegal positions can crash the search
+    pub fn from_fen_strict(fen_string: &str, variant: Variant) -> Result<Board, FENError> {
+        let board = fen_utility::FENUtility::from_fen_variant(fen_string, variant)?;
+        board.validate()?;
+        Ok(board)
+    }
+
     /// Returns a FEN string representation of the board.
+    /// Castle rights of Chess960 positions are written in X-FEN.
     pub fn to_fen(&self) -> String {
         fen_utility::FENUtility::to_fen(self)
     }
+
+    /// Returns a FEN string representation of the board with the castle rights as files of the rooks (Shredder-FEN)
+    pub fn to_shredder_fen(&self) -> String {
+        fen_utility::FENUtility::to_shredder_fen(self)
+    }
 }
@@ -23,8 +23,3 @@ const fn calculate_king_attack_pattern() -> [u64; 64] {
 }
 
 pub const ATTACK_PATTERN_KING: [u64; 64] = calculate_king_attack_pattern();
-
-pub const CASTLE_FREE_SQUARES: [[u64; 2]; 2] =
-    [[0x60, 0xe], [0x6000000000000000, 0xe00000000000000]];
-pub const CASTLE_ATTACK_FREE_SQUARES: [[u64; 2]; 2] =
-    [[0x60, 0xc], [0x6000000000000000, 0xc00000000000000]];
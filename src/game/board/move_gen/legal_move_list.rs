@@ -1,11 +1,12 @@
 use crate::game::{
     bit_manipulation::drop_lsb, board::bit_board::BitBoard, move_notation::MoveFlag, Color, Move,
-    Square,
+    PieceType, Square,
 };
 
 use super::helper::MoveGenerationMasks;
 
-const MAX_NUMBER_OF_MOVES_PER_POSITION: usize = 218;
+//218 is the maximum in standard chess, drops allow more moves
+const MAX_NUMBER_OF_MOVES_PER_POSITION: usize = 512;
 type MoveListArray = [Move; MAX_NUMBER_OF_MOVES_PER_POSITION];
 
 #[derive(Debug)]
@@ -82,6 +83,14 @@ impl LegalMoveList {
         });
     }
 
+    #[inline(always)]
+    /// Adds drops of the piece type to the destination squares
+    pub(super) fn create_and_add_drops(&mut self, piece_type: PieceType, dest: u64) {
+        BitBoard::from(dest).get_occupied().for_each(|dest| {
+            self.add_move(Move::new_drop(piece_type, dest));
+        });
+    }
+
     #[inline(always)]
     pub fn is_checkmate(&self) -> bool {
         self.count == 0 && self.masks.in_check
@@ -8,10 +8,7 @@ pub use legal_move_list::LegalMoveList;
 use magic::get_rook_moves;
 
 use crate::game::{
-    castle_rights::{CastleRights, CastleType},
-    color::Color,
-    move_notation::MoveFlag,
-    piece_type::PieceType,
+    castle_rights::CastleType, color::Color, move_notation::MoveFlag, piece_type::PieceType,
     square::Square,
 };
 
@@ -31,6 +28,10 @@ impl MoveGeneration {
     }
 
     fn generate_moves(board: &Board, captures_only: bool) -> LegalMoveList {
+        if board.variant().forced_captures() {
+            return Self::generate_forced_capture_moves(board, captures_only);
+        }
+
         let mut legal_moves = LegalMoveList::default();
 
         let data = MoveGenerationData::from_board(&board);
@@ -230,50 +231,144 @@ impl MoveGeneration {
                 }
                 PieceType::King => {
                     if !masks.in_check {
-                        if board
-                            .cur_state()
-                            .castling_rights
-                            .has(data.color, CastleType::KingSide)
-                        {
-                            legal_moves.create_and_add_moves(
-                                sq,
-                                moves_king_castle_king_side(
+                        for (castle_type, flag) in [
+                            (CastleType::KingSide, MoveFlag::KingSideCastle),
+                            (CastleType::QueenSide, MoveFlag::QueenSideCastle),
+                        ] {
+                            if board
+                                .cur_state()
+                                .castling_rights
+                                .has(data.color, castle_type)
+                            {
+                                legal_moves.create_and_add_moves(
                                     sq,
-                                    data.occupied,
-                                    masks.king_danger,
-                                    data.color,
-                                ),
-                                MoveFlag::KingSideCastle,
-                                move_type_mask,
-                            );
-                        }
-                        if board
-                            .cur_state()
-                            .castling_rights
-                            .has(data.color, CastleType::QueenSide)
-                        {
-                            legal_moves.create_and_add_moves(
-                                sq,
-                                moves_king_castle_queen_side(
-                                    sq,
-                                    data.occupied,
-                                    masks.king_danger,
-                                    data.color,
-                                ),
-                                MoveFlag::QueenSideCastle,
-                                move_type_mask,
-                            );
+                                    moves_king_castle(
+                                        board,
+                                        sq,
+                                        castle_type,
+                                        data.occupied,
+                                        masks.king_danger,
+                                        data.color,
+                                    ),
+                                    flag,
+                                    move_type_mask,
+                                );
+                            }
                         }
                     }
                 }
             }
         }
 
+        //pieces of the pocket can be dropped on empty squares, if in check only to block the check
+        if board.variant().has_drops() && !captures_only {
+            let drop_squares = masks.push_mask & !data.occupied;
+            for piece_type in board.cur_state().pockets.droppable(data.color) {
+                let squares = match piece_type {
+                    PieceType::Pawn => drop_squares & !BACK_RANKS,
+                    _ => drop_squares,
+                };
+                legal_moves.create_and_add_drops(piece_type, squares);
+            }
+        }
+
         legal_moves.set_masks(masks);
         legal_moves
     }
 }
 
+/// First and eighth rank, pawns can't be dropped there
+pub(super) const BACK_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+impl MoveGeneration {
+    /// Moves of variants without a royal king like Antichess. Every pseudo legal move is legal as there is no check,
+    /// castling isn't possible and pawns can also promote to a king. If a capture is possible only captures are generated.
+    fn generate_forced_capture_moves(board: &Board, captures_only: bool) -> LegalMoveList {
+        let mut moves = LegalMoveList::default();
+        let color = board.side_to_move;
+        let ally = *board.bb_occupied[color];
+        let enemy = *board.bb_occupied[color.opposite()];
+        let masks = MoveGenerationMasks {
+            enemy,
+            push_mask: u64::MAX,
+            capture_mask: u64::MAX,
+            push_capture_mask: u64::MAX,
+            ..Default::default()
+        };
+
+        let en_passant = board.cur_state().en_passant.map_or(0, |sq| sq.to_mask());
+        Self::add_pseudo_legal_moves(board, &mut moves, enemy, en_passant);
+        if moves.is_empty() && !captures_only {
+            Self::add_pseudo_legal_moves(board, &mut moves, !(ally | enemy), 0);
+        }
+
+        moves.set_masks(masks);
+        moves
+    }
+
+    /// Moves of the side to move without regard to checks, castling and drops.
+    /// Used to tell why a move is illegal, pawns can always promote to a king.
+    pub fn generate_pseudo_legal_moves(board: &Board) -> LegalMoveList {
+        let mut moves = LegalMoveList::default();
+        let en_passant = board.cur_state().en_passant.map_or(0, |sq| sq.to_mask());
+        Self::add_pseudo_legal_moves(board, &mut moves, u64::MAX, en_passant);
+        moves
+    }
+
+    /// Adds the moves of all pieces of the side to move to squares of the mask,
+    /// pawns are only moved diagonally to en passant squares of the mask
+    fn add_pseudo_legal_moves(
+        board: &Board,
+        moves: &mut LegalMoveList,
+        mask: u64,
+        en_passant: u64,
+    ) {
+        let color = board.side_to_move;
+        let ally = *board.bb_occupied[color];
+        let enemy = *board.bb_occupied[color.opposite()];
+        let occupied = ally | enemy;
+        let promotion_rank = 0xFF << (color.promotion_rank() * 8);
+
+        for sq in BitBoard::from(ally).get_occupied() {
+            let piece_type = board
+                .get_sq_piece_variation(sq)
+                .expect("Ally square must have a piece");
+            let targets = match piece_type {
+                PieceType::Pawn => {
+                    let pawn_moves =
+                        moves_pawn(sq, enemy, ally, color) | attacks_pawn(sq, enemy, ally, color);
+                    moves.create_and_add_pawn_moves(sq, pawn_moves, color, mask);
+                    moves.create_and_add_moves(
+                        sq,
+                        pawn_moves & promotion_rank,
+                        MoveFlag::KingPromotion,
+                        mask,
+                    );
+                    moves.create_and_add_moves(
+                        sq,
+                        moves_pawn_double_push(sq, occupied, color),
+                        MoveFlag::DoublePawnPush,
+                        mask,
+                    );
+                    moves.create_and_add_moves(
+                        sq,
+                        attacks_pawn(sq, en_passant, ally, color),
+                        MoveFlag::EnPassant,
+                        u64::MAX,
+                    );
+                    continue;
+                }
+                PieceType::Knight => attacks_knight(sq, ally),
+                PieceType::Bishop => attacks_bishop(sq, enemy, ally),
+                PieceType::Rook => attacks_rook(sq, enemy, ally),
+                PieceType::Queen => attacks_queen(sq, enemy, ally),
+                PieceType::King => attacks_king(sq, ally),
+            };
+            moves.create_and_add_moves(sq, targets, MoveFlag::Normal, mask);
+        }
+    }
+}
+
 #[inline(always)]
 pub fn attacks_rook(sq: Square, enemy: u64, ally: u64) -> u64 {
     (*magic::get_rook_moves(sq, enemy | ally)) & !ally
@@ -300,44 +395,56 @@ pub fn attacks_king(sq: Square, ally: u64) -> u64 {
     attacks::ATTACK_PATTERN_KING[sq] & !ally
 }
 
+/// Castling move of the king as mask of the dest square of the king.
+/// The squares between the king and its dest square and between the rook and its dest square must be empty,
+/// except for the castling king and rook. The king may not pass or land on an attacked square.
+/// Works for the arbitrary king and rook start files of Chess960.
 #[inline(always)]
-fn moves_king_castle_queen_side(sq: Square, occupied: u64, attacked: u64, color: Color) -> u64 {
-    if sq != CastleType::KING_SOURCE[color] {
+fn moves_king_castle(
+    board: &Board,
+    sq: Square,
+    castle_type: CastleType,
+    occupied: u64,
+    attacked: u64,
+    color: Color,
+) -> u64 {
+    let (rook_sq, rook_dest) = board
+        .cur_state()
+        .castling_rights
+        .rook_positions(color, castle_type);
+    let king_dest = castle_type.king_dest(color);
+    if sq.rank() != king_dest.rank()
+        || *board.get_bb_pieces()[color][PieceType::Rook] & rook_sq.to_mask() == 0
+    {
         return 0;
     }
 
-    let queen_side_free = attacks::CASTLE_FREE_SQUARES[color][CastleType::QueenSide];
-    let queen_side_attack_free = attacks::CASTLE_ATTACK_FREE_SQUARES[color][CastleType::QueenSide];
-
-    let queen_side_possible =
-        (queen_side_free & occupied) == 0 && (queen_side_attack_free & attacked) == 0;
-
-    if queen_side_possible {
-        CastleType::KING_DEST[CastleRights::to_index(color, CastleType::QueenSide) as usize]
-            .to_mask()
-    } else {
-        0
+    let castling_pieces = sq.to_mask() | rook_sq.to_mask();
+    let must_be_free =
+        (rank_span(sq, king_dest) | rank_span(rook_sq, rook_dest)) & !castling_pieces;
+    if occupied & must_be_free != 0 || attacked & rank_span(sq, king_dest) != 0 {
+        return 0;
     }
-}
 
-#[inline(always)]
-fn moves_king_castle_king_side(sq: Square, occupied: u64, attacked: u64, color: Color) -> u64 {
-    if sq != CastleType::KING_SOURCE[color] {
+    //the castling rook can shield the dest square of the king from a rook on the back rank in Chess960
+    let enemy_rooks = *board.get_bb_rook_slider(color.opposite());
+    let occupied_after = (occupied & !castling_pieces) | rook_dest.to_mask();
+    if *get_rook_moves(king_dest, occupied_after) & enemy_rooks != 0 {
         return 0;
     }
 
-    let king_side_free = attacks::CASTLE_FREE_SQUARES[color][CastleType::KingSide];
-    let king_side_attack_free = attacks::CASTLE_ATTACK_FREE_SQUARES[color][CastleType::KingSide];
-
-    let king_side_possible =
-        (king_side_free & occupied) == 0 && (king_side_attack_free & attacked) == 0;
+    king_dest.to_mask()
+}
 
-    if king_side_possible {
-        CastleType::KING_DEST[CastleRights::to_index(color, CastleType::KingSide) as usize]
-            .to_mask()
+/// Mask of all squares from a to b on the same rank, including both squares
+#[inline(always)]
+fn rank_span(a: Square, b: Square) -> u64 {
+    let (low, high) = if a.square_value() < b.square_value() {
+        (a.square_value(), b.square_value())
     } else {
-        0
-    }
+        (b.square_value(), a.square_value())
+    };
+    ((1u64 << high) << 1).wrapping_sub(1u64 << low)
 }
 
 #[inline(always)]
@@ -79,3 +79,166 @@ fn initial_position_perft() {
         assert_eq!(count, *results.get(depth as usize).unwrap());
     }
 }
+
+#[test]
+fn chess960_position_perft() {
+    use crate::{game::Board, perft};
+
+    //positions and node counts of the Chess960 perft suite
+    let tests = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            [21, 528, 12189],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            [21, 807, 18002],
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            [20, 479, 10471],
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            [22, 593, 13440],
+        ),
+        (
+            "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9",
+            [29, 502, 14569],
+        ),
+        (
+            "rbbqn1kr/pp2p1pp/6n1/2pp1p2/2P4P/P7/BP1PPPP1/R1BQNNKR w HAha - 0 9",
+            [27, 916, 25798],
+        ),
+    ];
+
+    for (fen, results) in tests {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(board.is_chess960());
+        assert_eq!(board.to_shredder_fen(), fen);
+
+        for (depth, expected) in results.into_iter().enumerate() {
+            let count: u64 = perft(depth as u8 + 1, &mut board, false)
+                .iter()
+                .map(|(_, counter)| counter.count)
+                .sum();
+            assert_eq!(count, expected, "{fen} depth {}", depth + 1);
+        }
+    }
+}
+
+#[test]
+fn crazyhouse_perft() {
+    use crate::{
+        game::{parser::png_parser::PGNParser, Board, Move, Variant},
+        perft,
+    };
+
+    //drops are only possible after the first capture in the third ply,
+    //the middle game has drops of every piece, castling, en passant and promoted pieces of both colors
+    for (fen, counts) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            &[20, 400, 8902, 197281, 4888832][..],
+        ),
+        (
+            "r3k2r/ppp2ppp/2n1b2q~/3pP3/1b1P4/2N2N2/PPPB~1PPP/R3K2R[QRBNPqrbnp] w KQkq d6 0 12",
+            &[203, 39495, 6156706][..],
+        ),
+    ] {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.variant(), Variant::Crazyhouse);
+        assert_eq!(board.to_fen(), fen);
+        for (depth, expected) in counts.iter().enumerate() {
+            let count: u64 = perft(depth as u8 + 1, &mut board, true)
+                .iter()
+                .map(|(_, counter)| counter.count)
+                .sum();
+            assert_eq!(count, *expected, "{fen} depth {}", depth + 1);
+        }
+    }
+
+    //captured promoted pieces go into the pocket as pawns
+    let fen = "1Q~2k3/8/8/8/8/8/8/4K3[r] b - - 0 1";
+    let mut board = Board::from_fen(fen).unwrap();
+    assert_eq!(board.to_fen(), fen);
+    let mov = Move::from_uci_notation("R@d8", &board).unwrap();
+    assert_eq!(PGNParser::move_as_san(&mov, &mut board).unwrap(), "R@d8");
+    assert_eq!(PGNParser::move_from_san("R@d8", &board).unwrap(), mov);
+    board.make_move(&mov, false, true).unwrap();
+    board
+        .make_move(
+            &Move::from_uci_notation("b8d8", &board).unwrap(),
+            false,
+            true,
+        )
+        .unwrap();
+    board
+        .make_move(
+            &Move::from_uci_notation("e8d8", &board).unwrap(),
+            false,
+            true,
+        )
+        .unwrap();
+    assert_eq!(board.to_fen(), "3k4/8/8/8/8/8/8/4K3[Rp] w - - 0 3");
+
+    //promoted pieces are part of the key as they go into the pocket as pawns
+    let key = |fen: &str| Board::from_fen(fen).unwrap().cur_state().zobrist;
+    assert_ne!(
+        key("1Q~2k3/8/8/8/8/8/8/4K3[r] b - - 0 1"),
+        key("1Q2k3/8/8/8/8/8/8/4K3[r] b - - 0 1")
+    );
+    for (fen, uci) in [
+        ("4k3/8/8/8/8/8/8/1Q~2K3[] w - - 0 1", "b1b5"),
+        ("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1", "a7a8q"),
+    ] {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mov = Move::from_uci_notation(uci, &board).unwrap();
+        board.make_move(&mov, false, true).unwrap();
+        assert_eq!(board.cur_state().zobrist, key(&board.to_fen()), "{uci}");
+    }
+}
+
+#[test]
+fn antichess_perft() {
+    use crate::{
+        game::{Board, Color, GameResult, Move, MoveGeneration, Variant},
+        perft,
+    };
+
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+    let mut board = Board::from_fen_variant(fen, Variant::Antichess).unwrap();
+    for (depth, expected) in [20, 400, 8067, 153299].into_iter().enumerate() {
+        let count: u64 = perft(depth as u8 + 1, &mut board, false)
+            .iter()
+            .map(|(_, counter)| counter.count)
+            .sum();
+        assert_eq!(count, expected, "depth {}", depth + 1);
+    }
+
+    //the capture is compulsory, promoting to a king is allowed and losing the last piece wins
+    let fen = "8/1P6/8/8/8/8/5k2/4R3 b - - 0 1";
+    let mut board = Board::from_fen_variant(fen, Variant::Antichess).unwrap();
+    let moves = MoveGeneration::generate_legal_moves(&board);
+    assert_eq!(moves.len(), 1);
+    let mov = Move::from_uci_notation("f2e1", &board).unwrap();
+    board.make_move(&mov, false, true).unwrap();
+    let moves = MoveGeneration::generate_legal_moves(&board);
+    assert_eq!(moves.len(), 5);
+    let mov = Move::from_uci_notation("b7b8k", &board).unwrap();
+    assert!(moves.has(&mov));
+    board.make_move(&mov, false, true).unwrap();
+    assert_eq!(
+        GameResult::get_game_result(&board, None),
+        GameResult::InProgress
+    );
+
+    let fen = "8/8/8/8/8/8/1p6/R7 b - - 0 1";
+    let mut board = Board::from_fen_variant(fen, Variant::Antichess).unwrap();
+    let mov = Move::from_uci_notation("b2a1q", &board).unwrap();
+    board.make_move(&mov, false, true).unwrap();
+    assert_eq!(
+        GameResult::get_game_result(&board, None),
+        GameResult::VariantEnd(Color::Black)
+    );
+}
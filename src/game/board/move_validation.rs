@@ -0,0 +1,133 @@
+use crate::game::{
+    move_notation::{Move, MoveFlag},
+    piece_type::PieceType,
+};
+
+use super::{
+    board_error::MoveError,
+    move_gen::{LegalMoveList, MoveGeneration, BACK_RANKS},
+    Board,
+};
+
+impl Board {
+    /// Checks if the move is legal in the current position.
+    /// Returns the reason why the move can't be played otherwise.
+    pub fn validate_move(&self, mov: &Move) -> Result<(), MoveError> {
+        let legal_moves = MoveGeneration::generate_legal_moves(self);
+        if legal_moves.has(mov) {
+            return Ok(());
+        }
+        Err(self.illegal_move_reason(mov))
+    }
+
+    /// Reason why the move isn't in the legal moves of the position,
+    /// moves a piece could do without regard to the king are rejected by the rules of the variant
+    fn illegal_move_reason(&self, mov: &Move) -> MoveError {
+        let color = self.side_to_move;
+        let dest = mov.dest();
+
+        if let Some(drop_type) = mov.drop_type() {
+            if !self.variant.has_drops() {
+                return MoveError::InvalidDrop(drop_type.as_colored_piece(color), dest);
+            }
+            if self.cur_state().pockets.count(color, drop_type) == 0 {
+                return MoveError::EmptyPocket(drop_type, color);
+            }
+            if self.get_sq_piece(dest).is_some()
+                || (drop_type == PieceType::Pawn && dest.to_mask() & BACK_RANKS != 0)
+            {
+                return MoveError::InvalidDrop(drop_type.as_colored_piece(color), dest);
+            }
+            return MoveError::KingInCheck;
+        }
+
+        let source = mov.source();
+        let Some(piece) = self.get_sq_piece(source) else {
+            return MoveError::EmptySource(source);
+        };
+        if piece.color() != color {
+            return MoveError::WrongColor(piece.color());
+        }
+        if source == dest {
+            return MoveError::SameSquare;
+        }
+
+        if let Some(castle_type) = mov.flag().castle_side() {
+            if !self.cur_state().castling_rights.has(color, castle_type) {
+                return MoveError::MissingCastleRight(castle_type, color);
+            }
+            return MoveError::CastleBlocked(castle_type);
+        }
+
+        if self.variant.has_royal_king()
+            && self
+                .get_sq_piece(dest)
+                .is_some_and(|p| p.ptype() == PieceType::King && p.color() != color)
+        {
+            return MoveError::KingCapture;
+        }
+
+        let pseudo_legal_moves = MoveGeneration::generate_pseudo_legal_moves(self);
+        if !reaches(&pseudo_legal_moves, mov) {
+            return MoveError::InvalidPieceMove(piece, source, dest);
+        }
+        //the piece can reach the square, but the flag doesn't fit the move (e.g. a missing promotion)
+        if !pseudo_legal_moves.has(mov)
+            || (mov.flag() == MoveFlag::KingPromotion && self.variant.has_royal_king())
+        {
+            return MoveError::InvalidMove;
+        }
+
+        if self.variant.forced_captures() {
+            return MoveError::CaptureRequired;
+        }
+        MoveError::KingInCheck
+    }
+}
+
+/// Checks if any of the moves goes from the source to the dest square of the move
+fn reaches(moves: &LegalMoveList, mov: &Move) -> bool {
+    moves
+        .iter()
+        .any(|m| m.source() == mov.source() && m.dest() == mov.dest())
+}
+
+mod test {
+    #[test]
+    fn test_move_errors() {
+        use crate::game::{
+            board::board_error::MoveError, castle_rights::CastleType,
+            parser::png_parser::PGNParser, Board, Color, Move, PieceType, Square, Variant,
+        };
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4r3/R3K2R w K - 0 1").unwrap();
+        let error = |uci: &str| Move::from_uci_notation(uci, &board).unwrap_err();
+        assert!(matches!(error("d2d4"), MoveError::EmptySource(Square::D2)));
+        assert!(matches!(error("e2e1"), MoveError::WrongColor(Color::Black)));
+        assert!(matches!(
+            error("e1c1"),
+            MoveError::MissingCastleRight(CastleType::QueenSide, Color::White)
+        ));
+        assert!(matches!(error("a1b2"), MoveError::InvalidPieceMove(..)));
+        assert!(matches!(error("e1d2"), MoveError::KingInCheck));
+        assert!(matches!(error("e1g1"), MoveError::CastleBlocked(..)));
+        assert!(matches!(error("a1a9"), MoveError::UCINotation(_)));
+
+        let error = |san: &str| PGNParser::move_from_san(san, &board).unwrap_err();
+        assert!(matches!(error("Kd2"), MoveError::KingInCheck));
+        assert!(matches!(error("O-O"), MoveError::CastleBlocked(..)));
+        assert!(matches!(error("Qd4"), MoveError::NoMatchingMove(_)));
+
+        let board =
+            Board::from_fen_variant("8/8/8/8/8/8/4r3/R3K3 w - - 0 1", Variant::Antichess).unwrap();
+        let mov = Move::from_uci_notation("a1a2", &board);
+        assert!(matches!(mov, Err(MoveError::CaptureRequired)));
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3[n] w - - 0 1").unwrap();
+        let mov = Move::new_drop(PieceType::Knight, Square::D4);
+        assert!(matches!(
+            board.validate_move(&mov),
+            Err(MoveError::EmptyPocket(PieceType::Knight, Color::White))
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+use super::bit_board::BitBoard;
+use crate::game::{color::Color, piece_type::PieceType, square::Square};
+
+/// Piece types which can be held in a pocket, kings are never captured
+pub const POCKET_PIECES: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+/// #### Pockets
+/// Pieces captured in Crazyhouse which the capturing color can drop back on the board,
+/// together with the squares of promoted pieces, which go back into the pocket as pawns when captured.
+/// Stored in the board state, so undoing a move restores the pockets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pockets {
+    counts: [[u8; 5]; 2],
+    promoted: u64,
+}
+
+impl Pockets {
+    pub fn count(&self, color: Color, piece_type: PieceType) -> u8 {
+        self.counts[color][piece_type]
+    }
+
+    pub fn add(&mut self, color: Color, piece_type: PieceType) {
+        self.counts[color][piece_type] += 1;
+    }
+
+    pub fn remove(&mut self, color: Color, piece_type: PieceType) {
+        self.counts[color][piece_type] -= 1;
+    }
+
+    /// Checks if neither color holds any piece
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().flatten().all(|count| *count == 0)
+    }
+
+    /// Piece types the color can drop
+    pub fn droppable(&self, color: Color) -> impl Iterator<Item = PieceType> + '_ {
+        POCKET_PIECES
+            .into_iter()
+            .filter(move |piece_type| self.count(color, *piece_type) > 0)
+    }
+
+    pub fn is_promoted(&self, square: Square) -> bool {
+        self.promoted & square.to_mask() != 0
+    }
+
+    pub fn set_promoted(&mut self, square: Square, promoted: bool) {
+        if promoted {
+            self.promoted |= square.to_mask();
+        } else {
+            self.promoted &= !square.to_mask();
+        }
+    }
+
+    /// Squares of the promoted pieces
+    pub fn promoted(&self) -> u64 {
+        self.promoted
+    }
+
+    /// Pockets with the promoted pieces moved to the mapped squares and optionally swapped colors
+    pub(super) fn transform(
+        &self,
+        map_sq: impl Fn(Square) -> Square,
+        swap_colors: bool,
+    ) -> Pockets {
+        let mut counts = self.counts;
+        if swap_colors {
+            counts.swap(0, 1);
+        }
+        let promoted = BitBoard::from(self.promoted)
+            .get_occupied()
+            .fold(0, |promoted, sq| promoted | map_sq(sq).to_mask());
+        Pockets { counts, promoted }
+    }
+
+    /// Parses the content of the pockets as written in the brackets of a fen, e.g. QNpp
+    pub fn from_fen(value: &str) -> Option<Pockets> {
+        let mut pockets = Pockets::default();
+        for c in value.chars() {
+            let piece_type = match c.to_ascii_lowercase() {
+                'p' | 'n' | 'b' | 'r' | 'q' => PieceType::from(c),
+                _ => return None,
+            };
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            pockets.add(color, piece_type);
+        }
+        Some(pockets)
+    }
+
+    /// Writes the content of the pockets as in the brackets of a fen, white pieces first from the queen to the pawns
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for color in [Color::White, Color::Black] {
+            for piece_type in POCKET_PIECES.into_iter().rev() {
+                let c = char::from(piece_type);
+                let c = match color {
+                    Color::White => c.to_ascii_uppercase(),
+                    Color::Black => c,
+                };
+                for _ in 0..self.count(color, piece_type) {
+                    fen.push(c);
+                }
+            }
+        }
+        fen
+    }
+}
@@ -0,0 +1,201 @@
+use crate::game::{castle_rights::CastleType, color::Color, piece_type::PieceType};
+
+use super::{
+    bit_board::BitBoard,
+    board_error::PositionError,
+    move_gen::{attacks_king, BACK_RANKS},
+    Board,
+};
+
+/// Number of pieces each color starts with
+const MAX_PIECES: u32 = 16;
+const MAX_PAWNS: u32 = 8;
+
+/// Number of pieces of each type a color starts with, more pieces must come from promotions
+const START_PIECES: [(PieceType, u32); 4] = [
+    (PieceType::Knight, 2),
+    (PieceType::Bishop, 2),
+    (PieceType::Rook, 2),
+    (PieceType::Queen, 1),
+];
+
+impl Board {
+    /// Checks if the position could occur in a game of the variant of the board.
+    /// Parsing a fen only checks its syntax, searching an illegal position can crash the engine.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        let pawns = self.bb_pieces[Color::White][PieceType::Pawn]
+            | self.bb_pieces[Color::Black][PieceType::Pawn];
+        if let Some(sq) = BitBoard::from(*pawns & BACK_RANKS).get_occupied().next() {
+            return Err(PositionError::PawnOnBackRank(sq));
+        }
+
+        //pieces change their color in variants with drops
+        if !self.variant.has_drops() {
+            self.validate_material(Color::White)?;
+            self.validate_material(Color::Black)?;
+        }
+        if self.variant.has_royal_king() {
+            self.validate_kings()?;
+        }
+        self.validate_castle_rights()?;
+        self.validate_en_passant()
+    }
+
+    fn validate_material(&self, color: Color) -> Result<(), PositionError> {
+        let pieces = &self.bb_pieces[color];
+        if self.bb_occupied[color].count_ones() > MAX_PIECES {
+            return Err(PositionError::TooManyPieces(color));
+        }
+        let pawns = pieces[PieceType::Pawn].count_ones();
+        if pawns > MAX_PAWNS {
+            return Err(PositionError::TooManyPawns(color));
+        }
+
+        //every piece more than at the start is a promoted pawn, without a royal king also kings
+        let mut promoted: u32 = START_PIECES
+            .iter()
+            .map(|(piece_type, count)| pieces[*piece_type].count_ones().saturating_sub(*count))
+            .sum();
+        if !self.variant.has_royal_king() {
+            promoted += pieces[PieceType::King].count_ones().saturating_sub(1);
+        }
+        if pawns + promoted > MAX_PAWNS {
+            return Err(PositionError::TooManyPromotedPieces(color));
+        }
+        Ok(())
+    }
+
+    fn validate_kings(&self) -> Result<(), PositionError> {
+        for color in [Color::White, Color::Black] {
+            match self.bb_pieces[color][PieceType::King].count_ones() {
+                0 => return Err(PositionError::MissingKing(color)),
+                1 => {}
+                _ => return Err(PositionError::TooManyKings(color)),
+            }
+        }
+
+        let white_king = self.get_king_pos(Color::White);
+        if attacks_king(white_king, 0) & *self.bb_pieces[Color::Black][PieceType::King] != 0 {
+            return Err(PositionError::AdjacentKings);
+        }
+
+        let opponent = self.side_to_move.opposite();
+        if self.sq_attacked(self.get_king_pos(opponent), self.side_to_move) {
+            return Err(PositionError::OpponentInCheck(opponent));
+        }
+        Ok(())
+    }
+
+    /// The king has to be on the back rank with the castling rook on its side of the king
+    fn validate_castle_rights(&self) -> Result<(), PositionError> {
+        let rights = self.cur_state().castling_rights;
+        for color in [Color::White, Color::Black] {
+            for castle_type in [CastleType::KingSide, CastleType::QueenSide] {
+                if !rights.has(color, castle_type) {
+                    continue;
+                }
+
+                let (rook_sq, _) = rights.rook_positions(color, castle_type);
+                let king_sq = self.bb_pieces[color][PieceType::King]
+                    .get_occupied()
+                    .find(|sq| sq.rank() == rook_sq.rank());
+                let valid = king_sq.is_some_and(|king_sq| match castle_type {
+                    CastleType::KingSide => rook_sq.file() > king_sq.file(),
+                    CastleType::QueenSide => rook_sq.file() < king_sq.file(),
+                }) && self.get_sq_piece(rook_sq)
+                    == Some(PieceType::Rook.as_colored_piece(color));
+
+                if !valid {
+                    return Err(PositionError::InvalidCastleRight(castle_type, color));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The en passant square must be behind a pawn which just moved two squares from its start square
+    fn validate_en_passant(&self) -> Result<(), PositionError> {
+        let Some(sq) = self.cur_state().en_passant else {
+            return Ok(());
+        };
+
+        let color = self.side_to_move;
+        let rank = match color {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        let valid = sq.rank() == rank
+            && self.get_sq_piece(sq).is_none()
+            && self.get_sq_piece(sq + color.perspective() * 8).is_none()
+            && self.get_sq_piece(sq - color.perspective() * 8)
+                == Some(PieceType::Pawn.as_colored_piece(color.opposite()));
+
+        if !valid {
+            return Err(PositionError::InvalidEnPassant(sq));
+        }
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_validate() {
+        use crate::game::{board::board_error::PositionError, Board, Color, Variant};
+
+        let validate =
+            |fen: &str, variant: Variant| Board::from_fen_variant(fen, variant).unwrap().validate();
+        let error = |fen: &str| validate(fen, Variant::Standard).unwrap_err();
+
+        assert!(validate(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            Variant::Standard
+        )
+        .is_ok());
+        assert!(validate("8/8/8/8/3pP3/8/8/k6K b - e3 0 1", Variant::Standard).is_ok());
+        assert!(matches!(
+            error("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"),
+            PositionError::PawnOnBackRank(_)
+        ));
+        assert!(matches!(
+            error("4k3/4Q3/8/8/8/8/8/4K3 w - - 0 1"),
+            PositionError::OpponentInCheck(Color::Black)
+        ));
+        assert!(matches!(
+            error("8/8/8/8/8/8/3k4/4K3 w - - 0 1"),
+            PositionError::AdjacentKings
+        ));
+        assert!(matches!(
+            error("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+            PositionError::InvalidCastleRight(..)
+        ));
+        assert!(matches!(
+            error("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"),
+            PositionError::InvalidEnPassant(_)
+        ));
+        assert!(matches!(
+            error("QQQk4/8/8/8/8/8/PPPPPPPP/4K3 b - - 0 1"),
+            PositionError::TooManyPromotedPieces(Color::White)
+        ));
+        assert!(
+            Board::from_fen_strict("8/8/8/8/8/8/3k4/4K3 w - - 0 1", Variant::Standard).is_err()
+        );
+
+        //the move number 0 is read as the first move
+        for (fen, expected) in [
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - - 0 0",
+                "4k3/8/8/8/8/8/8/4K3 b - - 0 1",
+            ),
+        ] {
+            let board = Board::from_fen_strict(fen, Variant::Standard).unwrap();
+            assert_eq!(board.to_fen(), expected);
+        }
+
+        //kings are ordinary pieces in Antichess
+        assert!(validate("8/8/8/8/8/8/3k4/3KK3 w - - 0 1", Variant::Antichess).is_ok());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::game::{
+    castle_rights::{CastleRights, CastleType},
+    color::Color,
+    piece_type::PieceType,
+    square::Square,
+};
+
+use super::{zobrist::ZOBRIST, Board};
+
+impl Board {
+    /// Mirrors the board along the horizontal axis (rank 1 <-> rank 8),
+    /// the colors of the pieces and the side to move are kept.
+    /// Castling rights and the en passant square are dropped as they are not valid anymore.
+    pub fn mirror_vertical(&self) -> Board {
+        self.transform(|sq| sq ^ 56, false)
+    }
+
+    /// Mirrors the board vertically and swaps the colors of all pieces and the side to move.
+    /// The resulting board is the same position seen from the other side,
+    /// castling rights and the en passant square are swapped as well.
+    pub fn flip_colors(&self) -> Board {
+        self.transform(|sq| sq ^ 56, true)
+    }
+
+    /// Mirrors the board along the vertical axis (a file <-> h file),
+    /// castling rights are dropped as the kings and rooks are not on their starting squares anymore.
+    pub fn mirror_horizontal(&self) -> Board {
+        self.transform(|sq| sq ^ 7, false)
+    }
+
+    /// Creates a new board with all pieces moved to the mapped squares and optionally swapped colors.
+    /// The castling rights are kept if the king and the rook are mapped to the same files on the back rank,
+    /// the en passant square if it is still on the en passant rank of the side to move.
+    /// The zobrist key is recalculated, the move history is not transformed.
+    fn transform(&self, map: impl Fn(u8) -> u8, swap_colors: bool) -> Board {
+        let map_sq = |sq: Square| Square::new(map(sq.square_value()));
+        let map_color = |color: Color| if swap_colors { color.opposite() } else { color };
+
+        let mut board = Board::empty();
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceType::iter() {
+                let piece = piece_type.as_colored_piece(map_color(color));
+                for sq in self.get_piece_positions(piece_type.as_colored_piece(color)) {
+                    board.update_bb(piece, map_sq(sq), true);
+                }
+            }
+        }
+        board.side_to_move = map_color(self.side_to_move);
+        board.chess960 = self.chess960;
+        board.variant = self.variant;
+
+        let mut castling_rights = CastleRights::default();
+        for color in [Color::White, Color::Black] {
+            for castle_type in [CastleType::KingSide, CastleType::QueenSide] {
+                let rights = self.current_state.castling_rights;
+                if !rights.has(color, castle_type) {
+                    continue;
+                }
+                let new_color = map_color(color);
+                let king = self.get_king_pos(color);
+                let (rook, _) = rights.rook_positions(color, castle_type);
+                let (new_king, new_rook) = (map_sq(king), map_sq(rook));
+                let back_rank = match new_color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                //the dest squares of king and rook are fixed, so the start files must be kept
+                if new_king.file() == king.file()
+                    && new_rook.file() == rook.file()
+                    && new_king.rank() == back_rank
+                    && new_rook.rank() == back_rank
+                {
+                    castling_rights.update(new_color, castle_type, true);
+                    castling_rights.set_rook_file(new_color, castle_type, rook.file());
+                }
+            }
+        }
+        board.current_state.castling_rights = castling_rights;
+
+        let en_passant_rank = match board.side_to_move {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        board.current_state.en_passant = self
+            .current_state
+            .en_passant
+            .map(map_sq)
+            .filter(|sq| sq.rank() == en_passant_rank);
+
+        board.current_state.ply_clock = self.current_state.ply_clock;
+        for color in [Color::White, Color::Black] {
+            board.current_state.checks[map_color(color)] = self.current_state.checks[color];
+        }
+        board.current_state.pockets = self.current_state.pockets.transform(map_sq, swap_colors);
+        //keep the move number, the parity of the ply count depends on the side to move
+        board.ply_count = match (self.side_to_move, board.side_to_move) {
+            (Color::White, Color::Black) => self.ply_count + 1,
+            (Color::Black, Color::White) => self.ply_count.saturating_sub(1),
+            _ => self.ply_count,
+        };
+        board.current_state.zobrist = ZOBRIST.calculate_zobrist_key(&board);
+
+        board
+    }
+}
+
+mod test {
+    #[test]
+    fn test_transformations() {
+        use crate::game::{Board, MoveGeneration};
+
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let flipped = board.flip_colors();
+        assert_eq!(
+            flipped.to_fen(),
+            "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1"
+        );
+        assert_eq!(
+            board.mirror_horizontal().to_fen(),
+            "r2k3r/1bpqpp1p/1pnp2nb/3NP3/3P2p1/p1Q2N2/PPPBBPPP/R2K3R w - - 0 1"
+        );
+        assert_eq!(
+            board.mirror_vertical().to_fen(),
+            "R3K2R/PPPBBPPP/2N2Q1p/1p2P3/3PN3/bn2pnp1/p1ppqpb1/r3k2r w - - 0 1"
+        );
+        assert_eq!(flipped.flip_colors().to_fen(), board.to_fen());
+        assert_eq!(
+            board
+                .mirror_horizontal()
+                .mirror_horizontal()
+                .cur_state()
+                .zobrist,
+            Board::from_fen(&board.mirror_horizontal().mirror_horizontal().to_fen())
+                .unwrap()
+                .cur_state()
+                .zobrist
+        );
+
+        //the en passant square is swapped and the zobrist key matches the parsed position
+        let board = Board::from_fen("8/8/8/8/3pP3/8/8/k6K b - e3 0 1").unwrap();
+        let flipped = board.flip_colors();
+        assert_eq!(flipped.to_fen(), "K6k/8/8/3Pp3/8/8/8/8 w - e6 0 1");
+        assert_eq!(
+            flipped.cur_state().zobrist,
+            Board::from_fen(&flipped.to_fen())
+                .unwrap()
+                .cur_state()
+                .zobrist
+        );
+        assert_eq!(
+            MoveGeneration::generate_legal_moves(&board).len(),
+            MoveGeneration::generate_legal_moves(&flipped).len()
+        );
+    }
+}
@@ -2,10 +2,10 @@ use lazy_static::lazy_static;
 use rand::{Rng, SeedableRng};
 
 use crate::game::{
-    castle_rights::CastleRights, color::Color, piece_type::PieceType, square::Square,
+    castle_rights::CastleRights, color::Color, piece::Piece, piece_type::PieceType, square::Square,
 };
 
-use super::Board;
+use super::{bit_board::BitBoard, pocket::POCKET_PIECES, Board};
 
 lazy_static! {
     pub static ref ZOBRIST: Zobrist = Zobrist::new(0x8151894534);
@@ -16,6 +16,9 @@ pub struct Zobrist {
     castling: [u64; 16],
     en_passant: [u64; 9], // 8 files + 1 for no en passant
     side_to_move: u64,
+    checks: [[u64; 3]; 2], // 1 to 3 checks given by each color, used in Three-check
+    pockets: [[[u64; 16]; 5]; 2], // 1 to 16 pieces of each type in the pocket of each color, used in Crazyhouse
+    promoted: [u64; 64], // squares of promoted pieces, which go into the pocket as pawns in Crazyhouse
 }
 
 impl Zobrist {
@@ -41,28 +44,43 @@ impl Zobrist {
             en_passant[i] = rng.gen();
         }
 
+        //generated last to keep the keys of the other features unchanged
+        let mut checks = [[0; 3]; 2];
+        for rn in checks.iter_mut().flatten() {
+            *rn = rng.gen();
+        }
+        let mut pockets = [[[0; 16]; 5]; 2];
+        for rn in pockets.iter_mut().flatten().flatten() {
+            *rn = rng.gen();
+        }
+        let mut promoted = [0; 64];
+        for rn in promoted.iter_mut() {
+            *rn = rng.gen();
+        }
+
         Zobrist {
             pieces,
             castling,
             en_passant,
             side_to_move,
+            checks,
+            pockets,
+            promoted,
         }
     }
 
     pub fn calculate_zobrist_key(&self, board: &Board) -> u64 {
         let mut zobrist_key = 0;
-        PieceType::iter().for_each(|piece_type| {
-            board.bb_pieces[Color::White][piece_type]
-                .get_occupied()
-                .for_each(|square| {
-                    zobrist_key ^= self.pieces[piece_type][square];
-                });
-            board.bb_pieces[Color::Black][piece_type]
-                .get_occupied()
-                .for_each(|square| {
-                    zobrist_key ^= self.pieces[piece_type as usize * 2][square];
-                });
-        });
+        for color in [Color::White, Color::Black] {
+            PieceType::iter().for_each(|piece_type| {
+                let piece = piece_type.as_colored_piece(color);
+                board.bb_pieces[color][piece_type]
+                    .get_occupied()
+                    .for_each(|square| {
+                        zobrist_key ^= self.get_rn_piece(piece, square);
+                    });
+            });
+        }
 
         if board.side_to_move == Color::Black {
             zobrist_key ^= self.side_to_move;
@@ -70,6 +88,14 @@ impl Zobrist {
 
         zobrist_key ^= self.castling[board.current_state.castling_rights.as_u8() as usize];
         zobrist_key ^= self.get_rn_en_passant(board.current_state.en_passant.as_ref());
+        for color in [Color::White, Color::Black] {
+            zobrist_key ^= self.get_rn_checks(color, board.current_state.checks[color]);
+            for piece_type in POCKET_PIECES {
+                let count = board.current_state.pockets.count(color, piece_type);
+                zobrist_key ^= self.get_rn_pocket(color, piece_type, count);
+            }
+        }
+        zobrist_key ^= self.get_rn_promoted(board.current_state.pockets.promoted());
 
         zobrist_key
     }
@@ -80,8 +106,8 @@ impl Zobrist {
     }
 
     ///use for incremental update
-    pub fn get_rn_piece(&self, piece_type: PieceType, square: Square) -> u64 {
-        self.pieces[piece_type][square]
+    pub fn get_rn_piece(&self, piece: Piece, square: Square) -> u64 {
+        self.pieces[piece.color() as usize * 6 + piece.ptype() as usize][square]
     }
 
     ///use for incremental update
@@ -96,4 +122,27 @@ impl Zobrist {
             None => self.en_passant[8],
         }
     }
+
+    ///use for incremental update, no checks given doesn't change the key
+    pub fn get_rn_checks(&self, color: Color, count: u8) -> u64 {
+        match count {
+            0 => 0,
+            count => self.checks[color][count.min(3) as usize - 1],
+        }
+    }
+
+    ///use for incremental update, an empty pocket doesn't change the key
+    pub fn get_rn_pocket(&self, color: Color, piece_type: PieceType, count: u8) -> u64 {
+        match count {
+            0 => 0,
+            count => self.pockets[color][piece_type][count.min(16) as usize - 1],
+        }
+    }
+
+    ///use for incremental update, combined key of the promoted pieces on the squares of the mask
+    pub fn get_rn_promoted(&self, squares: u64) -> u64 {
+        BitBoard::from(squares)
+            .get_occupied()
+            .fold(0, |key, square| key ^ self.promoted[square])
+    }
 }
@@ -31,21 +31,30 @@ impl<T, const N: usize> std::ops::IndexMut<CastleType> for [T; N] {
     }
 }
 
+/// #### Castle rights
+/// The castle rights of both colors and the start files of the castling rooks.
+/// The rook files are kept when the rights are lost, so the castling rook of a Chess960 game is always known.
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub struct CastleRights(u8);
+pub struct CastleRights {
+    rights: u8,
+    /// start file of the castling rook (indexed by CastleRights::to_index)
+    rook_files: [u8; 4],
+}
 
 impl CastleRights {
+    const STANDARD_ROOK_FILES: [u8; 4] = [7, 0, 7, 0];
+
     pub fn update(&mut self, color: Color, castle_type: CastleType, value: bool) {
         let index = Self::to_index(color, castle_type);
         if value {
-            self.0 |= 1 << index;
+            self.rights |= 1 << index;
         } else {
-            self.0 &= !(1 << index);
+            self.rights &= !(1 << index);
         }
     }
 
     pub fn has(&self, color: Color, castle_type: CastleType) -> bool {
-        self.0 & (1 << Self::to_index(color, castle_type)) != 0
+        self.rights & (1 << Self::to_index(color, castle_type)) != 0
     }
 
     pub fn has_any(&self, color: Color) -> bool {
@@ -61,31 +70,78 @@ impl CastleRights {
         }
     }
 
+    /// The castle rights as bit set, the rook files are not included
     pub fn as_u8(&self) -> u8 {
-        self.0
+        self.rights
+    }
+
+    pub fn rook_file(&self, color: Color, castle_type: CastleType) -> u8 {
+        self.rook_files[Self::to_index(color, castle_type) as usize]
+    }
+
+    pub fn set_rook_file(&mut self, color: Color, castle_type: CastleType, file: u8) {
+        self.rook_files[Self::to_index(color, castle_type) as usize] = file;
+    }
+
+    /// Checks if all castling rooks start on the a and h file
+    pub fn has_standard_rooks(&self) -> bool {
+        self.rook_files == Self::STANDARD_ROOK_FILES
+    }
+
+    /// Returns the source and dest square of the castling rook
+    pub fn rook_positions(&self, color: Color, castle_type: CastleType) -> (Square, Square) {
+        let index = Self::to_index(color, castle_type) as usize;
+        let back_rank = match color {
+            Color::White => 0,
+            Color::Black => 56,
+        };
+        (
+            Square::new(back_rank + self.rook_files[index]),
+            CastleType::ROOK_DEST[index],
+        )
+    }
+
+    /// File of the rook as used in Shredder-FEN and X-FEN, upper case for white
+    pub fn file_char(file: u8, color: Color) -> char {
+        let char = (b'a' + file) as char;
+        match color {
+            Color::White => char.to_ascii_uppercase(),
+            Color::Black => char,
+        }
     }
 }
 
 impl Default for CastleRights {
     fn default() -> Self {
-        CastleRights(0)
+        CastleRights {
+            rights: 0,
+            rook_files: Self::STANDARD_ROOK_FILES,
+        }
     }
 }
 
+/// Standard notation (KQkq) for rooks on the a and h file, else the files of the rooks (Shredder-FEN)
 impl std::fmt::Display for CastleRights {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut rights = String::new();
-        if self.has(Color::White, CastleType::KingSide) {
-            rights.push('K');
-        }
-        if self.has(Color::White, CastleType::QueenSide) {
-            rights.push('Q');
-        }
-        if self.has(Color::Black, CastleType::KingSide) {
-            rights.push('k');
-        }
-        if self.has(Color::Black, CastleType::QueenSide) {
-            rights.push('q');
+        let castling = [
+            (Color::White, CastleType::KingSide, 'K'),
+            (Color::White, CastleType::QueenSide, 'Q'),
+            (Color::Black, CastleType::KingSide, 'k'),
+            (Color::Black, CastleType::QueenSide, 'q'),
+        ];
+        for (color, castle_type, char) in castling {
+            if !self.has(color, castle_type) {
+                continue;
+            }
+            if self.has_standard_rooks() {
+                rights.push(char);
+            } else {
+                rights.push(CastleRights::file_char(
+                    self.rook_file(color, castle_type),
+                    color,
+                ));
+            }
         }
         if rights.is_empty() {
             write!(f, "-")
@@ -105,8 +161,11 @@ impl std::fmt::Debug for CastleRights {
 pub enum CastleRightsError {
     #[error("Invalid character {0}")]
     InvalidCharacter(char),
+    #[error("No rook to castle with for {0}")]
+    MissingRook(char),
 }
 
+/// Parses the standard notation (KQkq) with the rooks on the a and h file
 impl TryFrom<&str> for CastleRights {
     type Error = CastleRightsError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -130,17 +189,15 @@ impl TryFrom<&str> for CastleRights {
 }
 
 impl CastleType {
-    /// Returns the source square of the rook for the given castle type and color (indexed by CastleRights::to_index)
-    const ROOK_SOURCE: [Square; 4] = [Square::H1, Square::A1, Square::H8, Square::A8];
     /// Returns the dest square of the rook for the given castle type and color (indexed by CastleRights::to_index)
     const ROOK_DEST: [Square; 4] = [Square::F1, Square::D1, Square::F8, Square::D8];
 
-    /// Returns the source square of the king for the color
+    /// Returns the source square of the king for the color in standard chess
     pub const KING_SOURCE: [Square; 2] = [Square::E1, Square::E8];
     /// Returns the dest square of the king for the given castle type and color (indexed by CastleRights::to_index)
     pub const KING_DEST: [Square; 4] = [Square::G1, Square::C1, Square::G8, Square::C8];
 
-    /// Checks which castle type satisfies the move from and to squares
+    /// Checks which castle type satisfies the move from and to squares in standard notation (e1g1)
     pub fn satisfies_castle(from: Square, to: Square, color: Color) -> Option<Self> {
         if from == CastleType::KING_SOURCE[color]
             && to
@@ -158,10 +215,8 @@ impl CastleType {
         None
     }
 
-    pub fn get_rook_positions(&self, color: Color) -> (Square, Square) {
-        (
-            CastleType::ROOK_SOURCE[CastleRights::to_index(color, *self) as usize],
-            CastleType::ROOK_DEST[CastleRights::to_index(color, *self) as usize],
-        )
+    /// Returns the dest square of the king
+    pub fn king_dest(&self, color: Color) -> Square {
+        CastleType::KING_DEST[CastleRights::to_index(color, *self) as usize]
     }
 }
@@ -5,10 +5,13 @@ pub mod color;
 pub mod move_notation;
 pub mod parser;
 pub mod piece;
+pub mod piece_square_table;
 pub mod piece_type;
 pub mod square;
+pub mod variant;
 
 // Re-export the most important types
+pub use board::builder::BoardBuilder;
 pub use board::game_result::GameResult;
 pub use board::move_gen::MoveGeneration;
 pub use board::Board;
@@ -17,3 +20,4 @@ pub use move_notation::Move;
 pub use piece::Piece;
 pub use piece_type::PieceType;
 pub use square::Square;
+pub use variant::Variant;
@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use super::{castle_rights::CastleType, piece_type::PieceType, square::Square, Board};
+use super::{
+    board::board_error::MoveError, castle_rights::CastleType, color::Color, piece_type::PieceType,
+    square::Square, Board,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 /// A space-efficient representation of a move in a chess game.
@@ -8,6 +11,8 @@ use super::{castle_rights::CastleType, piece_type::PieceType, square::Square, Bo
 /// - The source square (6 bits)
 /// - The destination square (6 bits)
 /// - The move flag (4 bits)
+///
+/// Drops have no source square, the source bits store the type of the dropped piece instead.
 pub struct Move(u16);
 
 const SOURCE_SQUARE_MASK: u16 = 0b0000_0000_0000_0000_0000_0000_0011_1111;
@@ -34,6 +39,11 @@ impl Move {
         Move(first_move_value)
     }
 
+    /// Creates a drop of a piece from the pocket onto the destination square
+    pub fn new_drop(piece_type: PieceType, dest: Square) -> Self {
+        Move::new(Square::new(piece_type as u8), dest, MoveFlag::Drop)
+    }
+
     pub fn null() -> Self {
         Move(0)
     }
@@ -42,6 +52,15 @@ impl Move {
         self.0 == 0
     }
 
+    /// The packed value of the move, used to store moves in files
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    pub fn from_u16(value: u16) -> Self {
+        Move(value)
+    }
+
     pub fn source(&self) -> Square {
         Square::new(((self.0 & SOURCE_SQUARE_MASK) >> SOURCE_SQUARE_OFFSET) as u8)
     }
@@ -54,12 +73,33 @@ impl Move {
         MoveFlag::from(((self.0 & FLAGS_MASK) >> FLAGS_OFFSET) as u8)
     }
 
+    /// The type of the dropped piece if the move is a drop
+    pub fn drop_type(&self) -> Option<PieceType> {
+        self.flag().is_drop().then(|| {
+            PieceType::from(((self.0 & SOURCE_SQUARE_MASK) >> SOURCE_SQUARE_OFFSET) as usize)
+        })
+    }
+
+    /// The type of the piece moved on the board or dropped
+    pub fn moved_piece_type(&self, board: &Board) -> Option<PieceType> {
+        self.drop_type()
+            .or_else(|| board.get_sq_piece_variation(self.source()))
+    }
+
     /// Convert the move to UCI notation. This conversion is lossy, as the move will not contain more information than the source, destination and Promotion or not.
     pub fn as_uci_notation(&self) -> String {
         if self.is_null() {
             return String::from("0000");
         }
 
+        if let Some(drop_type) = self.drop_type() {
+            return format!(
+                "{}@{}",
+                char::from(drop_type).to_ascii_uppercase(),
+                self.dest()
+            );
+        }
+
         format!(
             "{}{}{}",
             self.source(),
@@ -73,13 +113,33 @@ impl Move {
     /// Parse a move from UCI notation.
     /// Promotion can be specified by =(Type) or (Type) at the end of the move. Example: e7e8q or e7e8=q
     /// Null move can be specified by 0000 or 00-00
-    pub fn from_uci_notation(uci: &str, board: &Board) -> Result<Self, UCIMoveParseError> {
+    /// Drops are written as the piece, @ and the destination square. Example: P@e4
+    /// Returns why the move is illegal if it can't be played on the board
+    pub fn from_uci_notation(uci: &str, board: &Board) -> Result<Self, MoveError> {
+        if uci == "0000" || uci == "00-00" {
+            return Ok(Move::null());
+        }
+
+        let mov = Self::parse_uci_notation(uci, board)?;
+        board.validate_move(&mov)?;
+        Ok(mov)
+    }
+
+    /// Parses the move without checking if it can be played on the board
+    fn parse_uci_notation(uci: &str, board: &Board) -> Result<Self, MoveError> {
         if uci.len() < 4 || uci.len() > 6 {
-            return Err(UCIMoveParseError::InvalidLength);
+            return Err(UCIMoveParseError::InvalidLength.into());
         }
 
-        if uci == "0000" || uci == "00-00" {
-            return Ok(Move::null());
+        if let Some((piece, dest)) = uci.split_once('@') {
+            let drop_type = match piece {
+                "P" | "N" | "B" | "R" | "Q" | "p" | "n" | "b" | "r" | "q" => {
+                    PieceType::from(piece.chars().next().unwrap())
+                }
+                _ => return Err(UCIMoveParseError::InvalidDropPiece.into()),
+            };
+            let dest = Square::try_from(dest).map_err(|_| UCIMoveParseError::InvalidDestSquare)?;
+            return Ok(Move::new_drop(drop_type, dest));
         }
 
         let source =
@@ -88,7 +148,7 @@ impl Move {
             Square::try_from(&uci[2..4]).map_err(|_| UCIMoveParseError::InvalidDestSquare)?;
         let moved_piece_type = board
             .get_sq_piece(source)
-            .ok_or(UCIMoveParseError::EmptySourceSquare)?
+            .ok_or(MoveError::EmptySource(source))?
             .ptype();
 
         let move_flag = match moved_piece_type {
@@ -99,7 +159,8 @@ impl Move {
                         "b" | "=b" => MoveFlag::BishopPromotion,
                         "r" | "=r" => MoveFlag::RookPromotion,
                         "q" | "=q" => MoveFlag::QueenPromotion,
-                        _ => return Err(UCIMoveParseError::InvalidPromotionFlag),
+                        "k" | "=k" => MoveFlag::KingPromotion,
+                        _ => return Err(UCIMoveParseError::InvalidPromotionFlag.into()),
                     }
                 } else if board.cur_state().en_passant.is_some_and(|sq| sq == dest) {
                     MoveFlag::EnPassant
@@ -109,16 +170,60 @@ impl Move {
                     MoveFlag::Normal
                 }
             }
-            PieceType::King => CastleType::satisfies_castle(source, dest, board.side_to_move())
-                .map_or(MoveFlag::Normal, |castle| match castle {
-                    CastleType::KingSide => MoveFlag::KingSideCastle,
-                    CastleType::QueenSide => MoveFlag::QueenSideCastle,
-                }),
+            //without a royal king there is no castling
+            PieceType::King if board.variant().has_royal_king() => {
+                let color = board.side_to_move();
+                let castle = Move::king_takes_rook_castle(dest, board)
+                    .or_else(|| CastleType::satisfies_castle(source, dest, color));
+                match castle {
+                    Some(castle_type) => {
+                        return Ok(Move::new(
+                            source,
+                            castle_type.king_dest(color),
+                            castle_type.into(),
+                        ))
+                    }
+                    None => MoveFlag::Normal,
+                }
+            }
             _ => MoveFlag::Normal,
         };
 
         return Ok(Move::new(source, dest, move_flag));
     }
+
+    /// Castling in Chess960 notation is written as the king capturing its own castling rook (e1h1)
+    fn king_takes_rook_castle(dest: Square, board: &Board) -> Option<CastleType> {
+        let color = board.side_to_move();
+        let rights = board.cur_state().castling_rights;
+        [CastleType::KingSide, CastleType::QueenSide]
+            .into_iter()
+            .find(|castle_type| {
+                rights.has(color, *castle_type)
+                    && rights.rook_positions(color, *castle_type).0 == dest
+                    && board.get_sq_piece(dest) == Some(PieceType::Rook.as_colored_piece(color))
+            })
+    }
+
+    /// Convert the move to UCI notation for the board.
+    /// Castling moves of Chess960 boards are written as the king capturing its own rook (e1h1)
+    pub fn to_uci(&self, board: &Board) -> String {
+        match self.flag().castle_side() {
+            Some(castle_type) if board.is_chess960() => {
+                let color = if self.source().rank() == 0 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let (rook, _) = board
+                    .cur_state()
+                    .castling_rights
+                    .rook_positions(color, castle_type);
+                format!("{}{}", self.source(), rook)
+            }
+            _ => self.as_uci_notation(),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -131,8 +236,8 @@ pub enum UCIMoveParseError {
     InvalidDestSquare,
     #[error("Invalid flag for promotion")]
     InvalidPromotionFlag,
-    #[error("Cannot parse move with an empty source square")]
-    EmptySourceSquare,
+    #[error("Invalid piece for a drop, must be one of P, N, B, R or Q")]
+    InvalidDropPiece,
 }
 
 impl std::fmt::Display for Move {
@@ -151,6 +256,10 @@ impl std::fmt::Debug for Move {
             return write!(f, "0000");
         }
 
+        if self.flag().is_drop() {
+            return write!(f, "{} {:?}", self.as_uci_notation(), self.flag());
+        }
+
         write!(f, "{}{} {:?}", self.source(), self.dest(), self.flag())
     }
 }
@@ -163,10 +272,12 @@ pub enum MoveFlag {
     QueenSideCastle = 3, // 0b0011
     DoublePawnPush = 4,  // 0b0100
     EnPassant = 5,       // 0b0101
+    Drop = 6,            // 0b0110
     KnightPromotion = 8, // 0b1000
     BishopPromotion = 9, // 0b1001
     RookPromotion = 10,  // 0b1010
     QueenPromotion = 11, // 0b1011
+    KingPromotion = 12,  // 0b1100, only in variants without a royal king
 }
 
 impl std::default::Default for MoveFlag {
@@ -184,15 +295,26 @@ impl From<u8> for MoveFlag {
             3 => MoveFlag::QueenSideCastle,
             4 => MoveFlag::DoublePawnPush,
             5 => MoveFlag::EnPassant,
+            6 => MoveFlag::Drop,
             8 => MoveFlag::KnightPromotion,
             9 => MoveFlag::BishopPromotion,
             10 => MoveFlag::RookPromotion,
             11 => MoveFlag::QueenPromotion,
+            12 => MoveFlag::KingPromotion,
             _ => MoveFlag::Null,
         }
     }
 }
 
+impl From<CastleType> for MoveFlag {
+    fn from(castle_type: CastleType) -> Self {
+        match castle_type {
+            CastleType::KingSide => MoveFlag::KingSideCastle,
+            CastleType::QueenSide => MoveFlag::QueenSideCastle,
+        }
+    }
+}
+
 impl From<MoveFlag> for u8 {
     fn from(flag: MoveFlag) -> u8 {
         flag as u8
@@ -212,6 +334,10 @@ impl MoveFlag {
         *self == MoveFlag::EnPassant
     }
 
+    pub fn is_drop(&self) -> bool {
+        *self == MoveFlag::Drop
+    }
+
     pub fn is_double_pawn_push(&self) -> bool {
         *self == MoveFlag::DoublePawnPush
     }
@@ -226,6 +352,7 @@ impl MoveFlag {
             MoveFlag::BishopPromotion => Some(PieceType::Bishop),
             MoveFlag::RookPromotion => Some(PieceType::Rook),
             MoveFlag::QueenPromotion => Some(PieceType::Queen),
+            MoveFlag::KingPromotion => Some(PieceType::King),
             _ => None,
         }
     }
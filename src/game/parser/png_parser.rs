@@ -3,9 +3,9 @@ use std::collections::HashMap;
 use thiserror::Error;
 
 use crate::game::{
-    board::{fen_utility::FENUtility, move_gen::MoveGeneration},
+    board::{board_error::MoveError, fen_utility::FENUtility, move_gen::MoveGeneration},
     castle_rights::CastleType,
-    Board, GameResult, Move, PieceType, Square,
+    Board, GameResult, Move, PieceType, Square, Variant,
 };
 
 pub struct PGNParser();
@@ -27,6 +27,16 @@ impl PGNParser {
             return Ok(castle_type.to_string());
         }
 
+        //drops are written as the piece, @ and the destination square, e.g. N@f3
+        if let Some(drop_type) = mov.drop_type() {
+            let san = format!(
+                "{}@{}",
+                drop_type.to_string().to_ascii_uppercase(),
+                mov.dest()
+            );
+            return Self::append_check(san, mov, board);
+        }
+
         let source = mov.source();
         let dest = mov.dest();
         let moved_piece = board
@@ -47,6 +57,7 @@ impl PGNParser {
                 .filter(|m| {
                     m.dest() == dest
                         && *m != mov
+                        && !m.flag().is_drop()
                         && board.get_sq_piece(m.source()).unwrap().ptype() == moved_piece
                 })
                 .collect::<Vec<_>>();
@@ -88,6 +99,11 @@ impl PGNParser {
             san.push_str(&promotion_piece.to_string().to_ascii_uppercase());
         }
 
+        Self::append_check(san, mov, board)
+    }
+
+    /// Appends + or # to the SAN of the move if it gives check or mate
+    fn append_check(mut san: String, mov: &Move, board: &mut Board) -> Result<String, ToSANError> {
         board
             .make_move(mov, true, false)
             .map_err(|_| ToSANError::MoveMakeError)?;
@@ -109,7 +125,8 @@ impl PGNParser {
     }
 
     /// Get the move from SAN (Standard Algebraic Notation).
-    pub fn move_from_san(san: &str, board: &Board) -> Result<Move, FromSANError> {
+    /// Returns why the move is illegal if it can't be played on the board
+    pub fn move_from_san(san: &str, board: &Board) -> Result<Move, MoveError> {
         if san == "null" {
             return Ok(Move::null());
         }
@@ -121,48 +138,86 @@ impl PGNParser {
             },
             "",
         )[..];
-        let legal_moves = MoveGeneration::generate_legal_moves(board);
+        let notation_error = || MoveError::SANNotation(san.to_string());
+
+        //drops of pieces, P@e4 or @e4 for pawns
+        if let Some((piece, dest)) = cleaned_san.split_once('@') {
+            let drop_type = match piece {
+                "" | "P" => PieceType::Pawn,
+                "N" | "B" | "R" | "Q" => PieceType::from(piece.chars().next().unwrap()),
+                _ => return Err(notation_error()),
+            };
+            let dest = Square::try_from(dest).map_err(|_| notation_error())?;
+            let mov = Move::new_drop(drop_type, dest);
+            board.validate_move(&mov)?;
+            return Ok(mov);
+        }
+        //the source of drops is no square, so only moves on the board are matched below
+        let legal_moves: Vec<Move> = MoveGeneration::generate_legal_moves(board)
+            .iter()
+            .filter(|m| !m.flag().is_drop())
+            .copied()
+            .collect();
 
         // Check if the move is a castle move
-        if cleaned_san == "OO" {
-            return legal_moves
+        let castle_type = match cleaned_san {
+            "OO" => Some(CastleType::KingSide),
+            "OOO" => Some(CastleType::QueenSide),
+            _ => None,
+        };
+        if let Some(castle_type) = castle_type {
+            let color = board.side_to_move();
+            return match legal_moves
                 .iter()
-                .find(|m| {
-                    m.flag()
-                        .castle_side()
-                        .is_some_and(|c| c == CastleType::KingSide)
-                })
-                .ok_or(FromSANError::InvalidMove)
-                .cloned();
+                .find(|m| m.flag().castle_side() == Some(castle_type))
+            {
+                Some(mov) => Ok(*mov),
+                None if !board.cur_state().castling_rights.has(color, castle_type) => {
+                    Err(MoveError::MissingCastleRight(castle_type, color))
+                }
+                None => Err(MoveError::CastleBlocked(castle_type)),
+            };
         }
 
-        if cleaned_san == "OOO" {
-            return legal_moves
-                .iter()
-                .find(|m| {
-                    m.flag()
-                        .castle_side()
-                        .is_some_and(|c| c == CastleType::QueenSide)
-                })
-                .ok_or(FromSANError::InvalidMove)
-                .cloned();
+        if cleaned_san.len() < 2 {
+            return Err(notation_error());
         }
 
-        if cleaned_san.len() < 2 {
-            return Err(FromSANError::InvalidMove);
+        if let Some(mov) = Self::match_san(cleaned_san, board, &legal_moves)? {
+            return Ok(mov);
         }
+        //the move a piece could do without regard to the rules tells why the move is illegal
+        let pseudo_legal_moves: Vec<Move> = MoveGeneration::generate_pseudo_legal_moves(board)
+            .iter()
+            .copied()
+            .collect();
+        match Self::match_san(cleaned_san, board, &pseudo_legal_moves)? {
+            Some(mov) => Err(board
+                .validate_move(&mov)
+                .err()
+                .unwrap_or_else(|| MoveError::NoMatchingMove(san.to_string()))),
+            None => Err(MoveError::NoMatchingMove(san.to_string())),
+        }
+    }
+
+    /// Finds the move of pieces and pawns in SAN without castling and drop characters
+    /// among the given moves. Returns None if no move matches.
+    fn match_san(
+        cleaned_san: &str,
+        board: &Board,
+        moves: &[Move],
+    ) -> Result<Option<Move>, MoveError> {
+        let notation_error = || MoveError::SANNotation(cleaned_san.to_string());
 
         //pawn moves or other
         match cleaned_san.chars().next().unwrap() {
             file @ ('a' | 'b' | 'c' | 'd' | 'e' | 'f' | 'g' | 'h') => {
                 //handle promotion
                 if cleaned_san.contains("=") {
-                    let dest = Square::try_from(&cleaned_san[..2])
-                        .map_err(|_| FromSANError::ParseError)?;
-                    let promotion_piece = PieceType::from(
-                        cleaned_san.chars().last().ok_or(FromSANError::ParseError)?,
-                    );
-                    let mov = legal_moves.iter().find(|m| {
+                    let dest = Square::try_from(&cleaned_san[..2]).map_err(|_| notation_error())?;
+                    let promotion_piece =
+                        PieceType::from(cleaned_san.chars().last().ok_or_else(notation_error)?);
+                    let mov = moves.iter().find(|m| {
                         m.dest() == dest
                             && file.to_string() == m.source().file_str()
                             && m.flag().is_promotion()
@@ -172,14 +227,13 @@ impl PGNParser {
                                 .is_some_and(|p| p == PieceType::Pawn)
                     });
 
-                    return mov.ok_or(FromSANError::InvalidMove).cloned();
+                    return Ok(mov.cloned());
                 }
 
                 //multiple pawns could move to the same square
                 if cleaned_san.len() == 3 {
-                    let dest = Square::try_from(&cleaned_san[1..])
-                        .map_err(|_| FromSANError::ParseError)?;
-                    let mov = legal_moves.iter().find(|m| {
+                    let dest = Square::try_from(&cleaned_san[1..]).map_err(|_| notation_error())?;
+                    let mov = moves.iter().find(|m| {
                         m.dest() == dest
                             && file.to_string() == m.source().file_str()
                             && board
@@ -187,43 +241,41 @@ impl PGNParser {
                                 .is_some_and(|p| p == PieceType::Pawn)
                     });
 
-                    return mov.ok_or(FromSANError::InvalidMove).cloned();
+                    return Ok(mov.cloned());
                 }
 
-                let dest = Square::try_from(cleaned_san).map_err(|_| FromSANError::ParseError)?;
-                let mov = legal_moves.iter().find(|m| {
+                let dest = Square::try_from(cleaned_san).map_err(|_| notation_error())?;
+                let mov = moves.iter().find(|m| {
                     m.dest() == dest
                         && board
                             .get_sq_piece_variation(m.source())
                             .is_some_and(|p| p == PieceType::Pawn)
                 });
 
-                return mov.ok_or(FromSANError::InvalidMove).cloned();
+                return Ok(mov.cloned());
             }
             _ => {
                 let moved_piece = PieceType::from(cleaned_san.chars().next().unwrap());
 
                 //exact move from the moved piece to the destination
                 if cleaned_san.len() == 3 {
-                    let dest = Square::try_from(&cleaned_san[1..])
-                        .map_err(|_| FromSANError::ParseError)?;
+                    let dest = Square::try_from(&cleaned_san[1..]).map_err(|_| notation_error())?;
 
-                    let mov = legal_moves.iter().find(|m| {
+                    let mov = moves.iter().find(|m| {
                         m.dest() == dest
                             && board
                                 .get_sq_piece_variation(m.source())
                                 .is_some_and(|p| p == moved_piece)
                     });
 
-                    return mov.ok_or(FromSANError::InvalidMove).cloned();
+                    return Ok(mov.cloned());
                 }
                 //move with file or rank specified
                 if cleaned_san.len() == 4 {
-                    let dest = Square::try_from(&cleaned_san[2..])
-                        .map_err(|_| FromSANError::ParseError)?;
+                    let dest = Square::try_from(&cleaned_san[2..]).map_err(|_| notation_error())?;
                     let source = &cleaned_san[1..2];
 
-                    let mov = legal_moves.iter().find(|m| {
+                    let mov = moves.iter().find(|m| {
                         m.dest() == dest
                             && (source == &m.source().file_str()
                                 || source == &m.source().rank_str())
@@ -232,16 +284,15 @@ impl PGNParser {
                                 .is_some_and(|p| p == moved_piece)
                     });
 
-                    return mov.ok_or(FromSANError::InvalidMove).cloned();
+                    return Ok(mov.cloned());
                 }
                 // move with file and rank specified
                 if cleaned_san.len() == 5 {
-                    let dest = Square::try_from(&cleaned_san[3..])
-                        .map_err(|_| FromSANError::ParseError)?;
-                    let source = Square::try_from(&cleaned_san[1..3])
-                        .map_err(|_| FromSANError::ParseError)?;
+                    let dest = Square::try_from(&cleaned_san[3..]).map_err(|_| notation_error())?;
+                    let source =
+                        Square::try_from(&cleaned_san[1..3]).map_err(|_| notation_error())?;
 
-                    let mov = legal_moves.iter().find(|m| {
+                    let mov = moves.iter().find(|m| {
                         m.dest() == dest
                             && m.source() == source
                             && board
@@ -249,12 +300,12 @@ impl PGNParser {
                                 .is_some_and(|p| p == moved_piece)
                     });
 
-                    return mov.ok_or(FromSANError::InvalidMove).cloned();
+                    return Ok(mov.cloned());
                 }
             }
         };
 
-        Err(FromSANError::InvalidMove)
+        Ok(None)
 
         // //must be at least 2 characters
         // if cleaned_san.len() < 2 {
@@ -370,7 +421,10 @@ impl PGNParser {
         }
         let mut board = tags_map
             .get("FEN")
-            .map_or_else(|| Ok(Board::default()), |fen| Board::from_fen(fen))
+            .map_or_else(
+                || Ok(Board::default()),
+                |fen| Board::from_fen_strict(fen, Variant::Standard),
+            )
             .map_err(|_| FromSANError::ParseError)?;
 
         let mut move_history = Vec::new();
@@ -405,10 +459,8 @@ impl PGNParser {
                 continue;
             }
 
-            let mov = Self::move_from_san(mov.trim(), &mut board)?;
-            board
-                .make_move(&mov, false, true)
-                .map_err(|_| FromSANError::InvalidMove)?;
+            let mov = Self::move_from_san(mov.trim(), &board)?;
+            board.make_move(&mov, false, false)?;
             move_history.push(mov);
         }
 
@@ -447,8 +499,8 @@ pub enum ToSANError {
 
 #[derive(Error, Debug)]
 pub enum FromSANError {
-    #[error("Move is not a legal move from this position")]
-    InvalidMove,
+    #[error(transparent)]
+    InvalidMove(#[from] MoveError),
     #[error("Error parsing")]
     ParseError,
 }
@@ -52,7 +52,7 @@ impl From<Piece> for char {
 
 //CONSTANTS
 impl Piece {
-    pub const BLACK_PAWN: Piece = Piece(PieceType::Rook, Color::Black);
+    pub const BLACK_PAWN: Piece = Piece(PieceType::Pawn, Color::Black);
     pub const BLACK_KNIGHT: Piece = Piece(PieceType::Knight, Color::Black);
     pub const BLACK_BISHOP: Piece = Piece(PieceType::Bishop, Color::Black);
     pub const BLACK_ROOK: Piece = Piece(PieceType::Rook, Color::Black);
@@ -1,21 +1,62 @@
-use crate::game::{Color, Piece};
+use super::{color::Color, piece::Piece, square::Square};
 
-use super::eval::Eval;
+/// Score in centipawns
+pub type Eval = i32;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// #### Weighted piece score  
 /// 1st Eval: weight for mid game  
 /// 2nd Eval: weight for end game  
 /// The final score is calculated as: **mid_game_score * (1-end_game_factor) + end_game_score * end_game_factor**
-pub struct W(Eval, Eval);
+pub struct W(pub Eval, pub Eval);
 
 impl W {
+    pub const ZERO: W = W(0, 0);
+
     #[inline(always)]
     pub fn weight(&self, mid_game: f32, end_game: f32) -> Eval {
         (self.0 as f32 * mid_game + self.1 as f32 * end_game) as Eval
     }
 }
 
+impl std::ops::Add for W {
+    type Output = W;
+
+    fn add(self, rhs: W) -> Self::Output {
+        W(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl std::ops::AddAssign for W {
+    fn add_assign(&mut self, rhs: W) {
+        self.0 += rhs.0;
+        self.1 += rhs.1;
+    }
+}
+
+impl std::ops::Sub for W {
+    type Output = W;
+
+    fn sub(self, rhs: W) -> Self::Output {
+        W(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl std::ops::SubAssign for W {
+    fn sub_assign(&mut self, rhs: W) {
+        self.0 -= rhs.0;
+        self.1 -= rhs.1;
+    }
+}
+
+impl std::ops::Mul<Eval> for W {
+    type Output = W;
+
+    fn mul(self, rhs: Eval) -> Self::Output {
+        W(self.0 * rhs, self.1 * rhs)
+    }
+}
+
 type PSqTable = [Eval; 64];
 type WPSqTable = [W; 64];
 
@@ -26,12 +67,12 @@ type WPSqTable = [W; 64];
 */
 
 // Piece values in centipawns
-pub(super) const PAWN_MG: Eval = 100;
-pub(super) const KNIGHT_MG: Eval = 320;
-pub(super) const BISHOP_MG: Eval = 330;
-pub(super) const ROOK_MG: Eval = 500;
-pub(super) const QUEEN_MG: Eval = 900;
-pub(super) const KING_MG: Eval = 0;
+pub const PAWN_MG: Eval = 100;
+pub const KNIGHT_MG: Eval = 320;
+pub const BISHOP_MG: Eval = 330;
+pub const ROOK_MG: Eval = 500;
+pub const QUEEN_MG: Eval = 900;
+pub const KING_MG: Eval = 0;
 
 const PAWN_EG: Eval = 100;
 const KNIGHT_EG: Eval = 320;
@@ -222,24 +263,43 @@ const W_PIECE_SQUARE_TABLES: [WPSqTable; 6] = [
     KING_TABLE,
 ];
 
-#[rustfmt::skip]
-const FLIP: [usize; 64] = [
-    56, 57, 58, 59, 60, 61, 62, 63,
-    48, 49, 50, 51, 52, 53, 54, 55,
-    40, 41, 42, 43, 44, 45, 46, 47,
-    32, 33, 34, 35, 36, 37, 38, 39,
-    24, 25, 26, 27, 28, 29, 30, 31,
-    16, 17, 18, 19, 20, 21, 22, 23,
-     8,  9, 10, 11, 12, 13, 14, 15,
-     0,  1,  2,  3,  4,  5,  6,  7,
+/// Material value of each piece type
+pub const PIECE_VALUES: [W; 6] = [
+    W(PAWN_MG, PAWN_EG),
+    W(KNIGHT_MG, KNIGHT_EG),
+    W(BISHOP_MG, BISHOP_EG),
+    W(ROOK_MG, ROOK_EG),
+    W(QUEEN_MG, QUEEN_EG),
+    W(KING_MG, KING_EG),
 ];
 
+/// Piece square tables without the material value of the piece
+pub const fn positional_tables() -> [WPSqTable; 6] {
+    let mut result = [[W(0, 0); 64]; 6];
+    let mut piece = 0;
+    while piece < 6 {
+        let mut i = 0;
+        while i < 64 {
+            let w = W_PIECE_SQUARE_TABLES[piece][i];
+            let value = PIECE_VALUES[piece];
+            result[piece][i] = W(w.0 - value.0, w.1 - value.1);
+            i += 1;
+        }
+        piece += 1;
+    }
+    result
+}
+
+/// Non pawn material of each piece type, used to determine the game phase
+pub const NON_PAWN_MATERIAL: [Eval; 6] = [0, KNIGHT_MG, BISHOP_MG, ROOK_MG, QUEEN_MG, 0];
+
 #[inline(always)]
-pub(super) fn read_p_sq_table(piece: Piece, sq: u8) -> &'static W {
+/// Material and piece square table score of the piece on the square with the compiled in weights
+pub fn piece_square_value(piece: Piece, sq: Square) -> W {
     let index = match piece.color() {
-        Color::White => FLIP[sq as usize],
-        Color::Black => sq as usize,
+        Color::White => sq.square_value() as usize ^ 56,
+        Color::Black => sq.square_value() as usize,
     };
 
-    &W_PIECE_SQUARE_TABLES[piece.ptype()][index]
+    W_PIECE_SQUARE_TABLES[piece.ptype()][index]
 }
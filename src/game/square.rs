@@ -85,6 +85,14 @@ impl Square {
         }
     }
 
+    #[inline(always)]
+    /// Chebyshev distance (number of king moves) between two squares
+    pub fn distance(&self, other: Square) -> u8 {
+        self.rank()
+            .abs_diff(other.rank())
+            .max(self.file().abs_diff(other.file()))
+    }
+
     pub fn color(&self) -> Color {
         match (self.file() % 2, self.rank() % 2) {
             (0, 0) | (1, 1) => Color::Black,
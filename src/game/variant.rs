@@ -0,0 +1,180 @@
+use super::{board::Board, color::Color, piece_type::PieceType, GameResult};
+
+/// Number of checks a color has to give to win in Three-check
+pub const THREE_CHECK_LIMIT: u8 = 3;
+
+/// Squares the king has to reach in King of the Hill (d4, e4, d5 and e5)
+const HILL: u64 = 0x0000_0018_1800_0000;
+
+/// #### Chess variants
+/// The rules of the game played on the board. The variant is consulted by the board to track additional state,
+/// by the game result for additional ways to end the game and by the search, which only uses opening books and tablebases in standard chess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// The first color giving three checks wins, the checks are stored in the board state
+    ThreeCheck,
+    /// The first king reaching one of the four center squares wins
+    KingOfTheHill,
+    /// Captured pieces go into the pocket of the capturing color and can be dropped back on the board
+    Crazyhouse,
+    /// Captures are compulsory and the king is an ordinary piece, the first color losing all pieces or being stalemated wins
+    Antichess,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 5] = [
+        Variant::Standard,
+        Variant::ThreeCheck,
+        Variant::KingOfTheHill,
+        Variant::Crazyhouse,
+        Variant::Antichess,
+    ];
+
+    /// Checks if the checks given by each color are counted on the board
+    pub fn counts_checks(&self) -> bool {
+        matches!(self, Variant::ThreeCheck)
+    }
+
+    /// Checks if captured pieces are kept in the pockets and can be dropped
+    pub fn has_drops(&self) -> bool {
+        matches!(self, Variant::Crazyhouse)
+    }
+
+    /// Checks if each color has exactly one king which can be checked and castle.
+    /// Without a royal king moves don't have to get out of check and a color can have any number of kings
+    pub fn has_royal_king(&self) -> bool {
+        !matches!(self, Variant::Antichess)
+    }
+
+    /// Checks if a capture has to be played if one is possible
+    pub fn forced_captures(&self) -> bool {
+        matches!(self, Variant::Antichess)
+    }
+
+    /// Result of the game if the side to move has no legal moves
+    pub fn no_moves_result(&self, board: &Board, in_check: bool) -> GameResult {
+        match self {
+            Variant::Antichess => GameResult::VariantEnd(board.side_to_move().opposite()),
+            _ if in_check => GameResult::Mate(board.side_to_move()),
+            _ => GameResult::Stalemate,
+        }
+    }
+
+    /// Result of the game by the additional rules of the variant, checked before mate and draws.
+    /// Returns None if the rules of the variant don't end the game.
+    pub fn game_result(&self, board: &Board) -> Option<GameResult> {
+        match self {
+            Variant::Standard | Variant::Crazyhouse => None,
+            Variant::ThreeCheck => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| board.cur_state().checks[*color] >= THREE_CHECK_LIMIT)
+                .map(|color| GameResult::VariantEnd(color.opposite())),
+            Variant::KingOfTheHill => [Color::White, Color::Black]
+                .into_iter()
+                .find(|color| *board.get_bb_pieces()[*color][PieceType::King] & HILL != 0)
+                .map(|color| GameResult::VariantEnd(color.opposite())),
+            //only the side to move can have lost all pieces by the capture of the last move
+            Variant::Antichess => {
+                let color = board.side_to_move();
+                (*board.get_bb_occupied(color) == 0)
+                    .then_some(GameResult::VariantEnd(color.opposite()))
+            }
+        }
+    }
+}
+
+/// Names of the variants as used by UCI_Variant
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "chess"),
+            Variant::ThreeCheck => write!(f, "3check"),
+            Variant::KingOfTheHill => write!(f, "kingofthehill"),
+            Variant::Crazyhouse => write!(f, "crazyhouse"),
+            Variant::Antichess => write!(f, "antichess"),
+        }
+    }
+}
+
+impl std::str::FromStr for Variant {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Variant::ALL
+            .into_iter()
+            .find(|variant| variant.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_variant_results() {
+        use crate::game::{Board, Color, GameResult, Move, Variant};
+
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/3K4/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::KingOfTheHill);
+        let mov = Move::from_uci_notation("d2d3", &board).unwrap();
+        board.make_move(&mov, false, true).unwrap();
+        //the bare kings can still reach the hill
+        assert_eq!(
+            GameResult::get_game_result(&board, None),
+            GameResult::InProgress
+        );
+        board
+            .make_move(
+                &Move::from_uci_notation("e8e7", &board).unwrap(),
+                false,
+                true,
+            )
+            .unwrap();
+        board
+            .make_move(
+                &Move::from_uci_notation("d3d4", &board).unwrap(),
+                false,
+                true,
+            )
+            .unwrap();
+        assert_eq!(
+            GameResult::get_game_result(&board, None),
+            GameResult::VariantEnd(Color::Black)
+        );
+
+        //a knight can still give the checks, the bare kings can't
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1 +0+0").unwrap();
+        assert_eq!(
+            GameResult::get_game_result(&board, None),
+            GameResult::InProgress
+        );
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+0").unwrap();
+        assert_eq!(
+            GameResult::get_game_result(&board, None),
+            GameResult::InsufficientMaterial
+        );
+
+        //the checks are counted by the board and written as suffix of the fen
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1 +2+0";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.variant(), Variant::ThreeCheck);
+        assert_eq!(board.to_fen(), fen);
+        let key = board.cur_state().zobrist;
+        let mov = Move::from_uci_notation("a1a8", &board).unwrap();
+        board.make_move(&mov, false, true).unwrap();
+        assert_eq!(board.cur_state().checks, [3, 0]);
+        assert_eq!(
+            GameResult::get_game_result(&board, None),
+            GameResult::VariantEnd(Color::Black)
+        );
+        board.undo_move(&mov, false).unwrap();
+        assert_eq!(board.cur_state().zobrist, key);
+        assert_ne!(
+            key,
+            Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1")
+                .unwrap()
+                .cur_state()
+                .zobrist
+        );
+    }
+}
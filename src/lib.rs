@@ -17,6 +17,7 @@ use threadpool::ThreadPool;
 pub fn init() {
     dotenv().ok();
     game::board::move_gen::magic::lazy_static_attack_table_init();
+    bot::lazy_static_kpk_bitbase_init();
 
     #[cfg(not(feature = "log_to_file"))]
     {
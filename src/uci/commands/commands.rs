@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use super::{
+    command_game_over::{self, GameOverParams},
     command_go::{self, GoParams},
     command_position::{self, PositionParams},
     command_set_option::{self, SetOptionParams},
@@ -11,12 +12,13 @@ pub enum UCICommand {
     Quit,
     UCI,
     IsReady,
-    Go(GoParams),
+    Go(Box<GoParams>),
     Position(PositionParams),
     SetOption(SetOptionParams),
     Display,
     UCINewGame,
     Stop,
+    GameOver(GameOverParams),
 }
 
 impl UCICommand {
@@ -44,6 +46,7 @@ const COMMAND_STR_SET_OPTION: &str = "setoption";
 const COMMAND_STR_DISPLAY: &str = "d";
 const COMMAND_STR_UCI_NEW_GAME: &str = "ucinewgame";
 const COMMAND_STR_STOP: &str = "stop";
+const COMMAND_STR_GAME_OVER: &str = "gameover";
 
 impl std::str::FromStr for UCICommand {
     type Err = CommandParseError;
@@ -67,6 +70,7 @@ impl std::str::FromStr for UCICommand {
                 COMMAND_STR_GO => return command_go::parse_go(params),
                 COMMAND_STR_SET_OPTION => return command_set_option::parse_set_option(params),
                 COMMAND_STR_POSITION => return command_position::parse_position(params),
+                COMMAND_STR_GAME_OVER => return command_game_over::parse_game_over(params),
                 _ => (),
             }
             com = params;
@@ -0,0 +1,32 @@
+use crate::{
+    bot::Bot,
+    game::{Color, GameResult},
+    uci::commands::{CommandParseError, UCICommand},
+};
+
+#[derive(Debug)]
+pub struct GameOverParams {
+    /// Reported result of the game, if missing the result is taken from the current position
+    pub result: Option<GameResult>,
+}
+
+pub fn handle_game_over(bot: &mut Bot, params: GameOverParams) -> Option<String> {
+    bot.game_over(params.result);
+    None
+}
+
+/// Parses the result in PGN notation, a won game is treated as resignation of the loser
+pub fn parse_game_over(params: &str) -> Result<UCICommand, CommandParseError> {
+    let result = match params.trim() {
+        "" => None,
+        "1-0" => Some(GameResult::Resign(Color::Black)),
+        "0-1" => Some(GameResult::Resign(Color::White)),
+        "1/2-1/2" => Some(GameResult::Draw),
+        result => {
+            return Err(CommandParseError::ParseError(format!(
+                "Invalid game result {result}, expected 1-0, 0-1 or 1/2-1/2"
+            )))
+        }
+    };
+    Ok(UCICommand::GameOver(GameOverParams { result }))
+}
@@ -43,6 +43,7 @@ pub enum GoMode {
     Search(Limits),
     Perft(u8),
     Eval(bool),
+    EvalTrace,
 }
 
 #[derive(Debug, PartialEq)]
@@ -76,6 +77,7 @@ pub fn handle_go(bot: &mut Bot, params: GoParams) -> Option<String> {
         }
         GoMode::Perft(depth) => Some(bot.perft(depth)),
         GoMode::Eval(divide) => Some(bot.eval_board(divide)),
+        GoMode::EvalTrace => Some(bot.trace_eval()),
     };
 
     msg
@@ -89,9 +91,12 @@ pub fn parse_go(params: &str) -> Result<UCICommand, CommandParseError> {
     let mut parts = params.split_whitespace();
     while let Some(part) = parts.next() {
         if part == "eval" {
-            let divide = parts.next().is_some_and(|val| val == "divide");
-            let params = GoParams::new(GoMode::Eval(divide), None, None);
-            return Ok(UCICommand::Go(params));
+            let mode = match parts.next() {
+                Some("trace") => GoMode::EvalTrace,
+                value => GoMode::Eval(value.is_some_and(|val| val == "divide")),
+            };
+            let params = GoParams::new(mode, None, None);
+            return Ok(UCICommand::Go(Box::new(params)));
         }
 
         if part == "perft" {
@@ -101,13 +106,13 @@ pub fn parse_go(params: &str) -> Result<UCICommand, CommandParseError> {
                 .parse()
                 .map_err(|_| CommandParseError::ParseError("Invalid depth".into()))?;
             let params = GoParams::new(GoMode::Perft(depth), None, None);
-            return Ok(UCICommand::Go(params));
+            return Ok(UCICommand::Go(Box::new(params)));
         }
 
         if part == "infinite" || part.is_empty() {
             limits.add_limit(Limit::Depth(INFINITY_DEPTH));
             let params = GoParams::new(GoMode::Search(limits), None, None);
-            return Ok(UCICommand::Go(params));
+            return Ok(UCICommand::Go(Box::new(params)));
         }
 
         match part {
@@ -186,5 +191,5 @@ pub fn parse_go(params: &str) -> Result<UCICommand, CommandParseError> {
     }
 
     let params = GoParams::new(GoMode::Search(limits), Some(time_control), search_moves);
-    Ok(UCICommand::Go(params))
+    Ok(UCICommand::Go(Box::new(params)))
 }
@@ -1,24 +1,50 @@
 use crate::{
     bot::Bot,
-    game::{move_notation::Move, Board},
+    game::{board::fen_utility::FENUtility, move_notation::Move, Board},
     uci::commands::{CommandParseError, UCICommand},
 };
 
+/// The fen of the start position and the moves played from it. The position is set up by the main thread
+/// as it depends on the variant of the bot
+#[derive(Debug)]
 pub struct PositionParams {
-    pub board: Board,
+    pub fen: String,
+    pub moves: Vec<String>,
 }
 
-impl std::fmt::Debug for PositionParams {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("PositionParams")
-            .field("board", &self.board.to_fen())
-            .finish()
+pub fn handle_position(bot: &mut Bot, params: PositionParams) -> Option<String> {
+    let mut board = match Board::from_fen_strict(&params.fen, bot.variant()) {
+        Ok(board) => board,
+        Err(err) => {
+            return Some(format!(
+                "info string Invalid position {}: {}",
+                params.fen, err
+            ))
+        }
+    };
+    bot.apply_rules(&mut board);
+
+    for mv_str in &params.moves {
+        let mov = match Move::from_uci_notation(mv_str, &board) {
+            Ok(mov) => mov,
+            Err(err) => {
+                return Some(format!(
+                    "info string Move {} is invalid for the position {}: {}",
+                    mv_str,
+                    board.to_fen(),
+                    err
+                ))
+            }
+        };
+
+        //the move is validated while parsing
+        board
+            .make_move(&mov, false, false)
+            .expect("Parsed move must be legal");
     }
-}
 
-pub fn handle_position(bot: &mut Bot, params: PositionParams) -> Option<String> {
-    info!("Set position to: {}", params.board.to_fen());
-    bot.set_board(params.board);
+    info!("Set position to: {}", board.to_fen());
+    bot.set_board(board);
     None
 }
 
@@ -27,73 +53,16 @@ pub fn parse_position(params: &str) -> Result<UCICommand, CommandParseError> {
         .split_once(" ")
         .or_else(|| Some((params, "")))
         .unwrap();
-    let board = match literal {
-        "startpos" => {
-            let split = rest.split_once("moves");
-
-            match split {
-                Some((_, move_list)) => {
-                    let mut board = Board::default();
-                    for mv_str in move_list.trim().split_whitespace() {
-                        let mov = Move::from_uci_notation(mv_str, &board).map_err(|_| {
-                            CommandParseError::ParseError(format!(
-                                "Move {} is not valid uci notation",
-                                mv_str
-                            ))
-                        })?;
+    let (position, move_list) = rest.split_once("moves").unwrap_or((rest, ""));
 
-                        board.make_move(&mov, false, true).map_err(|_| {
-                            CommandParseError::ParseError(format!(
-                                "Move {:?} is invalid for the position {}",
-                                mov,
-                                board.to_fen()
-                            ))
-                        })?;
-                    }
-
-                    board
-                }
-                None => Board::default(),
-            }
-        }
-        "fen" => {
-            let split = rest.split_once("moves");
-
-            match split {
-                Some((fen, move_list)) => {
-                    let mut board = Board::from_fen(fen)
-                        .map_err(|err| CommandParseError::ParseError(err.to_string()))?;
-
-                    let mut moves = vec![];
-                    for mv_str in move_list.trim().split_whitespace() {
-                        let mov = Move::from_uci_notation(mv_str, &board).map_err(|_| {
-                            CommandParseError::ParseError(format!(
-                                "Move {} is not valid uci notation",
-                                mv_str
-                            ))
-                        })?;
-
-                        board.make_move(&mov, false, true).map_err(|_| {
-                            CommandParseError::ParseError(format!(
-                                "Move {:?} is invalid for the position {}",
-                                mov,
-                                board.to_fen()
-                            ))
-                        })?;
-                        moves.push(mov);
-                    }
-
-                    board
-                }
-                //rest is the fen
-                None => Board::from_fen(rest)
-                    .map_err(|err| CommandParseError::ParseError(err.to_string()))?,
-            }
-        }
+    let fen = match literal {
+        "startpos" => FENUtility::START_FEN.to_string(),
+        "fen" => position.trim().to_string(),
         _ => Err(CommandParseError::ParseError(
             format!("Invalid literal : {}", literal).into(),
         ))?,
     };
+    let moves = move_list.split_whitespace().map(String::from).collect();
 
-    Ok(UCICommand::Position(PositionParams { board }))
+    Ok(UCICommand::Position(PositionParams { fen, moves }))
 }
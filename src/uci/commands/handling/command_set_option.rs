@@ -2,10 +2,11 @@ use crate::{
     bot::{
         search::{
             transposition_table::{DEFAULT_HASH_SIZE, MAX_HASH_SIZE},
-            DEFAULT_OPENING_BOOK_ENABLED,
+            BookMode, DEFAULT_BOOK_DEPTH, DEFAULT_OPENING_BOOK_ENABLED,
         },
-        ActionMessage, Bot,
+        ActionMessage, Bot, DEFAULT_EVALUATOR, REGISTERED_EVALUATORS,
     },
+    game::Variant,
     uci::commands::{CommandParseError, UCICommand},
 };
 
@@ -21,6 +22,17 @@ pub enum OptionType {
     Threads(u8),
     DebugFile(String),
     OwnBook(bool),
+    BookFile(String),
+    BookMode(BookMode),
+    BookDepth(usize),
+    BookMinWeight(u32),
+    Evaluator(String),
+    EvalFile(String),
+    EvalParams(String),
+    SyzygyPath(String),
+    DtmPath(String),
+    Chess960(bool),
+    Variant(Variant),
 }
 
 impl OptionType {
@@ -39,6 +51,60 @@ impl OptionType {
                 std::env::var("LOG_FILE").unwrap_or("logs.log".to_string())
             ),
             OptionType::OwnBook(val) => format!("OwnBook type check default {}", val),
+            OptionType::BookFile(val) => format!(
+                "BookFile type string default {}",
+                if val.is_empty() { "<empty>" } else { val }
+            ),
+            OptionType::BookMode(val) => format!(
+                "BookMode type combo default {} {}",
+                val,
+                BookMode::ALL
+                    .iter()
+                    .map(|mode| format!("var {}", mode))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            OptionType::BookDepth(val) => {
+                format!("BookDepth type spin default {} min 0 max 1024", val)
+            }
+            OptionType::BookMinWeight(val) => {
+                format!("BookMinWeight type spin default {} min 1 max 65535", val)
+            }
+            OptionType::Evaluator(val) => format!(
+                "Evaluator type combo default {} {}",
+                val,
+                REGISTERED_EVALUATORS
+                    .iter()
+                    .map(|name| format!("var {}", name))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            OptionType::EvalFile(val) => format!(
+                "EvalFile type string default {}",
+                if val.is_empty() { "<empty>" } else { val }
+            ),
+            OptionType::EvalParams(val) => format!(
+                "EvalParams type string default {}",
+                if val.is_empty() { "<empty>" } else { val }
+            ),
+            OptionType::SyzygyPath(val) => format!(
+                "SyzygyPath type string default {}",
+                if val.is_empty() { "<empty>" } else { val }
+            ),
+            OptionType::DtmPath(val) => format!(
+                "DtmPath type string default {}",
+                if val.is_empty() { "<empty>" } else { val }
+            ),
+            OptionType::Chess960(val) => format!("UCI_Chess960 type check default {}", val),
+            OptionType::Variant(val) => format!(
+                "UCI_Variant type combo default {} {}",
+                val,
+                Variant::ALL
+                    .iter()
+                    .map(|variant| format!("var {}", variant))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
         }
     }
 
@@ -49,13 +115,51 @@ impl OptionType {
             OptionType::Threads(1).get_option_description(),
             OptionType::DebugFile("".into()).get_option_description(),
             OptionType::OwnBook(*DEFAULT_OPENING_BOOK_ENABLED).get_option_description(),
+            OptionType::BookFile(std::env::var("OPENING_BOOK").unwrap_or_default())
+                .get_option_description(),
+            OptionType::BookMode(BookMode::default()).get_option_description(),
+            OptionType::BookDepth(DEFAULT_BOOK_DEPTH).get_option_description(),
+            OptionType::BookMinWeight(1).get_option_description(),
+            OptionType::Evaluator(DEFAULT_EVALUATOR.into()).get_option_description(),
+            OptionType::EvalFile(std::env::var("EVAL_FILE").unwrap_or_default())
+                .get_option_description(),
+            OptionType::EvalParams(std::env::var("EVAL_PARAMS").unwrap_or_default())
+                .get_option_description(),
+            OptionType::SyzygyPath(std::env::var("SYZYGY_PATH").unwrap_or_default())
+                .get_option_description(),
+            OptionType::DtmPath(std::env::var("DTM_PATH").unwrap_or_default())
+                .get_option_description(),
+            OptionType::Chess960(false).get_option_description(),
+            OptionType::Variant(Variant::default()).get_option_description(),
         ]
     }
 }
 
 pub fn handle_set_option(bot: &mut Bot, params: SetOptionParams) -> Option<String> {
-    bot.send_message(ActionMessage::SetOption(params.option));
-    None
+    match params.option {
+        //the parameters are also needed by the main thread for the static evaluation
+        OptionType::EvalParams(path) => match bot.load_eval_params(&path) {
+            Ok(()) => None,
+            Err(err) => Some(format!(
+                "info string Failed to load evaluation parameters {}, using the defaults: {}",
+                path, err
+            )),
+        },
+        //castling notation is handled by the board of the bot
+        OptionType::Chess960(value) => {
+            bot.set_chess960(value);
+            None
+        }
+        //the variant is applied to the board of the bot before the moves of a position are played
+        OptionType::Variant(variant) => {
+            bot.set_variant(variant);
+            None
+        }
+        option => {
+            bot.send_message(ActionMessage::SetOption(option));
+            None
+        }
+    }
 }
 
 pub fn parse_set_option(params: &str) -> Result<UCICommand, CommandParseError> {
@@ -92,6 +196,75 @@ pub fn parse_set_option(params: &str) -> Result<UCICommand, CommandParseError> {
                 .map_err(|_| CommandParseError::ParseError("Invalid value for OwnBook".into()))?;
             OptionType::OwnBook(value)
         }
+        "BookFile" => OptionType::BookFile(match value {
+            "<empty>" => String::new(),
+            path => path.into(),
+        }),
+        "BookMode" => {
+            let mode = value.parse::<BookMode>().map_err(|_| {
+                CommandParseError::ParseError(format!(
+                    "Unknown book mode {}, available: {}",
+                    value,
+                    BookMode::ALL.map(|mode| mode.to_string()).join(", ")
+                ))
+            })?;
+            OptionType::BookMode(mode)
+        }
+        "BookDepth" => {
+            let value = value
+                .parse::<usize>()
+                .map_err(|_| CommandParseError::ParseError("Invalid value for BookDepth".into()))?;
+            OptionType::BookDepth(value)
+        }
+        "BookMinWeight" => {
+            let value = value.parse::<u32>().map_err(|_| {
+                CommandParseError::ParseError("Invalid value for BookMinWeight".into())
+            })?;
+            OptionType::BookMinWeight(value)
+        }
+        "Evaluator" => {
+            let name = REGISTERED_EVALUATORS
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(value))
+                .ok_or(CommandParseError::ParseError(format!(
+                    "Unknown evaluator {}, available: {}",
+                    value,
+                    REGISTERED_EVALUATORS.join(", ")
+                )))?;
+            OptionType::Evaluator(name.to_string())
+        }
+        "EvalFile" => OptionType::EvalFile(match value {
+            "<empty>" => String::new(),
+            path => path.into(),
+        }),
+        "EvalParams" => OptionType::EvalParams(match value {
+            "<empty>" => String::new(),
+            path => path.into(),
+        }),
+        "SyzygyPath" => OptionType::SyzygyPath(match value {
+            "<empty>" => String::new(),
+            path => path.into(),
+        }),
+        "DtmPath" => OptionType::DtmPath(match value {
+            "<empty>" => String::new(),
+            path => path.into(),
+        }),
+        "UCI_Chess960" => {
+            let value = value.parse::<bool>().map_err(|_| {
+                CommandParseError::ParseError("Invalid value for UCI_Chess960".into())
+            })?;
+            OptionType::Chess960(value)
+        }
+        "UCI_Variant" => {
+            let variant = value.parse::<Variant>().map_err(|_| {
+                CommandParseError::ParseError(format!(
+                    "Unknown variant {}, available: {}",
+                    value,
+                    Variant::ALL.map(|variant| variant.to_string()).join(", ")
+                ))
+            })?;
+            OptionType::Variant(variant)
+        }
         _ => {
             return Err(CommandParseError::ParseError(
                 format!("Unknown option : {}", name).into(),
@@ -1,3 +1,4 @@
+pub mod command_game_over;
 pub mod command_go;
 pub mod command_position;
 pub mod command_set_option;
@@ -14,6 +15,7 @@ pub fn handle_uci_command(command: UCICommand, bot: &mut Bot) -> Option<String>
 
     match command {
         UCICommand::UCINewGame => {
+            bot.game_over(None);
             bot.send_message(ActionMessage::SetOption(OptionType::ClearHash));
             None
         }
@@ -23,10 +25,11 @@ pub fn handle_uci_command(command: UCICommand, bot: &mut Bot) -> Option<String>
         UCICommand::SetOption(params) => command_set_option::handle_set_option(bot, params),
         UCICommand::Position(params) => command_position::handle_position(bot, params),
         UCICommand::Display => Some(bot.get_board().to_string()),
-        UCICommand::Go(params) => command_go::handle_go(bot, params),
+        UCICommand::Go(params) => command_go::handle_go(bot, *params),
         UCICommand::Stop => {
             bot.stop();
             None
         }
+        UCICommand::GameOver(params) => command_game_over::handle_game_over(bot, params),
     }
 }
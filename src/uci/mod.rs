@@ -19,6 +19,7 @@ pub fn run_uci_protocol() {
             Ok(command) => {
                 if command.is_quit() {
                     info!("Quitting UCI Protocol");
+                    bot.quit();
                     break;
                 }
 
@@ -37,7 +38,7 @@ pub fn run_uci_protocol() {
         match bot.poll_reaction() {
             Ok(msg) => match msg {
                 ReactionMessage::BestMove(m) => {
-                    send_message(&format!("bestmove {}", m));
+                    send_message(&format!("bestmove {}", m.to_uci(bot.get_board())));
                 }
                 ReactionMessage::Info(i) => {
                     send_message(&format!("info {}", i));